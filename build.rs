@@ -0,0 +1,5 @@
+fn main() {
+    // Node.js addons resolve the N-API symbols from the host process at load time
+    #[cfg(feature = "napi")]
+    napi_build::setup();
+}
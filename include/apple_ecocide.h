@@ -0,0 +1,52 @@
+/*
+ * C interface to the apple-ecocide Little Snitch rules generator.
+ *
+ * Build the library with `cargo build --release --no-default-features --features ffi`
+ * and link against target/release/libapple_ecocide.dylib (.so on Linux).
+ *
+ * All strings are NUL-terminated UTF-8. Strings returned by apple_ecocide_generate
+ * and apple_ecocide_list_categories belong to the caller and must be released with
+ * apple_ecocide_free. Functions returning NULL (or -1) on failure record a message
+ * that apple_ecocide_last_error returns.
+ */
+
+#ifndef APPLE_ECOCIDE_H
+#define APPLE_ECOCIDE_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Generate a .lsrules file from JSON parameters with the kebab-case GenerateParams
+ * keys, e.g. {"mode": "block", "include": ["apple-*"], "ls-version": "5"}.
+ * Returns the rules JSON, or NULL on error.
+ */
+char *apple_ecocide_generate(const char *params_json);
+
+/* List the available categories as a JSON array, or NULL on error. */
+char *apple_ecocide_list_categories(void);
+
+/*
+ * Load categories from a directory of .toml files instead of the embedded ones.
+ * Returns 0 on success and -1 on error. Required first in a no-embed build.
+ */
+int apple_ecocide_load_categories(const char *path);
+
+/*
+ * The error of the last failed call on this thread, or NULL. Owned by the
+ * library and valid until the next call on the same thread.
+ */
+const char *apple_ecocide_last_error(void);
+
+/* The library version, e.g. "0.1.0". Static; do not free. */
+const char *apple_ecocide_version(void);
+
+/* Release a string returned by this library. NULL is ignored. */
+void apple_ecocide_free(char *value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* APPLE_ECOCIDE_H */
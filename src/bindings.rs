@@ -0,0 +1,31 @@
+//! Category storage shared by the C, Swift and Node.js bindings.
+
+use crate::{Category, Error};
+use std::sync::Mutex;
+
+/// Categories loaded through one of the bindings, replacing the embedded ones
+static CATEGORIES: Mutex<Option<Vec<(String, Category)>>> = Mutex::new(None);
+
+/// Replace the embedded categories for every later call
+pub fn set_categories(categories: Vec<(String, Category)>) {
+    *CATEGORIES.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(categories);
+}
+
+/// Run `f` with the loaded categories, or the embedded ones when none were loaded
+pub fn with_categories<T>(f: impl FnOnce(&[(String, Category)]) -> Result<T, Error>) -> Result<T, Error> {
+    let loaded = CATEGORIES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match loaded.as_deref() {
+        Some(categories) => f(categories),
+        None => f(embedded()?),
+    }
+}
+
+#[cfg(not(feature = "no-embed"))]
+fn embedded() -> Result<&'static [(String, Category)], Error> {
+    crate::embedded_categories()
+}
+
+#[cfg(feature = "no-embed")]
+fn embedded() -> Result<&'static [(String, Category)], Error> {
+    Err(Error::NoCategories)
+}
@@ -0,0 +1,68 @@
+//! Machine-readable history of category data changes between releases.
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+const CHANGELOG: &str = include_str!("../data/changelog.toml");
+
+/// Category data changes shipped in one crate release
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Release {
+    pub version: String,
+    pub date: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_categories: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_categories: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<CategoryChange>,
+}
+
+/// Domains and processes added to or removed from an existing category
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CategoryChange {
+    pub slug: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_domains: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_domains: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_processes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_processes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ChangelogFile {
+    releases: Vec<Release>,
+}
+
+/// Load the embedded changelog, newest release first
+pub fn load_changelog() -> Result<Vec<Release>> {
+    parse_changelog(CHANGELOG)
+}
+
+/// Parse a changelog in the format of the embedded one, such as a newer published copy
+pub fn parse_changelog(content: &str) -> Result<Vec<Release>> {
+    let file: ChangelogFile = toml::from_str(content).map_err(|e| Error::parse("changelog.toml", content, e))?;
+    Ok(file.releases)
+}
+
+/// Releases newer than `since`, newest first
+pub fn releases_since<'a>(releases: &'a [Release], since: &str) -> Result<Vec<&'a Release>> {
+    let since = parse_version(since).ok_or_else(|| Error::InvalidVersion(since.to_string()))?;
+    Ok(releases
+        .iter()
+        .filter(|r| parse_version(&r.version).is_some_and(|v| v > since))
+        .collect())
+}
+
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = version.trim().trim_start_matches('v').split('.');
+    let mut next = || parts.next().map_or(Some(0), |p| p.parse().ok());
+    Some((next()?, next()?, next()?))
+}
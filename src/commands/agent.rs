@@ -0,0 +1,219 @@
+//! `apple-ecocide agent`: a launchd LaunchAgent that regenerates rules on a schedule.
+
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const LABEL: &str = "com.github.bobstanton.apple-ecocide";
+
+/// How often the agent regenerates rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interval {
+    Hourly,
+    Daily,
+    Weekly,
+}
+
+impl Interval {
+    fn seconds(self) -> u64 {
+        match self {
+            Interval::Hourly => 60 * 60,
+            Interval::Daily => 24 * 60 * 60,
+            Interval::Weekly => 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Arguments owned by `agent install` itself, which are not passed on to the agent
+const AGENT_FLAGS: &[&str] = &["--interval", "--then"];
+
+/// The current command line with the `agent install` part removed
+///
+/// Everything else, such as the category selection and `--output`, is what the
+/// agent runs on each regeneration.
+pub fn generation_args() -> Vec<OsString> {
+    let mut args = Vec::new();
+    let mut raw = std::env::args_os().skip(1);
+    let mut in_agent = false;
+
+    while let Some(arg) = raw.next() {
+        let text = arg.to_string_lossy();
+        if !in_agent && text == "agent" {
+            in_agent = true;
+            // Skip the `install` subcommand name
+            raw.next();
+            continue;
+        }
+        if in_agent {
+            if AGENT_FLAGS.contains(&text.as_ref()) {
+                raw.next();
+                continue;
+            }
+            if AGENT_FLAGS.iter().any(|flag| text.starts_with(&format!("{}=", flag))) {
+                continue;
+            }
+        }
+        args.push(arg);
+    }
+    args
+}
+
+/// Install (or replace) the LaunchAgent and load it
+pub fn install(args: &[OsString], interval: Interval, then: Option<&str>) -> Result<PathBuf> {
+    ensure_macos()?;
+
+    let exe = std::env::current_exe().context("Failed to locate the apple-ecocide binary")?;
+    let mut command = vec![exe.into_os_string()];
+    command.extend(args.iter().cloned());
+
+    let program_arguments = match then {
+        Some(then) => vec![
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            format!("{} && {}", shell_join(&command), then),
+        ],
+        None => command.iter().map(|a| a.to_string_lossy().into_owned()).collect(),
+    };
+
+    let plist = render_plist(
+        &program_arguments,
+        &std::env::current_dir()?.display().to_string(),
+        interval,
+        &log_path()?.display().to_string(),
+    );
+
+    let path = plist_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Replacing a loaded agent requires unloading it first
+    if path.exists() {
+        let _ = launchctl(&["bootout", &domain_target()?, &path.display().to_string()]);
+    }
+    fs::write(&path, plist).with_context(|| format!("Failed to write: {}", path.display()))?;
+
+    if !launchctl(&["bootstrap", &domain_target()?, &path.display().to_string()])? {
+        anyhow::bail!("launchctl failed to load {}", path.display());
+    }
+    Ok(path)
+}
+
+/// Unload and remove the LaunchAgent, returning whether one was installed
+pub fn uninstall() -> Result<bool> {
+    ensure_macos()?;
+
+    let path = plist_path()?;
+    if !path.exists() {
+        return Ok(false);
+    }
+    let _ = launchctl(&["bootout", &domain_target()?, &path.display().to_string()]);
+    fs::remove_file(&path).with_context(|| format!("Failed to remove: {}", path.display()))?;
+    Ok(true)
+}
+
+/// Print whether the LaunchAgent is installed and loaded
+pub fn status() -> Result<()> {
+    ensure_macos()?;
+
+    let path = plist_path()?;
+    if !path.exists() {
+        println!("  [warn] Agent not installed ({})", path.display());
+        return Ok(());
+    }
+    println!("  [ok]   Agent installed at {}", path.display());
+
+    let service = format!("{}/{}", domain_target()?, LABEL);
+    if launchctl(&["print", &service])? {
+        println!("  [ok]   Agent loaded as {}", service);
+    } else {
+        println!("  [fail] Agent is not loaded; run 'apple-ecocide agent install' again");
+    }
+    println!("         Log: {}", log_path()?.display());
+    Ok(())
+}
+
+fn ensure_macos() -> Result<()> {
+    if !cfg!(target_os = "macos") {
+        anyhow::bail!("launchd agents are only available on macOS");
+    }
+    Ok(())
+}
+
+fn home() -> Result<PathBuf> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .context("HOME is not set")
+}
+
+fn plist_path() -> Result<PathBuf> {
+    Ok(home()?.join("Library/LaunchAgents").join(format!("{}.plist", LABEL)))
+}
+
+fn log_path() -> Result<PathBuf> {
+    Ok(home()?.join("Library/Logs/apple-ecocide.log"))
+}
+
+/// The `gui/<uid>` launchd domain of the current user
+fn domain_target() -> Result<String> {
+    let output = Command::new("id").arg("-u").output().context("Failed to run 'id -u'")?;
+    let uid = String::from_utf8(output.stdout)?.trim().to_string();
+    Ok(format!("gui/{}", uid))
+}
+
+fn launchctl(args: &[&str]) -> Result<bool> {
+    let output = Command::new("launchctl")
+        .args(args)
+        .output()
+        .context("Failed to run launchctl")?;
+    Ok(output.status.success())
+}
+
+fn render_plist(program_arguments: &[String], working_dir: &str, interval: Interval, log: &str) -> String {
+    let arguments: String = program_arguments
+        .iter()
+        .map(|a| format!("        <string>{}</string>\n", xml_escape(a)))
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>WorkingDirectory</key>
+    <string>{working_dir}</string>
+    <key>StartInterval</key>
+    <integer>{interval}</integer>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = LABEL,
+        arguments = arguments,
+        working_dir = xml_escape(working_dir),
+        interval = interval.seconds(),
+        log = xml_escape(log),
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn shell_join(args: &[OsString]) -> String {
+    args.iter()
+        .map(|a| format!("'{}'", a.to_string_lossy().replace('\'', r"'\''")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
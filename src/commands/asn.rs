@@ -0,0 +1,48 @@
+//! Address prefixes announced by the autonomous systems of categories (`--expand-asn`).
+
+use anyhow::{Context, Result};
+use apple_ecocide::Category;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+const RIPESTAT_URL: &str = "https://stat.ripe.net/data/announced-prefixes/data.json";
+
+#[derive(Deserialize)]
+struct RipeStatResponse {
+    data: AnnouncedPrefixes,
+}
+
+#[derive(Deserialize)]
+struct AnnouncedPrefixes {
+    prefixes: Vec<AnnouncedPrefix>,
+}
+
+#[derive(Deserialize)]
+struct AnnouncedPrefix {
+    prefix: String,
+}
+
+/// Look up the prefixes currently announced by every ASN listed in the selected categories
+pub fn fetch(selected: &[&(String, Category)]) -> Result<BTreeMap<u32, Vec<String>>> {
+    let asns: BTreeSet<u32> = selected.iter().flat_map(|(_, category)| &category.asns).copied().collect();
+
+    let mut prefixes = BTreeMap::new();
+    for asn in asns {
+        let found = query_ripestat(asn)?;
+        eprintln!("AS{} announces {} prefixes", asn, found.len());
+        prefixes.insert(asn, found);
+    }
+    Ok(prefixes)
+}
+
+fn query_ripestat(asn: u32) -> Result<Vec<String>> {
+    let response = ureq::get(RIPESTAT_URL)
+        .query("resource", &format!("AS{}", asn))
+        .call()
+        .with_context(|| format!("Failed to query RIPEstat for AS{}", asn))?;
+    let body: RipeStatResponse = serde_json::from_reader(response.into_reader())
+        .with_context(|| format!("Invalid response from RIPEstat for AS{}", asn))?;
+
+    let prefixes: BTreeSet<String> = body.data.prefixes.into_iter().map(|p| p.prefix).collect();
+    Ok(prefixes.into_iter().collect())
+}
@@ -0,0 +1,60 @@
+//! `apple-ecocide changelog`: shows category data changes between releases.
+
+use anyhow::Result;
+use apple_ecocide::{load_changelog, releases_since, Release};
+
+/// Print releases newer than `since` (or every release), as text or JSON
+pub fn run(since: Option<&str>, json: bool) -> Result<()> {
+    let releases = load_changelog()?;
+    let releases: Vec<&Release> = match since {
+        Some(since) => releases_since(&releases, since)?,
+        None => releases.iter().collect(),
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&releases)?);
+        return Ok(());
+    }
+
+    if releases.is_empty() {
+        println!("No category changes since {}", since.unwrap_or_default());
+        return Ok(());
+    }
+
+    for release in releases {
+        print_release(release);
+    }
+    Ok(())
+}
+
+pub fn print_release(release: &Release) {
+    if release.summary.is_empty() {
+        println!("{} ({})", release.version, release.date);
+    } else {
+        println!("{} ({}): {}", release.version, release.date, release.summary);
+    }
+
+    if !release.added_categories.is_empty() {
+        println!("  Added categories: {}", release.added_categories.join(", "));
+    }
+    if !release.removed_categories.is_empty() {
+        println!("  Removed categories: {}", release.removed_categories.join(", "));
+    }
+
+    for change in &release.categories {
+        println!("  {}", change.slug);
+        for domain in &change.added_domains {
+            println!("    + {}", domain);
+        }
+        for domain in &change.removed_domains {
+            println!("    - {}", domain);
+        }
+        for process in &change.added_processes {
+            println!("    + process {}", process);
+        }
+        for process in &change.removed_processes {
+            println!("    - process {}", process);
+        }
+    }
+    println!();
+}
@@ -0,0 +1,167 @@
+//! `apple-ecocide check`: maintainer checks against the selected categories.
+
+use super::processes;
+use apple_ecocide::Category;
+use std::collections::BTreeMap;
+use std::io;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Run the requested checks, returning whether all of them passed
+///
+/// With neither `resolve` nor `processes` set, every check runs.
+pub fn run(selected: &[&(String, Category)], resolve: bool, check_processes: bool, concurrency: usize) -> bool {
+    let run_all = !resolve && !check_processes;
+    let mut ok = true;
+
+    if resolve || run_all {
+        ok &= check_dns(selected, concurrency);
+    }
+    if check_processes || run_all {
+        ok &= processes::run(selected.iter().copied());
+    }
+
+    ok
+}
+
+fn check_dns(selected: &[&(String, Category)], concurrency: usize) -> bool {
+    // Resolve each distinct domain once, remembering every category listing it
+    let mut domains: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (slug, category) in selected.iter().copied() {
+        for domain in category.rules.iter().flat_map(|r| &r.domains) {
+            domains.entry(domain.as_str()).or_default().push(slug.as_str());
+        }
+    }
+
+    let domain_list: Vec<&str> = domains.keys().copied().collect();
+    let lookups = resolve_addresses(&domain_list, concurrency.max(1));
+    let dead: Vec<&str> = lookups
+        .iter()
+        .filter(|(_, lookup)| matches!(lookup, Lookup::NotFound))
+        .map(|(domain, _)| *domain)
+        .collect();
+    let failed: Vec<(&str, &str)> = lookups
+        .iter()
+        .filter_map(|(domain, lookup)| match lookup {
+            Lookup::Failed(error) => Some((*domain, error.as_str())),
+            _ => None,
+        })
+        .collect();
+
+    // Failed lookups say nothing about the domain, so they are reported but do not fail the check
+    if !failed.is_empty() {
+        println!(
+            "[warn] {} of {} domains could not be looked up (is the network up?):",
+            failed.len(),
+            domain_list.len()
+        );
+        for (domain, error) in &failed {
+            println!("         {}: {}", domain, error);
+        }
+    }
+
+    if dead.is_empty() {
+        if failed.is_empty() {
+            println!("[ok]   All {} domains resolve", domain_list.len());
+        } else {
+            println!("[ok]   The other {} domains resolve", domain_list.len() - failed.len());
+        }
+        return true;
+    }
+
+    let mut by_category: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for domain in &dead {
+        for slug in &domains[domain] {
+            by_category.entry(slug).or_default().push(domain);
+        }
+    }
+
+    println!(
+        "[warn] {} of {} domains do not resolve:",
+        dead.len(),
+        domain_list.len()
+    );
+    for (slug, domains) in by_category {
+        println!("         {}", slug);
+        for domain in domains {
+            println!("           {}", domain);
+        }
+    }
+    false
+}
+
+/// Outcome of looking up a domain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lookup {
+    Resolved(Vec<IpAddr>),
+    /// The domain does not exist or has no addresses
+    NotFound,
+    /// The lookup itself failed, e.g. on a timeout or without network
+    Failed(String),
+}
+
+impl Lookup {
+    fn new(result: io::Result<Vec<IpAddr>>) -> Lookup {
+        match result {
+            Ok(addresses) if addresses.is_empty() => Lookup::NotFound,
+            Ok(addresses) => Lookup::Resolved(addresses),
+            Err(error) if is_not_found(&error) => Lookup::NotFound,
+            Err(error) => Lookup::Failed(error.to_string()),
+        }
+    }
+
+    /// The resolved addresses; none unless resolved
+    pub fn addresses(&self) -> &[IpAddr] {
+        match self {
+            Lookup::Resolved(addresses) => addresses,
+            Lookup::NotFound | Lookup::Failed(_) => &[],
+        }
+    }
+}
+
+/// Whether a lookup error means the domain has no addresses, rather than that the lookup failed
+///
+/// std only passes on the resolver's message: `EAI_NONAME` ("Name or service not known" on
+/// glibc, "nodename nor servname provided, or not known" on macOS) or glibc's `EAI_NODATA`.
+fn is_not_found(error: &io::Error) -> bool {
+    let message = error.to_string();
+    message.contains("not known") || message.contains("No address associated")
+}
+
+/// Look up the addresses of every domain with at most `concurrency` lookups in flight
+pub fn resolve_addresses<'a>(domains: &[&'a str], concurrency: usize) -> BTreeMap<&'a str, Lookup> {
+    let next = AtomicUsize::new(0);
+    let resolved = Mutex::new(BTreeMap::new());
+
+    thread::scope(|scope| {
+        for _ in 0..concurrency.max(1).min(domains.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&domain) = domains.get(i) else {
+                    break;
+                };
+                let lookup = Lookup::new((domain, 443).to_socket_addrs().map(|addrs| addrs.map(|addr| addr.ip()).collect()));
+                resolved.lock().unwrap().insert(domain, lookup);
+            });
+        }
+    });
+
+    resolved.into_inner().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_missing_domains_count_as_not_found() {
+        let error = |message: &str| Err(io::Error::other(format!("failed to lookup address information: {}", message)));
+        assert_eq!(Lookup::new(error("Name or service not known")), Lookup::NotFound);
+        assert_eq!(Lookup::new(error("nodename nor servname provided, or not known")), Lookup::NotFound);
+        assert_eq!(Lookup::new(Ok(Vec::new())), Lookup::NotFound);
+        assert!(matches!(Lookup::new(error("Temporary failure in name resolution")), Lookup::Failed(_)));
+        assert!(matches!(Lookup::new(error("Non-recoverable failure in name resolution")), Lookup::Failed(_)));
+    }
+}
@@ -0,0 +1,178 @@
+//! Rule-count and size limits for generated rule groups (`--max-rules`, `--max-bytes`).
+
+use anyhow::Result;
+use apple_ecocide::{LsRule, LsRulesOutput};
+use std::path::{Path, PathBuf};
+
+/// Thresholds above which Little Snitch gets sluggish with a single rule group
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_rules: Option<usize>,
+    pub max_bytes: Option<usize>,
+    /// Whether files are written as minified JSON, which `max_bytes` is measured against
+    pub compact: bool,
+}
+
+impl Limits {
+    /// Describe how `output` exceeds the limits, or `None` if it fits
+    pub fn exceeded(&self, output: &LsRulesOutput, bytes: usize) -> Option<String> {
+        let mut reasons = Vec::new();
+        if let Some(max) = self.max_rules.filter(|max| output.rules.len() > *max) {
+            reasons.push(format!("{} rules (limit {})", output.rules.len(), max));
+        }
+        if let Some(max) = self.max_bytes.filter(|max| bytes > *max) {
+            reasons.push(format!("{} bytes (limit {})", bytes, max));
+        }
+        (!reasons.is_empty()).then(|| reasons.join(" and "))
+    }
+}
+
+/// Split `output` into parts that each fit within `limits`
+///
+/// Rules are kept whole and in order, so a single rule larger than the byte
+/// limit ends up alone in its own part. `finish` is applied to every part once
+/// it is named " (part N of M)", e.g. to stamp a checksum; the byte limit
+/// accounts for both.
+pub fn split(
+    output: LsRulesOutput,
+    limits: Limits,
+    finish: impl Fn(&mut LsRulesOutput) -> Result<()>,
+) -> Result<Vec<LsRulesOutput>> {
+    let LsRulesOutput {
+        name,
+        description,
+        rules,
+    } = output;
+
+    // Size of a part without rules, with the longest name a part can get
+    let mut widest = LsRulesOutput {
+        name: format!("{} (part {} of {})", name, rules.len(), rules.len()),
+        description: description.clone(),
+        rules: Vec::new(),
+    };
+    finish(&mut widest)?;
+    let base = crate::rules_json(&widest, limits.compact)?.len();
+    // A non-empty array adds its line breaks (pretty-printed), and a separator between rules
+    let (frame, separator) = if limits.compact { (0, 1) } else { (4, 2) };
+
+    let mut groups: Vec<Vec<LsRule>> = vec![Vec::new()];
+    let mut bytes = base;
+    for rule in rules {
+        let size = rule_bytes(&rule, limits.compact)?;
+        let current = groups.last_mut().expect("at least one part");
+        let added = if current.is_empty() { frame + size } else { separator + size };
+        let fits = limits.max_rules.is_none_or(|max| current.len() < max)
+            && limits.max_bytes.is_none_or(|max| bytes + added <= max);
+        if current.is_empty() || fits {
+            current.push(rule);
+            bytes += added;
+        } else {
+            groups.push(vec![rule]);
+            bytes = base + frame + size;
+        }
+    }
+
+    let count = groups.len();
+    groups
+        .into_iter()
+        .enumerate()
+        .map(|(i, rules)| {
+            let mut part = LsRulesOutput {
+                name: match count {
+                    1 => name.clone(),
+                    _ => format!("{} (part {} of {})", name, i + 1, count),
+                },
+                description: description.clone(),
+                rules,
+            };
+            finish(&mut part)?;
+            Ok(part)
+        })
+        .collect()
+}
+
+/// Bytes a rule takes in the `rules` array of a written file, without separators
+fn rule_bytes(rule: &LsRule, compact: bool) -> Result<usize> {
+    if compact {
+        return Ok(serde_json::to_string(rule)?.len());
+    }
+    // Pretty-printed rules sit two levels deep, so each of their lines is indented by four spaces
+    let json = serde_json::to_string_pretty(rule)?;
+    Ok(json.len() + 4 * (json.matches('\n').count() + 1))
+}
+
+/// Path of part `n` (1-based): `rules.lsrules` becomes `rules.2.lsrules`
+pub fn part_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!("{}.{}.{}", stem, n, ext.to_string_lossy())),
+        None => path.with_file_name(format!("{}.{}", stem, n)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(rules: usize) -> LsRulesOutput<'static> {
+        LsRulesOutput {
+            name: "Apple Ecocide".into(),
+            description: "Generated by apple-ecocide".into(),
+            rules: (0..rules)
+                .map(|i| LsRule {
+                    action: "deny",
+                    priority: None,
+                    process: "any".into(),
+                    via: None,
+                    remote_domains: (0..i % 5 + 1).map(|n| format!("host{}.example{}.com", n, i).into()).collect(),
+                    remote_addresses: Vec::new(),
+                    remote: None,
+                    protocol: None,
+                    disabled: None,
+                    notes: format!("[example] Rule {}", i),
+                })
+                .collect(),
+        }
+    }
+
+    fn stamp(part: &mut LsRulesOutput) -> Result<()> {
+        part.description.push_str("\nIntegrity: sha256 0000000000000000000000000000000000000000000000000000000000000000");
+        Ok(())
+    }
+
+    #[test]
+    fn parts_fit_once_named_and_stamped() {
+        for compact in [false, true] {
+            let limits = Limits {
+                max_rules: None,
+                max_bytes: Some(1200),
+                compact,
+            };
+            let parts = split(output(40), limits, stamp).unwrap();
+            assert!(parts.len() > 1);
+            assert_eq!(parts.iter().map(|part| part.rules.len()).sum::<usize>(), 40);
+            for (i, part) in parts.iter().enumerate() {
+                assert_eq!(part.name, format!("Apple Ecocide (part {} of {})", i + 1, parts.len()));
+                assert!(part.description.contains("Integrity"));
+                let bytes = crate::rules_json(part, compact).unwrap().len();
+                assert!(bytes <= 1200, "part {} has {} bytes (compact: {})", i + 1, bytes, compact);
+            }
+        }
+    }
+
+    #[test]
+    fn parts_respect_the_rule_count() {
+        let limits = Limits {
+            max_rules: Some(7),
+            max_bytes: None,
+            compact: false,
+        };
+        let parts = split(output(20), limits, |_| Ok(())).unwrap();
+        let sizes: Vec<usize> = parts.iter().map(|part| part.rules.len()).collect();
+        assert_eq!(sizes, [7, 7, 6]);
+
+        let whole = split(output(5), limits, |_| Ok(())).unwrap();
+        assert_eq!(whole.len(), 1);
+        assert_eq!(whole[0].name, "Apple Ecocide");
+    }
+}
@@ -0,0 +1,40 @@
+//! Copying generated output to the system clipboard (`--clipboard`).
+
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard tools to try in order, with their arguments
+#[cfg(target_os = "macos")]
+const TOOLS: &[(&str, &[&str])] = &[("pbcopy", &[])];
+#[cfg(windows)]
+const TOOLS: &[(&str, &[&str])] = &[("clip", &[])];
+#[cfg(not(any(target_os = "macos", windows)))]
+const TOOLS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+/// Put `content` on the clipboard using the first available clipboard tool
+pub fn copy(content: &[u8]) -> Result<()> {
+    for (program, args) in TOOLS {
+        let Ok(mut child) = Command::new(program).args(*args).stdin(Stdio::piped()).spawn() else {
+            continue;
+        };
+        child
+            .stdin
+            .take()
+            .context("Failed to open clipboard tool input")?
+            .write_all(content)
+            .with_context(|| format!("Failed to write to {}", program))?;
+        let status = child.wait().with_context(|| format!("Failed to run {}", program))?;
+        if !status.success() {
+            bail!("{} exited with {}", program, status);
+        }
+        return Ok(());
+    }
+
+    let names: Vec<&str> = TOOLS.iter().map(|(program, _)| *program).collect();
+    bail!("No clipboard tool found. Install one of: {}", names.join(", "))
+}
@@ -0,0 +1,77 @@
+//! `apple-ecocide coverage`: compares the selected categories with a reference blocklist.
+
+use super::import::{parse_hosts, read_source};
+use anyhow::Result;
+use apple_ecocide::{Category, Domain, DomainIndex, DomainSyntax};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Serialize)]
+struct CoverageReport<'a> {
+    reference: &'a str,
+    reference_domains: usize,
+    category_domains: usize,
+    /// Share of reference domains covered by the selected categories
+    coverage: f64,
+    /// Reference domains not covered by any selected category
+    missing_from_categories: Vec<&'a str>,
+    /// Category domains the reference list does not cover, with the categories listing them
+    missing_from_reference: BTreeMap<&'a str, Vec<&'a str>>,
+}
+
+/// Print a JSON coverage report of `selected` against the list at `source`
+pub fn run(selected: &[&(String, Category)], source: &str) -> Result<()> {
+    let content = read_source(source)?;
+    // Third-party lists often contain service names such as `_dmarc`
+    let (reference, _) = parse_hosts(&content, DomainSyntax { allow_underscores: true });
+
+    let index = DomainIndex::new(selected.iter().copied());
+
+    let missing_from_categories: Vec<&str> = reference
+        .iter()
+        .filter(|domain| index.lookup(domain).is_empty())
+        .map(Domain::as_str)
+        .collect();
+
+    let mut category_domains = BTreeSet::new();
+    let mut missing_from_reference: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (slug, category) in selected.iter().copied() {
+        for domain in category.rules.iter().flat_map(|r| &r.domains) {
+            category_domains.insert(domain.as_str());
+            if !covered_by(&reference, domain) {
+                missing_from_reference.entry(domain).or_default().push(slug);
+            }
+        }
+    }
+
+    let report = CoverageReport {
+        reference: source,
+        reference_domains: reference.len(),
+        category_domains: category_domains.len(),
+        coverage: if reference.is_empty() {
+            1.0
+        } else {
+            (reference.len() - missing_from_categories.len()) as f64 / reference.len() as f64
+        },
+        missing_from_categories,
+        missing_from_reference,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// Whether `domain` or one of its parent domains is in `set`
+fn covered_by(set: &BTreeSet<Domain>, domain: &str) -> bool {
+    let domain = domain.to_lowercase();
+    let mut suffix = domain.as_str();
+    loop {
+        if set.contains(suffix) {
+            return true;
+        }
+        match suffix.split_once('.') {
+            Some((_, rest)) => suffix = rest,
+            None => return false,
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Changes a new rules file makes to the existing one (`--show-diff`).
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// The parts of an `.lsrules` file that decide what Little Snitch blocks or allows
+#[derive(Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<Rule>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Rule {
+    action: String,
+    process: String,
+    via: Option<String>,
+    #[serde(default)]
+    remote_domains: Vec<String>,
+    #[serde(default)]
+    remote_addresses: Vec<String>,
+    remote: Option<String>,
+    disabled: Option<bool>,
+}
+
+impl RulesFile {
+    fn parse(content: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(content)?)
+    }
+
+    fn domains(&self) -> BTreeSet<&str> {
+        self.rules.iter().flat_map(|r| &r.remote_domains).map(String::as_str).collect()
+    }
+
+    /// One `action process remote` line per domain, address or process-wide rule
+    fn entries(&self) -> BTreeSet<String> {
+        let mut entries = BTreeSet::new();
+        for rule in &self.rules {
+            let suffix = if rule.disabled == Some(true) { " (disabled)" } else { "" };
+            let process = match &rule.via {
+                Some(via) => format!("{} via {}", rule.process, via),
+                None => rule.process.clone(),
+            };
+            if rule.remote_domains.is_empty() && rule.remote_addresses.is_empty() {
+                let remote = rule.remote.as_deref().unwrap_or("any");
+                entries.insert(format!("{} {} {}{}", rule.action, process, remote, suffix));
+            }
+            for remote in rule.remote_domains.iter().chain(&rule.remote_addresses) {
+                entries.insert(format!("{} {} {}{}", rule.action, process, remote, suffix));
+            }
+        }
+        entries
+    }
+}
+
+/// Print the domains and rules `content` adds to and removes from the file at `path`
+///
+/// Does nothing when the file does not exist yet.
+pub fn print(path: &Path, content: &[u8]) -> Result<()> {
+    let Ok(existing) = fs::read(path) else {
+        return Ok(());
+    };
+    let old = RulesFile::parse(&existing).with_context(|| format!("Failed to parse existing rules: {}", path.display()))?;
+    let new = RulesFile::parse(content)?;
+
+    let (old_domains, new_domains) = (old.domains(), new.domains());
+    let (old_entries, new_entries) = (old.entries(), new.entries());
+    if old_domains == new_domains && old_entries == new_entries {
+        return Ok(());
+    }
+
+    eprintln!("Changes to {} ({} -> {} rules):", path.display(), old.rules.len(), new.rules.len());
+    print_section("Domains added", '+', new_domains.difference(&old_domains));
+    print_section("Domains removed", '-', old_domains.difference(&new_domains));
+    print_section("Rules added", '+', new_entries.difference(&old_entries));
+    print_section("Rules removed", '-', old_entries.difference(&new_entries));
+    Ok(())
+}
+
+fn print_section<T: std::fmt::Display>(title: &str, sign: char, items: impl Iterator<Item = T>) {
+    let items: Vec<T> = items.collect();
+    if items.is_empty() {
+        return;
+    }
+    eprintln!("  {} ({}):", title, items.len());
+    for item in items {
+        eprintln!("    {} {}", sign, item);
+    }
+}
@@ -0,0 +1,56 @@
+//! `apple-ecocide discover`: finds subdomains in certificate transparency logs
+//! that no category covers yet.
+
+use anyhow::{Context, Result};
+use apple_ecocide::{Category, DomainIndex};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+const CRT_SH_URL: &str = "https://crt.sh/";
+
+#[derive(Deserialize)]
+struct CrtShEntry {
+    name_value: String,
+}
+
+/// Query crt.sh for `base` and print subdomains not covered by any category
+pub fn run(categories: &[(String, Category)], base: &str) -> Result<()> {
+    let base = base.trim().trim_end_matches('.').to_lowercase();
+    let seen = query_crt_sh(&base)?;
+
+    let index = DomainIndex::new(categories);
+    let uncovered: Vec<&String> = seen
+        .iter()
+        .filter(|domain| index.lookup(domain).is_empty())
+        .collect();
+
+    eprintln!(
+        "Found {} names under {} in certificate transparency logs, {} not covered by any category",
+        seen.len(),
+        base,
+        uncovered.len()
+    );
+    for domain in uncovered {
+        println!("{}", domain);
+    }
+
+    Ok(())
+}
+
+fn query_crt_sh(base: &str) -> Result<BTreeSet<String>> {
+    let response = ureq::get(CRT_SH_URL)
+        .query("q", &format!("%.{}", base))
+        .query("output", "json")
+        .call()
+        .context("Failed to query crt.sh")?;
+    let entries: Vec<CrtShEntry> =
+        serde_json::from_reader(response.into_reader()).context("Invalid response from crt.sh")?;
+
+    let suffix = format!(".{}", base);
+    Ok(entries
+        .iter()
+        .flat_map(|entry| entry.name_value.lines())
+        .map(|name| name.trim().trim_start_matches("*.").to_lowercase())
+        .filter(|name| name.ends_with(&suffix) || *name == base)
+        .collect())
+}
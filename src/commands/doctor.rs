@@ -0,0 +1,78 @@
+//! `apple-ecocide doctor`: checks the local environment rules will be used in.
+
+use super::processes;
+use anyhow::Result;
+use apple_ecocide::{Category, CategorySource};
+use std::path::{Path, PathBuf};
+use std::{env, process::Command};
+
+const LITTLE_SNITCH_APP: &str = "/Applications/Little Snitch.app";
+
+/// Run all environment checks and print a report
+pub fn run(categories: &[(String, Category)], source: &dyn CategorySource) -> Result<()> {
+    println!("Checking environment (categories {}):\n", source.describe());
+
+    if !cfg!(target_os = "macos") {
+        println!("  [warn] Not running on macOS; Little Snitch and process checks skipped");
+        return Ok(());
+    }
+
+    check_little_snitch();
+    check_cli();
+    println!();
+    check_processes(categories);
+
+    Ok(())
+}
+
+fn check_little_snitch() {
+    let app = Path::new(LITTLE_SNITCH_APP);
+    if !app.is_dir() {
+        println!("  [fail] Little Snitch is not installed in /Applications");
+        return;
+    }
+
+    match app_version(app) {
+        Some(version) => println!("  [ok]   Little Snitch {} installed", version),
+        None => println!("  [warn] Little Snitch installed, but its version could not be read"),
+    }
+}
+
+fn app_version(app: &Path) -> Option<String> {
+    let output = Command::new("defaults")
+        .arg("read")
+        .arg(app.join("Contents/Info"))
+        .arg("CFBundleShortVersionString")
+        .output()
+        .ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    let version = version.trim();
+    (output.status.success() && !version.is_empty()).then(|| version.to_string())
+}
+
+fn check_cli() {
+    match find_in_path("littlesnitch") {
+        Some(path) => println!("  [ok]   littlesnitch CLI available at {}", path.display()),
+        None => {
+            let bundled = Path::new(LITTLE_SNITCH_APP).join("Contents/Components/littlesnitch");
+            if bundled.is_file() {
+                println!(
+                    "  [warn] littlesnitch CLI is not on PATH; link it from {}",
+                    bundled.display()
+                );
+            } else {
+                println!("  [warn] littlesnitch CLI not found");
+            }
+        }
+    }
+}
+
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|p| p.is_file())
+}
+
+fn check_processes(categories: &[(String, Category)]) {
+    processes::print_report(&processes::verify(categories), "  ");
+}
@@ -0,0 +1,225 @@
+//! `apple-ecocide report`: how many connection attempts the rules of a deployed
+//! `.lsrules` file matched in a Little Snitch traffic log.
+
+use super::import::{read_source, split_slug_prefix, LsRulesFile, OneOrMany};
+use super::traffic::{parse_log, LogEntry};
+use anyhow::{Context, Result};
+use apple_ecocide::{Category, DomainIndex};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Rules without a `[slug]` note prefix or a category listing them
+const OTHER: &str = "(other)";
+
+/// A rule of the `.lsrules` file, reduced to what a traffic log can be matched against
+struct RuleMatcher {
+    slug: String,
+    deny: bool,
+    process: Option<String>,
+    domains: Vec<String>,
+    hosts: Vec<String>,
+}
+
+impl RuleMatcher {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        if self.process.as_ref().is_some_and(|process| entry.process.as_ref() != Some(process)) {
+            return false;
+        }
+        if self.domains.is_empty() && self.hosts.is_empty() {
+            // A process rule covering every remote
+            return self.process.is_some();
+        }
+        let Some(host) = &entry.host else {
+            return false;
+        };
+        self.hosts.iter().any(|h| h == host)
+            || self
+                .domains
+                .iter()
+                .any(|domain| host == domain || host.ends_with(&format!(".{}", domain)))
+    }
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    rules: &'a str,
+    log: &'a str,
+    /// Connection attempts in the log
+    attempts: u64,
+    /// Whether `blocked` comes from the log's denied counts rather than the rule actions
+    records_denials: bool,
+    /// Categories of the rules file, most blocked first
+    categories: Vec<CategoryEffect>,
+    /// Categories whose rules matched no connection in the log
+    never_fired: Vec<String>,
+    /// Rules whose remote (e.g. `local-net`) a traffic log cannot be matched against
+    unmatchable_rules: usize,
+}
+
+#[derive(Serialize, Default)]
+struct CategoryEffect {
+    slug: String,
+    rules: usize,
+    /// Attempts matching one of the category's rules
+    attempts: u64,
+    /// Matching attempts that were blocked
+    blocked: u64,
+    hosts: BTreeSet<String>,
+    processes: BTreeSet<String>,
+}
+
+/// Print how often the rules in the `.lsrules` file at `rules` matched the traffic log at `log`
+pub fn run(categories: &[(String, Category)], rules: &str, log: &str, json: bool) -> Result<()> {
+    let file: LsRulesFile = serde_json::from_str(&read_source(rules)?)
+        .with_context(|| format!("Failed to parse: {}", rules))?;
+    let traffic = parse_log(&read_source(log)?);
+    if traffic.entries.is_empty() {
+        anyhow::bail!("No connections found in {}", log);
+    }
+
+    let index = DomainIndex::new(categories);
+    let mut effects: BTreeMap<String, CategoryEffect> = BTreeMap::new();
+    let mut matchers = Vec::new();
+    let mut unmatchable_rules = 0;
+    for rule in file.rules.into_iter().filter(|rule| !rule.disabled) {
+        let domains: Vec<String> = rule.remote_domains.map(OneOrMany::into_vec).unwrap_or_default();
+        let hosts: Vec<String> = rule.remote_hosts.map(OneOrMany::into_vec).unwrap_or_default();
+        let process = rule.process.filter(|p| p != "any");
+        let slug = rule
+            .notes
+            .as_deref()
+            .and_then(split_slug_prefix)
+            .map(|(slug, _)| slug.to_string())
+            .or_else(|| category_of(categories, &index, &domains, &hosts, process.as_deref()))
+            .unwrap_or_else(|| OTHER.to_string());
+
+        let effect = effects.entry(slug.clone()).or_default();
+        effect.rules += 1;
+        if rule.remote.as_deref().is_some_and(|remote| remote != "any") || rule.ports.is_some() {
+            unmatchable_rules += 1;
+            continue;
+        }
+        matchers.push(RuleMatcher {
+            slug,
+            deny: rule.action.as_deref() != Some("allow"),
+            process,
+            domains: domains.iter().map(|d| d.to_lowercase()).collect(),
+            hosts: hosts.iter().map(|h| h.to_lowercase()).collect(),
+        });
+    }
+
+    for entry in &traffic.entries {
+        // Count each attempt once per category, even when several of its rules match
+        let mut counted = BTreeSet::new();
+        for matcher in matchers.iter().filter(|m| m.matches(entry)) {
+            if !counted.insert(&matcher.slug) {
+                continue;
+            }
+            let effect = effects.get_mut(&matcher.slug).expect("every matcher has an entry");
+            effect.attempts += entry.connections;
+            effect.blocked += match (traffic.records_denials, matcher.deny) {
+                (true, _) => entry.denied,
+                (false, true) => entry.connections,
+                (false, false) => 0,
+            };
+            effect.hosts.extend(entry.host.clone());
+            effect.processes.extend(entry.process.clone());
+        }
+    }
+
+    let mut categories: Vec<CategoryEffect> = effects
+        .into_iter()
+        .map(|(slug, effect)| CategoryEffect { slug, ..effect })
+        .collect();
+    categories.sort_by(|a, b| {
+        b.blocked
+            .cmp(&a.blocked)
+            .then(b.attempts.cmp(&a.attempts))
+            .then(a.slug.cmp(&b.slug))
+    });
+    let report = Report {
+        rules,
+        log,
+        attempts: traffic.entries.iter().map(|e| e.connections).sum(),
+        records_denials: traffic.records_denials,
+        never_fired: categories
+            .iter()
+            .filter(|c| c.attempts == 0 && c.slug != OTHER)
+            .map(|c| c.slug.clone())
+            .collect(),
+        categories,
+        unmatchable_rules,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print(&report);
+    }
+    Ok(())
+}
+
+fn print(report: &Report) {
+    println!(
+        "Rules in {} against {} connection attempts in {}\n",
+        report.rules, report.attempts, report.log
+    );
+    println!("  {:30} {:>5} {:>9} {:>9} {:>6}", "category", "rules", "attempts", "blocked", "hosts");
+    for effect in report.categories.iter().filter(|c| c.attempts > 0) {
+        println!(
+            "  {:30} {:>5} {:>9} {:>9} {:>6}",
+            effect.slug,
+            effect.rules,
+            effect.attempts,
+            effect.blocked,
+            effect.hosts.len()
+        );
+    }
+
+    if !report.records_denials {
+        println!("\nThe log has no denied counts, so every attempt matching a deny rule is counted as blocked.");
+    }
+    if report.unmatchable_rules > 0 {
+        println!(
+            "{} rules restrict ports or special remotes such as local-net and were not matched.",
+            report.unmatchable_rules
+        );
+    }
+    if report.never_fired.is_empty() {
+        println!("\nEvery category matched at least one connection.");
+    } else {
+        println!("\nNever fired ({}):", report.never_fired.len());
+        for slug in &report.never_fired {
+            println!("  {}", slug);
+        }
+        println!(
+            "\nIf the log covers your usual activity, these can be dropped with:\n  --exclude {}",
+            report.never_fired.join(" ")
+        );
+    }
+}
+
+/// The category listing one of a rule's domains or its process, for rules without a `[slug]` note
+fn category_of(
+    categories: &[(String, Category)],
+    index: &DomainIndex,
+    domains: &[String],
+    hosts: &[String],
+    process: Option<&str>,
+) -> Option<String> {
+    if let Some(slug) = domains.iter().chain(hosts).find_map(|d| index.lookup(d).first().copied()) {
+        return Some(slug.to_string());
+    }
+    let process = process?;
+    categories
+        .iter()
+        .find(|(_, category)| {
+            category.rules.iter().any(|rule| {
+                [&rule.deny_process, &rule.allow_process]
+                    .into_iter()
+                    .flatten()
+                    .any(|p| p == process)
+            })
+        })
+        .map(|(slug, _)| slug.clone())
+}
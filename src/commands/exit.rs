@@ -0,0 +1,53 @@
+//! Exit codes scripts and CI jobs can branch on.
+
+use apple_ecocide::Error;
+
+/// Any other error: unreadable files, failed downloads, invalid arguments
+pub const FAILURE: i32 = 1;
+/// The selection parameters did not select any category
+pub const NOTHING_SELECTED: i32 = 2;
+/// Categories or patterns failed validation (`validate`, `check`, `--verify-processes`,
+/// `--strict`, malformed category files)
+pub const INVALID: i32 = 3;
+/// `--check`: the output file already holds the generated rules
+pub const UNCHANGED: i32 = 4;
+/// `--check`: generating would change the output file; `update --check`: newer category data
+/// was published; `stale`: categories are overdue for verification
+pub const OUTDATED: i32 = 5;
+
+/// The exit code for a command line clap rejected, or 0 for `--help` and `--version`
+///
+/// clap's own code for usage errors is 2, which would read as [`NOTHING_SELECTED`].
+pub fn for_usage(err: &clap::Error) -> i32 {
+    if err.use_stderr() {
+        FAILURE
+    } else {
+        0
+    }
+}
+
+/// The exit code for an error that ended the run
+pub fn for_error(err: &anyhow::Error) -> i32 {
+    match err.chain().find_map(|cause| cause.downcast_ref::<Error>()) {
+        Some(Error::EmptySelection) => NOTHING_SELECTED,
+        Some(Error::UnmatchedPatterns(_) | Error::Parse(_) | Error::InvalidDomain { .. } | Error::InvalidParams(_)) => {
+            INVALID
+        }
+        _ => FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn library_errors_map_to_exit_codes() {
+        let code = |err: Error| for_error(&anyhow::Error::from(err).context("Failed to generate"));
+        assert_eq!(code(Error::EmptySelection), NOTHING_SELECTED);
+        assert_eq!(code(Error::UnmatchedPatterns(vec!["apple-nope".into()])), INVALID);
+        assert_eq!(code(Error::InvalidParams("conflict".into())), INVALID);
+        assert_eq!(code(Error::UnknownCategory("apple-nope".into())), FAILURE);
+        assert_eq!(for_error(&anyhow::anyhow!("Failed to read: rules.lsrules")), FAILURE);
+    }
+}
@@ -0,0 +1,145 @@
+//! Time-bound rules for `--expires`, and `apple-ecocide prune` removing them once expired.
+
+use super::write::write_if_changed;
+use anyhow::{Context, Result};
+use apple_ecocide::{Date, LsRulesOutput};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Marks the expiry date in rule notes, e.g. `[apple-ads] Apple advertising SDK (expires 2025-03-01)`
+const EXPIRES: &str = "(expires ";
+
+/// Parse an `--expires` value: a number of days (`30d`) or weeks (`2w`), or a date (`2025-03-01`)
+pub fn parse(value: &str) -> Result<Date, String> {
+    let today = Date::today().ok_or("no system clock to count from")?;
+    let too_far = || format!("'{}' is too far in the future", value);
+    let date = if let Some(days) = value.strip_suffix('d') {
+        let days = days.parse().map_err(|_| format!("invalid number of days '{}'", days))?;
+        today.checked_add_days(days).ok_or_else(too_far)?
+    } else if let Some(weeks) = value.strip_suffix('w') {
+        let weeks = weeks.parse::<i64>().map_err(|_| format!("invalid number of weeks '{}'", weeks))?;
+        weeks.checked_mul(7).and_then(|days| today.checked_add_days(days)).ok_or_else(too_far)?
+    } else {
+        value
+            .parse()
+            .map_err(|_| format!("expected e.g. '30d', '2w' or '2025-03-01', got '{}'", value))?
+    };
+    if date <= today {
+        return Err(format!("{} is not in the future", date));
+    }
+    Ok(date)
+}
+
+/// Note the expiry date on every rule and in the description
+pub fn stamp(output: &mut LsRulesOutput, date: Date) {
+    for rule in &mut output.rules {
+        rule.notes = match rule.notes.as_str() {
+            "" => format!("{}{})", EXPIRES, date),
+            notes => format!("{} {}{})", notes, EXPIRES, date),
+        };
+    }
+    output.description.push_str(&format!("\nExpires {}; remove with `apple-ecocide prune`.", date));
+}
+
+/// The expiry date noted on a rule, if any
+fn expiry(notes: &str) -> Option<Date> {
+    let start = notes.rfind(EXPIRES)? + EXPIRES.len();
+    notes[start..].strip_suffix(')')?.parse().ok()
+}
+
+/// Path of the removal file written next to `path`: `rules.lsrules` → `rules.remove.lsrules`
+pub fn removal_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!("{}.remove.lsrules", stem))
+}
+
+/// Write the companion of a time-bound rules file: the same rule group without rules
+///
+/// Little Snitch replaces a rule group with the one of the same name on import, or
+/// on the next refresh of a subscription, so importing this file removes the rules.
+pub fn write_removal(path: &Path, output: &LsRulesOutput, date: Date) -> Result<PathBuf> {
+    let removal = LsRulesOutput {
+        name: output.name.clone(),
+        description: format!("Removes the rules of {} that expire on {}.", output.name, date),
+        rules: Vec::new(),
+    };
+    let removal_path = removal_path(path);
+    write_if_changed(&removal_path, serde_json::to_string_pretty(&removal)?.as_bytes(), 0)?;
+    Ok(removal_path)
+}
+
+/// Remove the rules of the `.lsrules` file at `path` whose expiry date has passed
+///
+/// Writes the result to `output`, or back to `path`. Other rules and keys are kept
+/// as they are. Returns the number of rules removed and left.
+pub fn prune(path: &Path, output: Option<&Path>, today: Date) -> Result<(usize, usize)> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read: {}", path.display()))?;
+    let mut file: serde_json::Value =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse: {}", path.display()))?;
+    let rules = file
+        .get_mut("rules")
+        .and_then(|rules| rules.as_array_mut())
+        .with_context(|| format!("No rules array in {}", path.display()))?;
+
+    let before = rules.len();
+    rules.retain(|rule| {
+        let notes = rule.get("notes").and_then(|notes| notes.as_str()).unwrap_or_default();
+        expiry(notes).is_none_or(|date| date > today)
+    });
+    let left = rules.len();
+
+    if left < before || output.is_some() {
+        let output = output.unwrap_or(path);
+        write_if_changed(output, serde_json::to_string_pretty(&file)?.as_bytes(), 0)?;
+    }
+    Ok((before - left, left))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_offsets_and_dates() {
+        let today = Date::today().unwrap();
+        assert_eq!(parse("30d"), Ok(today.checked_add_days(30).unwrap()));
+        assert_eq!(parse("2w"), Ok(today.checked_add_days(14).unwrap()));
+        assert_eq!(parse("9999-12-31"), Ok("9999-12-31".parse().unwrap()));
+        for invalid in ["0d", "-1w", "xd", "2000-01-01", "soon"] {
+            assert!(parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn rejects_offsets_that_overflow_or_leave_four_digit_years() {
+        for too_far in ["9223372036854775807d", "9223372036854775807w", "2000000000000000000w", "3000000d"] {
+            assert!(parse(too_far).is_err(), "{}", too_far);
+        }
+    }
+
+    #[test]
+    fn reads_back_stamped_expiry() {
+        assert_eq!(expiry("[apple-ads] Apple advertising SDK (expires 2025-03-01)"), "2025-03-01".parse().ok());
+        assert_eq!(expiry("[apple-ads] Apple advertising SDK"), None);
+    }
+
+    #[test]
+    fn prunes_expired_rules_only() {
+        let path = std::env::temp_dir().join(format!("apple-ecocide-prune-{}.lsrules", std::process::id()));
+        let rules = serde_json::json!({
+            "name": "Apple Ecocide",
+            "rules": [
+                {"action": "deny", "process": "any", "notes": "kept"},
+                {"action": "deny", "process": "any", "notes": "trial (expires 2025-03-01)"},
+                {"action": "deny", "process": "any", "notes": "later (expires 2025-06-01)"},
+            ],
+        });
+        fs::write(&path, rules.to_string()).unwrap();
+
+        let pruned = prune(&path, None, "2025-03-01".parse().unwrap());
+        let content = fs::read_to_string(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(pruned.unwrap(), (1, 2));
+        assert!(!content.unwrap().contains("trial"));
+    }
+}
@@ -0,0 +1,121 @@
+//! Flat dataset exports of the expanded rule set (`--format csv|sqlite`).
+
+use crate::commands::list::csv_field;
+use apple_ecocide::{Category, CategorySelection, GenerateParams, Severity};
+
+/// One process or domain of a selected category
+pub struct Row<'a> {
+    pub category: &'a str,
+    pub severity: Severity,
+    pub action: &'static str,
+    pub process: &'a str,
+    pub domain: &'a str,
+    pub notes: &'a str,
+}
+
+/// Expand the selected categories into one row per denied or allowed process or listed domain
+///
+/// Like the generated rules, domains are scoped to `params.app` when set, and
+/// process rules are then left out.
+pub fn rows<'a>(
+    params: &'a GenerateParams,
+    selected: &[&'a (String, Category)],
+    selection: &CategorySelection,
+) -> Vec<Row<'a>> {
+    let mut rows = Vec::new();
+    for (slug, category) in selected {
+        let denied = selection.denied.contains(slug);
+        let action = if denied { "deny" } else { "allow" };
+        let severity = params.severity_for(slug, category);
+
+        for rule in category.rules.iter().filter(|r| params.includes_rule(r)) {
+            let process = if denied { &rule.deny_process } else { &rule.allow_process };
+            if let Some(process) = process.as_deref().filter(|_| params.app.is_none()) {
+                rows.push(Row {
+                    category: slug,
+                    severity,
+                    action,
+                    process,
+                    domain: "",
+                    notes: &rule.notes,
+                });
+            }
+            for domain in &rule.domains {
+                rows.push(Row {
+                    category: slug,
+                    severity,
+                    action,
+                    process: params.app.as_deref().unwrap_or("any"),
+                    domain,
+                    notes: &rule.notes,
+                });
+            }
+        }
+    }
+    rows
+}
+
+/// Render rows as CSV with a header line
+pub fn csv(rows: &[Row]) -> String {
+    let mut out = String::from("category,severity,action,process,domain,notes\n");
+    for row in rows {
+        out.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            csv_field(row.category),
+            row.severity,
+            row.action,
+            csv_field(row.process),
+            csv_field(row.domain),
+            csv_field(row.notes)
+        ));
+    }
+    out
+}
+
+/// Build a SQLite database holding a `rules` table of the rows
+#[cfg(feature = "sqlite")]
+pub fn sqlite(rows: &[Row]) -> anyhow::Result<Vec<u8>> {
+    use anyhow::Context;
+
+    let temp = std::env::temp_dir().join(format!("apple-ecocide-{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&temp);
+
+    let result = (|| -> anyhow::Result<Vec<u8>> {
+        let mut conn = rusqlite::Connection::open(&temp)?;
+        let tx = conn.transaction()?;
+        tx.execute_batch(
+            "CREATE TABLE rules (
+                category TEXT NOT NULL,
+                severity TEXT NOT NULL,
+                action TEXT NOT NULL,
+                process TEXT NOT NULL,
+                domain TEXT,
+                notes TEXT NOT NULL
+            );
+            CREATE INDEX rules_domain ON rules (domain);
+            CREATE INDEX rules_category ON rules (category);",
+        )?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO rules (category, severity, action, process, domain, notes)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for row in rows {
+                insert.execute((
+                    row.category,
+                    row.severity.as_str(),
+                    row.action,
+                    row.process,
+                    Some(row.domain).filter(|d| !d.is_empty()),
+                    row.notes,
+                ))?;
+            }
+        }
+        tx.commit()?;
+        conn.close().map_err(|(_, e)| e)?;
+        std::fs::read(&temp).context("Failed to read SQLite export")
+    })();
+
+    let _ = std::fs::remove_file(&temp);
+    result
+}
@@ -0,0 +1,418 @@
+//! `apple-ecocide import`: converts existing blocklists into category TOML files.
+
+use anyhow::{Context, Result};
+use apple_ecocide::{Category, CategoryRule, Domain, DomainSyntax, Priority, Remote, Severity};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Hostnames that hosts files map to loopback but are not blocklist entries
+const HOSTS_BOILERPLATE: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "local",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "ip6-localnet",
+    "ip6-mcastprefix",
+    "ip6-allnodes",
+    "ip6-allrouters",
+    "ip6-allhosts",
+    "0.0.0.0",
+];
+
+/// Convert a hosts, adblock, or plain domain list into `<dir>/<slug>.toml`
+pub fn run_hosts(
+    source: &str,
+    slug: &str,
+    name: Option<&str>,
+    severity: Severity,
+    dir: &Path,
+    force: bool,
+) -> Result<PathBuf> {
+    check_overwrite(dir, [slug], force)?;
+    let content = read_source(source)?;
+    let (domains, rejected) = parse_hosts(&content, DomainSyntax::default());
+    if domains.is_empty() {
+        anyhow::bail!("No domains found in {}", source);
+    }
+
+    for entry in &rejected {
+        eprintln!("Warning: skipping invalid domain '{}'", entry);
+    }
+
+    let category = Category {
+        name: name.map_or_else(|| slug.to_string(), String::from),
+        description: format!("Domains imported from {}", source),
+        severity,
+        breakage: None,
+        impact: String::new(),
+        sources: Vec::new(),
+        last_verified: None,
+        asns: Vec::new(),
+        translations: BTreeMap::new(),
+        normalized: Vec::new(),
+        rules: vec![CategoryRule {
+            notes: format!("Imported from {}", source),
+            domains: domains.into_iter().collect(),
+            deny_process: None,
+            allow_process: None,
+            min_macos: None,
+            max_macos: None,
+            via_process: None,
+            remote: None,
+            priority: None,
+            disabled: false,
+            breaks: Vec::new(),
+        }],
+    };
+
+    write_category(dir, slug, &category)
+}
+
+/// How rules of an imported `.lsrules` file are grouped into categories
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitBy {
+    /// One category per distinct note (or `[slug]` prefix written by this tool)
+    Notes,
+    /// A single category containing every rule
+    None,
+}
+
+/// A `.lsrules` file, read leniently
+#[derive(Deserialize)]
+pub struct LsRulesFile {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<LsRulesEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct LsRulesEntry {
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub process: Option<String>,
+    #[serde(rename = "remote-domains", default)]
+    pub remote_domains: Option<OneOrMany>,
+    #[serde(rename = "remote-hosts", default)]
+    pub remote_hosts: Option<OneOrMany>,
+    #[serde(default)]
+    pub remote: Option<String>,
+    #[serde(default)]
+    pub ports: Option<serde_json::Value>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub via: Option<String>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// Little Snitch accepts either a single string or an array for host keys
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    pub fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(s) => vec![s],
+            OneOrMany::Many(v) => v,
+        }
+    }
+}
+
+/// Reconstruct category files from an existing `.lsrules` file, returning the written paths
+pub fn run_lsrules(
+    source: &str,
+    split_by: SplitBy,
+    slug: Option<&str>,
+    dir: &Path,
+    force: bool,
+) -> Result<Vec<PathBuf>> {
+    let content = read_source(source)?;
+    let file: LsRulesFile =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse: {}", source))?;
+
+    let default_slug = slug
+        .map(clean_slug)
+        .filter(|slug| !slug.is_empty())
+        .or_else(|| Path::new(source).file_stem().map(|s| slugify(&s.to_string_lossy())))
+        .filter(|slug| !slug.is_empty())
+        .unwrap_or_else(|| "imported".into());
+
+    let mut groups: BTreeMap<String, Vec<CategoryRule>> = BTreeMap::new();
+    for (i, entry) in file.rules.into_iter().enumerate() {
+        let note = entry.notes.clone().unwrap_or_default();
+        let Some(rule) = convert_rule(entry) else {
+            eprintln!("Warning: skipping rule {} ({}): not expressible as a category rule", i + 1, note);
+            continue;
+        };
+
+        let (group, rule_note) = match split_by {
+            SplitBy::None => (default_slug.clone(), note),
+            SplitBy::Notes => match split_slug_prefix(&note) {
+                // Notes come from untrusted files, so the prefix must not leave `dir`
+                Some((slug, rest)) if !clean_slug(slug).is_empty() => (clean_slug(slug), rest.to_string()),
+                Some((_, rest)) => (default_slug.clone(), rest.to_string()),
+                None if note.is_empty() => (default_slug.clone(), note),
+                None => (slugify(&note), note),
+            },
+        };
+        groups.entry(group).or_default().push(CategoryRule {
+            notes: rule_note,
+            ..rule
+        });
+    }
+
+    if groups.is_empty() {
+        anyhow::bail!("No importable rules found in {}", source);
+    }
+    // Check every file up front, so a refused import writes nothing
+    check_overwrite(dir, groups.keys().map(String::as_str), force)?;
+
+    let single = groups.len() == 1;
+    groups
+        .into_iter()
+        .map(|(slug, rules)| {
+            let name = match (&file.name, single) {
+                (Some(name), true) => name.clone(),
+                _ => slug.clone(),
+            };
+            let category = Category {
+                name,
+                description: file
+                    .description
+                    .clone()
+                    .filter(|_| single)
+                    .unwrap_or_else(|| format!("Rules imported from {}", source)),
+                severity: Severity::default(),
+                breakage: None,
+                impact: String::new(),
+                sources: Vec::new(),
+                last_verified: None,
+                asns: Vec::new(),
+                translations: BTreeMap::new(),
+                normalized: Vec::new(),
+                rules,
+            };
+            write_category(dir, &slug, &category)
+        })
+        .collect()
+}
+
+/// Map a Little Snitch rule onto a category rule: domain rules for any process,
+/// or a whole-process rule with no remote restriction
+fn convert_rule(entry: LsRulesEntry) -> Option<CategoryRule> {
+    if entry.ports.is_some() {
+        return None;
+    }
+
+    let mut domains: Vec<Domain> = entry
+        .remote_domains
+        .into_iter()
+        .chain(entry.remote_hosts)
+        .flat_map(OneOrMany::into_vec)
+        .map(|d| Domain::parse(&d).ok())
+        .collect::<Option<_>>()?;
+    domains.sort();
+    domains.dedup();
+
+    let process = entry.process.filter(|p| p != "any");
+    let disabled = entry.disabled;
+    let priority = entry.priority;
+    let via_process = entry.via;
+    let allow = entry.action.as_deref() == Some("allow");
+    let rule = |domains, process: Option<String>, remote| CategoryRule {
+        notes: String::new(),
+        remote,
+        via_process: via_process.clone(),
+        // Only record priorities that differ from what generation defaults to
+        priority: match (priority, process.is_some() && !allow) {
+            (Some(Priority::Regular) | None, true) => Some(Priority::Regular),
+            (Some(Priority::High), false) => Some(Priority::High),
+            _ => None,
+        },
+        domains,
+        deny_process: process.clone().filter(|_| !allow),
+        allow_process: process.filter(|_| allow),
+        min_macos: None,
+        max_macos: None,
+        disabled,
+        breaks: Vec::new(),
+    };
+
+    match (process, domains.is_empty(), entry.remote.as_deref()) {
+        (None, false, None) => Some(rule(domains, None, None)),
+        (Some(process), true, Some("any") | None) => Some(rule(Vec::new(), Some(process), None)),
+        (process, true, Some(remote)) => {
+            let remote: Remote = remote.parse().ok()?;
+            Some(rule(Vec::new(), process, Some(remote)))
+        }
+        _ => None,
+    }
+}
+
+/// Split the `[slug] notes` prefix this tool writes into rule notes
+pub fn split_slug_prefix(note: &str) -> Option<(&str, &str)> {
+    let rest = note.strip_prefix('[')?;
+    let (slug, rest) = rest.split_once(']')?;
+    (!slug.is_empty()).then(|| (slug, rest.trim_start()))
+}
+
+/// A slug safe to use as a path below the category directory: each `/`-separated
+/// part slugified, empty parts (and so `..` or a leading `/`) dropped
+fn clean_slug(slug: &str) -> String {
+    slug.split('/').map(slugify).filter(|part| !part.is_empty()).collect::<Vec<_>>().join("/")
+}
+
+fn slugify(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Read a local file or download an `http(s)://` URL
+pub fn read_source(source: &str) -> Result<String> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        ureq::get(source)
+            .call()
+            .with_context(|| format!("Failed to download {}", source))?
+            .into_string()
+            .with_context(|| format!("Failed to read response from {}", source))
+    } else {
+        fs::read_to_string(source).with_context(|| format!("Failed to read: {}", source))
+    }
+}
+
+/// Refuse to replace existing category files unless `force` is set
+fn check_overwrite<'a>(dir: &Path, slugs: impl IntoIterator<Item = &'a str>, force: bool) -> Result<()> {
+    if force {
+        return Ok(());
+    }
+    let existing: Vec<String> = slugs
+        .into_iter()
+        .map(|slug| category_path(dir, slug))
+        .filter(|path| path.exists())
+        .map(|path| path.display().to_string())
+        .collect();
+    if !existing.is_empty() {
+        anyhow::bail!("Refusing to overwrite {}; pass --force to replace", existing.join(", "));
+    }
+    Ok(())
+}
+
+fn category_path(dir: &Path, slug: &str) -> PathBuf {
+    dir.join(format!("{}.toml", slug))
+}
+
+/// Write a category as `<dir>/<slug>.toml`, returning the written path
+pub fn write_category(dir: &Path, slug: &str, category: &Category) -> Result<PathBuf> {
+    let path = category_path(dir, slug);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create: {}", parent.display()))?;
+    }
+    let toml = toml::to_string_pretty(category).context("Failed to serialize category")?;
+    fs::write(&path, toml).with_context(|| format!("Failed to write: {}", path.display()))?;
+    Ok(path)
+}
+
+/// Extract domains from hosts (`0.0.0.0 example.com`), adblock (`||example.com^`),
+/// or plain one-domain-per-line lists, returning sorted valid domains and rejected entries
+pub fn parse_hosts(content: &str, syntax: DomainSyntax) -> (BTreeSet<Domain>, BTreeSet<String>) {
+    let mut domains = BTreeSet::new();
+    let mut rejected = BTreeSet::new();
+
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() || line.starts_with('!') || line.starts_with('[') || line.starts_with("@@") {
+            continue;
+        }
+
+        let entries: Vec<&str> = if let Some(rule) = line.strip_prefix("||") {
+            // Adblock rules only block a whole domain when there are no modifiers
+            match rule.split_once('^') {
+                Some((domain, "")) => vec![domain],
+                _ => continue,
+            }
+        } else {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.clone().next()) {
+                (Some(address), Some(_)) if address.parse::<std::net::IpAddr>().is_ok() => {
+                    fields.collect()
+                }
+                (Some(domain), None) => vec![domain],
+                _ => continue,
+            }
+        };
+
+        for entry in entries {
+            if HOSTS_BOILERPLATE.contains(&entry.to_lowercase().as_str()) {
+                continue;
+            }
+            match Domain::parse_with(entry, syntax) {
+                Ok(domain) => {
+                    domains.insert(domain);
+                }
+                Err(_) => {
+                    rejected.insert(entry.to_string());
+                }
+            }
+        }
+    }
+
+    (domains, rejected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slug_prefixes_stay_inside_the_directory() {
+        assert_eq!(clean_slug("../escaped"), "escaped");
+        assert_eq!(clean_slug("/etc/passwd"), "etc/passwd");
+        assert_eq!(clean_slug("vendor/Analytics"), "vendor/analytics");
+        assert_eq!(clean_slug(".."), "");
+    }
+
+    #[test]
+    fn lsrules_import_groups_rules_by_prefix() {
+        let base = std::env::temp_dir().join(format!("apple-ecocide-import-{}", std::process::id()));
+        let source = base.join("existing.lsrules");
+        let dir = base.join("categories");
+        fs::create_dir_all(&base).unwrap();
+        let rules = serde_json::json!({
+            "name": "Existing",
+            "rules": [
+                {"action": "deny", "process": "any", "remote-domains": "ads.example.com", "notes": "[apple-ads] Ads"},
+                {"action": "deny", "process": "any", "remote-domains": "evil.example.com", "notes": "[../escaped] hi"},
+                {"action": "deny", "process": "any", "remote-domains": "dots.example.com", "notes": "[..] dots"},
+            ],
+        });
+        fs::write(&source, rules.to_string()).unwrap();
+
+        let paths = run_lsrules(source.to_str().unwrap(), SplitBy::Notes, None, &dir, false);
+        let _ = fs::remove_dir_all(&base);
+        let mut names: Vec<String> = paths
+            .unwrap()
+            .iter()
+            .map(|path| path.strip_prefix(&dir).unwrap().display().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["apple-ads.toml", "escaped.toml", "existing.toml"]);
+    }
+}
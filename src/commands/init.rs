@@ -0,0 +1,97 @@
+//! `apple-ecocide init`: a first-run wizard turning a few questions about how the
+//! Mac is used into a saved profile and the first rules file.
+
+use anyhow::{Context, Result};
+use apple_ecocide::{Answers, Category, GenerateParams, Severity, QUESTIONS};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Ask the questions on stdin and turn the answers into generation parameters
+///
+/// Starts from `params`, so options such as `--ls-version` still apply; see
+/// [`Answers::apply`] for how the answers select categories.
+pub fn run(params: GenerateParams, categories: &[(String, Category)], profile: &Path) -> Result<GenerateParams> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    println!("Answer a few questions to tailor your rules (press Enter for the default).\n");
+
+    let mut answers = Answers {
+        severity: ask_severity(&mut input)?,
+        ..Answers::default()
+    };
+    for question in QUESTIONS {
+        // Custom category sets may not have the categories a question is about
+        if !categories.iter().any(|(slug, _)| question.slugs.contains(&slug.as_str())) {
+            continue;
+        }
+        let uses = ask(&mut input, question.prompt, question.default)?;
+        answers.uses.insert(question.id.to_string(), uses);
+    }
+    let params = answers.apply(params, categories)?;
+
+    // The profile is meant to be regenerated on other machines and macOS releases
+    let saved = GenerateParams {
+        macos_version: None,
+        ..params.clone()
+    };
+    let toml = toml::to_string_pretty(&saved).context("Failed to serialize profile")?;
+    fs::write(profile, toml).with_context(|| format!("Failed to write: {}", profile.display()))?;
+    println!("\nSaved profile to {}", profile.display());
+    println!("Regenerate the same rules later with:\n  {}\n", command_line(&params));
+
+    Ok(params)
+}
+
+fn ask_severity(input: &mut impl BufRead) -> Result<Severity> {
+    loop {
+        print!("How strict should blocking be? 1) minimal  2) recommended  3) aggressive [2]: ");
+        io::stdout().flush()?;
+        match read_answer(input)?.as_deref() {
+            None | Some("" | "2") => return Ok(Severity::Recommended),
+            Some("1") => return Ok(Severity::Minimal),
+            Some("3") => return Ok(Severity::Aggressive),
+            Some(answer) => match answer.parse() {
+                Ok(severity) => return Ok(severity),
+                Err(_) => println!("Please answer 1, 2 or 3."),
+            },
+        }
+    }
+}
+
+fn ask(input: &mut impl BufRead, prompt: &str, default: bool) -> Result<bool> {
+    loop {
+        print!("{} [{}]: ", prompt, if default { "Y/n" } else { "y/N" });
+        io::stdout().flush()?;
+        match read_answer(input)?.map(|a| a.to_lowercase()).as_deref() {
+            None | Some("") => return Ok(default),
+            Some("y" | "yes") => return Ok(true),
+            Some("n" | "no") => return Ok(false),
+            Some(_) => println!("Please answer y or n."),
+        }
+    }
+}
+
+/// The next trimmed line, or `None` once input has ended
+fn read_answer(input: &mut impl BufRead) -> Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line).context("Failed to read answer")? == 0 {
+        // Keep the transcript readable when answers are piped in
+        println!();
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+/// The `apple-ecocide` invocation generating the same rules as `params`
+fn command_line(params: &GenerateParams) -> String {
+    let mut args = vec!["apple-ecocide".to_string(), "--all".to_string()];
+    args.push(format!("-s {}", params.severity));
+    for (slug, severity) in &params.severity_overrides {
+        args.push(format!("--severity-override {}={}", slug, severity));
+    }
+    if !params.exclude.is_empty() {
+        args.push(format!("--exclude {}", params.exclude.join(" ")));
+    }
+    args.join(" ")
+}
@@ -0,0 +1,176 @@
+//! Printing the category catalog for `--list`, and the feature table for `--list-features`.
+
+use anyhow::Result;
+use apple_ecocide::{category_group, get_category_info, Category, CategoryInfo, CategorySource, FEATURES};
+
+/// Output format of `--list`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListFormat {
+    #[default]
+    Text,
+    Json,
+    Yaml,
+    Markdown,
+    Csv,
+}
+
+/// Order of `--list`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListSort {
+    /// Catalog order, grouped by vendor
+    #[default]
+    Slug,
+    /// Highest impact score first
+    Score,
+}
+
+/// Print the available categories in the given format and order
+pub fn run(
+    categories: &[(String, Category)],
+    source: &dyn CategorySource,
+    format: ListFormat,
+    sort: ListSort,
+    verbose: bool,
+) -> Result<()> {
+    let mut infos = get_category_info(categories);
+    if sort == ListSort::Score {
+        infos.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.slug.cmp(&b.slug)));
+    }
+    match format {
+        ListFormat::Text if sort == ListSort::Score && !verbose => print_scores(&infos, source),
+        ListFormat::Text => print_text(&infos, source, verbose),
+        ListFormat::Json => println!("{}", serde_json::to_string_pretty(&infos)?),
+        ListFormat::Yaml => print!("{}", serde_yaml::to_string(&infos)?),
+        ListFormat::Markdown => print_markdown(&infos),
+        ListFormat::Csv => print_csv(&infos),
+    }
+    Ok(())
+}
+
+fn print_text(infos: &[CategoryInfo], source: &dyn CategorySource, verbose: bool) {
+    println!("Available categories ({}):\n", source.describe());
+
+    let mut current_group = None;
+    for info in infos {
+        let group = category_group(&info.slug);
+        if group != current_group {
+            if current_group.is_some() {
+                println!();
+            }
+            if let Some(group) = group {
+                println!("  {}/", group);
+            }
+            current_group = group;
+        }
+
+        if verbose {
+            println!("  {} ({})", info.slug, info.severity);
+            println!("    Name: {}", info.name);
+            println!("    Description: {}", info.description);
+            match info.breakage {
+                Some(breakage) => println!("    Breakage: {}", breakage),
+                None => println!("    Breakage: not rated"),
+            }
+            println!("    Score: {}", info.score);
+            println!(
+                "    Impact: {}",
+                info.impact.trim().replace('\n', "\n            ")
+            );
+            if !info.sources.is_empty() {
+                println!("    Sources: {}", info.sources.join("\n             "));
+            }
+            if let Some(date) = info.last_verified {
+                println!("    Last verified: {}", date);
+            }
+            println!();
+        } else {
+            println!("  {:30} [{:11}] {}", info.slug, info.severity, info.name);
+        }
+    }
+
+    if !verbose {
+        println!("\nUse --verbose for detailed descriptions and impact information.");
+    }
+}
+
+/// Flat list with the impact score, for `--sort-by score`
+fn print_scores(infos: &[CategoryInfo], source: &dyn CategorySource) {
+    println!("Available categories by impact score ({}):\n", source.describe());
+    println!("  {:>5}  {:30} {:11} {:8} name", "score", "category", "severity", "breakage");
+    for info in infos {
+        let breakage = info.breakage.map_or("-", |b| b.as_str());
+        println!(
+            "  {:>5}  {:30} {:11} {:8} {}",
+            info.score,
+            info.slug,
+            info.severity.as_str(),
+            breakage,
+            info.name
+        );
+    }
+    println!("\nHigher scores block more tracking for less breakage; enable those first.");
+}
+
+/// Print the features `--block-feature` accepts and what each one blocks
+pub fn features() {
+    println!("Features for --block-feature:\n");
+    for feature in FEATURES {
+        let blocks: Vec<String> = feature
+            .blocks
+            .iter()
+            .map(|block| match block.rules.len() {
+                0 => block.category.to_string(),
+                1 => format!("{} (1 rule)", block.category),
+                n => format!("{} ({} rules)", block.category, n),
+            })
+            .collect();
+        println!("  {:24} {:34} {}", feature.id, feature.name, blocks.join(", "));
+    }
+    println!("\nFeatures blocking only some rules of a category add a feature-<id> category holding them.");
+}
+
+fn print_markdown(infos: &[CategoryInfo]) {
+    println!("| Category | Name | Severity | Score | Rules | Description |");
+    println!("|----------|------|----------|-------|-------|-------------|");
+    for info in infos {
+        println!(
+            "| `{}` | {} | {} | {} | {} | {} |",
+            info.slug,
+            markdown_cell(&info.name),
+            info.severity,
+            info.score,
+            info.rule_count,
+            markdown_cell(&info.description)
+        );
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.trim().replace('|', "\\|").replace('\n', " ")
+}
+
+fn print_csv(infos: &[CategoryInfo]) {
+    println!("slug,name,description,severity,impact,rule_count,breakage,score");
+    for info in infos {
+        println!(
+            "{},{},{},{},{},{},{},{}",
+            csv_field(&info.slug),
+            csv_field(&info.name),
+            csv_field(&info.description),
+            info.severity,
+            csv_field(info.impact.trim()),
+            info.rule_count,
+            info.breakage.map_or("", |b| b.as_str()),
+            info.score
+        );
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote, or line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
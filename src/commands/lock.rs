@@ -0,0 +1,69 @@
+//! Lock files recording the exact rule set a configuration generates.
+
+use anyhow::{Context, Result};
+use apple_ecocide::LsRulesOutput;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize)]
+struct LockFile {
+    generator: String,
+    entries: BTreeSet<String>,
+}
+
+/// Outcome of checking generated output against a lock file
+pub enum LockStatus {
+    /// No lock file existed; one was written
+    Created,
+    /// The output matches the lock file
+    Unchanged,
+    /// The lock file was rewritten to match the output
+    Updated,
+}
+
+/// Compare `output` with the lock file at `path`, creating it if missing
+///
+/// When the output differs, the lock is rewritten if `update` is set; otherwise
+/// the differences are printed and an error is returned.
+pub fn check(path: &Path, output: &LsRulesOutput, update: bool) -> Result<LockStatus> {
+    let entries = output.entries();
+
+    if !path.exists() {
+        write(path, entries)?;
+        return Ok(LockStatus::Created);
+    }
+
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read: {}", path.display()))?;
+    let lock: LockFile =
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse lock file: {}", path.display()))?;
+
+    if lock.entries == entries {
+        return Ok(LockStatus::Unchanged);
+    }
+    if update {
+        write(path, entries)?;
+        return Ok(LockStatus::Updated);
+    }
+
+    for removed in lock.entries.difference(&entries) {
+        eprintln!("- {}", removed);
+    }
+    for added in entries.difference(&lock.entries) {
+        eprintln!("+ {}", added);
+    }
+    anyhow::bail!(
+        "Generated rules differ from lock file {}. Review the changes above and rerun with --update-lock to accept them",
+        path.display()
+    )
+}
+
+fn write(path: &Path, entries: BTreeSet<String>) -> Result<()> {
+    let lock = LockFile {
+        generator: format!("apple-ecocide {}", apple_ecocide::get_version()),
+        entries,
+    };
+    fs::write(path, serde_json::to_string_pretty(&lock)? + "\n")
+        .with_context(|| format!("Failed to write: {}", path.display()))
+}
@@ -0,0 +1,105 @@
+//! One rules file per Little Snitch profile (`--ls-profile`), so each network
+//! location can block at its own strictness.
+
+use anyhow::{Context, Result};
+use apple_ecocide::{GenerateParams, LsRulesOutput, Severity};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A Little Snitch profile and the parameters its rules are generated with
+#[derive(Debug, Clone)]
+pub struct LsProfile {
+    /// Profile name as Little Snitch shows it, e.g. `Home`
+    pub name: String,
+    pub source: ProfileSource,
+}
+
+/// Where a profile's parameters come from
+#[derive(Debug, Clone)]
+pub enum ProfileSource {
+    /// The command line's parameters
+    CommandLine,
+    /// The command line's parameters at another severity
+    Severity(Severity),
+    /// A saved profile, as written by `init` or served by `serve --profiles`
+    File(PathBuf),
+}
+
+/// Parse an `--ls-profile` value: `NAME`, `NAME=SEVERITY` or `NAME=FILE`
+pub fn parse(value: &str) -> Result<LsProfile, String> {
+    let (name, source) = match value.split_once('=') {
+        None => (value, ProfileSource::CommandLine),
+        Some((name, source)) => match source.parse() {
+            Ok(severity) => (name, ProfileSource::Severity(severity)),
+            Err(_) if source.ends_with(".toml") || source.ends_with(".json") => {
+                (name, ProfileSource::File(PathBuf::from(source)))
+            }
+            Err(_) => {
+                return Err(format!(
+                    "expected a severity or a .toml/.json profile after '{}=', got '{}'",
+                    name, source
+                ))
+            }
+        },
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("missing profile name in '{}'", value));
+    }
+    Ok(LsProfile {
+        name: name.to_string(),
+        source,
+    })
+}
+
+impl LsProfile {
+    /// Parameters for this profile, starting from the command line's
+    pub fn params(&self, base: &GenerateParams) -> Result<GenerateParams> {
+        let params = match &self.source {
+            ProfileSource::CommandLine => base.clone(),
+            ProfileSource::Severity(severity) => GenerateParams {
+                severity: *severity,
+                ..base.clone()
+            },
+            ProfileSource::File(path) => {
+                let content = fs::read_to_string(path).with_context(|| format!("Failed to read: {}", path.display()))?;
+                let params: GenerateParams = if path.extension().is_some_and(|e| e == "json") {
+                    serde_json::from_str(&content).map_err(anyhow::Error::from)
+                } else {
+                    toml::from_str(&content).map_err(anyhow::Error::from)
+                }
+                .with_context(|| format!("Invalid profile: {}", path.display()))?;
+                // Saved profiles leave out the macOS version so they work on any Mac
+                GenerateParams {
+                    macos_version: params.macos_version.or(base.macos_version),
+                    ..params
+                }
+            }
+        };
+        params
+            .validate()
+            .with_context(|| format!("Invalid parameters for Little Snitch profile '{}'", self.name))?;
+        Ok(params)
+    }
+
+    /// Path of this profile's rules file: `rules.lsrules` → `rules.home.lsrules`
+    pub fn path(&self, output: &Path) -> PathBuf {
+        let stem = output.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+        let slug: String = self
+            .name
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect();
+        output.with_file_name(format!("{}.{}.lsrules", stem, slug))
+    }
+
+    /// Name the rule group after the profile and say where it belongs
+    pub fn annotate(&self, output: &mut LsRulesOutput) {
+        output.name = format!("{} ({})", output.name, self.name);
+        output.description.push_str(&format!(
+            "\nFor the Little Snitch profile '{}': activate this rule group in that profile only.",
+            self.name
+        ));
+    }
+}
@@ -0,0 +1,146 @@
+//! Prometheus metrics for `apple-ecocide serve`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Upper bounds of the generation latency histogram, in seconds
+const LATENCY_BUCKETS: [f64; 8] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5];
+
+/// Counters collected while serving, rendered by [`Metrics::render`]
+#[derive(Default)]
+pub struct Metrics {
+    /// Responses by route and status code
+    requests: BTreeMap<(String, u16), u64>,
+    /// Rule file requests answered, by format and parameter set label
+    generated: BTreeMap<(String, String), u64>,
+    cache_hits: u64,
+    cache_misses: u64,
+    not_modified: u64,
+    /// Cumulative counts per latency bucket, plus the sum and count of all generations
+    latency_buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+    latency_count: u64,
+}
+
+/// The catalog being served, reported as labels of `apple_ecocide_catalog_info`
+pub struct CatalogInfo {
+    /// Latest release in the bundled changelog
+    pub release: String,
+    /// Hash of the loaded categories, which changes with any edit or `--categories` override
+    pub fingerprint: String,
+    pub categories: usize,
+}
+
+impl Metrics {
+    pub fn record_request(&mut self, route: &str, status: u16) {
+        *self.requests.entry((route.to_string(), status)).or_default() += 1;
+    }
+
+    pub fn record_generated(&mut self, format: &str, params: &str) {
+        *self.generated.entry((format.to_string(), params.to_string())).or_default() += 1;
+    }
+
+    pub fn record_cache(&mut self, hit: bool) {
+        if hit {
+            self.cache_hits += 1;
+        } else {
+            self.cache_misses += 1;
+        }
+    }
+
+    pub fn record_not_modified(&mut self) {
+        self.not_modified += 1;
+    }
+
+    pub fn record_generation(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        for (count, bound) in self.latency_buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *count += 1;
+            }
+        }
+        self.latency_sum += seconds;
+        self.latency_count += 1;
+    }
+
+    /// The metrics in the Prometheus text exposition format
+    pub fn render(&self, catalog: &CatalogInfo) -> String {
+        let mut out = String::new();
+
+        header(&mut out, "apple_ecocide_catalog_info", "gauge", "Tool version and category catalog being served");
+        let _ = writeln!(
+            out,
+            "apple_ecocide_catalog_info{{version=\"{}\",release=\"{}\",fingerprint=\"{}\"}} 1",
+            apple_ecocide::get_version(),
+            escape(&catalog.release),
+            catalog.fingerprint
+        );
+        header(&mut out, "apple_ecocide_catalog_categories", "gauge", "Categories in the served catalog");
+        let _ = writeln!(out, "apple_ecocide_catalog_categories {}", catalog.categories);
+
+        header(&mut out, "apple_ecocide_requests_total", "counter", "HTTP responses by route and status code");
+        for ((route, status), count) in &self.requests {
+            let _ = writeln!(out, "apple_ecocide_requests_total{{route=\"{}\",status=\"{}\"}} {}", escape(route), status, count);
+        }
+
+        header(&mut out, "apple_ecocide_generated_total", "counter", "Rule file requests answered, including 304s, by format and parameter set: default, profile:<name> or adhoc");
+        for ((format, params), count) in &self.generated {
+            let _ = writeln!(out, "apple_ecocide_generated_total{{format=\"{}\",params=\"{}\"}} {}", format, escape(params), count);
+        }
+
+        header(&mut out, "apple_ecocide_cache_hits_total", "counter", "Rule files served from the generation cache");
+        let _ = writeln!(out, "apple_ecocide_cache_hits_total {}", self.cache_hits);
+        header(&mut out, "apple_ecocide_cache_misses_total", "counter", "Rule files generated because they were not cached");
+        let _ = writeln!(out, "apple_ecocide_cache_misses_total {}", self.cache_misses);
+        header(&mut out, "apple_ecocide_not_modified_total", "counter", "Conditional requests answered with 304 Not Modified");
+        let _ = writeln!(out, "apple_ecocide_not_modified_total {}", self.not_modified);
+
+        header(&mut out, "apple_ecocide_generation_seconds", "histogram", "Time spent generating and rendering rule files");
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(self.latency_buckets) {
+            let _ = writeln!(out, "apple_ecocide_generation_seconds_bucket{{le=\"{}\"}} {}", bound, count);
+        }
+        let _ = writeln!(out, "apple_ecocide_generation_seconds_bucket{{le=\"+Inf\"}} {}", self.latency_count);
+        let _ = writeln!(out, "apple_ecocide_generation_seconds_sum {}", self.latency_sum);
+        let _ = writeln!(out, "apple_ecocide_generation_seconds_count {}", self.latency_count);
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', "\\\"").replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_series_are_labelled_by_parameter_set() {
+        let mut metrics = Metrics::default();
+        metrics.record_generated("lsrules", "default");
+        metrics.record_generated("lsrules", "profile:Work \"VPN\"");
+        metrics.record_generated("lsrules", "adhoc");
+        metrics.record_generated("lsrules", "adhoc");
+        let catalog = CatalogInfo {
+            release: "1.0.0".to_string(),
+            fingerprint: "abc".to_string(),
+            categories: 1,
+        };
+        let rendered = metrics.render(&catalog);
+        let generated: Vec<_> = rendered.lines().filter(|l| l.starts_with("apple_ecocide_generated_total{")).collect();
+        assert_eq!(
+            generated,
+            [
+                "apple_ecocide_generated_total{format=\"lsrules\",params=\"adhoc\"} 2",
+                "apple_ecocide_generated_total{format=\"lsrules\",params=\"default\"} 1",
+                "apple_ecocide_generated_total{format=\"lsrules\",params=\"profile:Work \\\"VPN\\\"\"} 1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,39 @@
+//! Subcommands and supporting pieces of the command-line interface.
+
+pub mod agent;
+#[cfg(feature = "asn")]
+pub mod asn;
+pub mod changelog;
+pub mod check;
+pub mod chunk;
+pub mod clipboard;
+pub mod coverage;
+pub mod diff;
+pub mod discover;
+pub mod doctor;
+pub mod effectiveness;
+pub mod exit;
+pub mod expiry;
+pub mod export;
+pub mod import;
+pub mod init;
+pub mod list;
+pub mod lock;
+pub mod ls_profile;
+pub mod metrics;
+pub mod plan;
+pub mod processes;
+pub mod report;
+pub mod serve;
+pub mod sign;
+pub mod snapshot;
+pub mod split;
+pub mod stale;
+pub mod stdin;
+pub mod suggest;
+pub mod summary;
+pub mod traffic;
+pub mod update;
+pub mod validate;
+pub mod web;
+pub mod write;
@@ -0,0 +1,68 @@
+//! The selection plan printed by `--dry-run`, and the feature breakage printed by `--advise`.
+
+use apple_ecocide::{
+    BrokenFeature, Category, CategorySelection, GenerateParams, LsRulesOutput, SelectionPreview, SelectionReason,
+};
+
+/// Print which categories were chosen and why, which were skipped, and how many rules each contributes
+pub fn print(
+    params: &GenerateParams,
+    categories: &[(String, Category)],
+    selection: &CategorySelection,
+    output: &LsRulesOutput,
+) {
+    println!("Dry run: {} ({} rules)\n", output.name, output.rules.len());
+    println!("Description:");
+    for line in output.description.lines() {
+        println!("  {}", line);
+    }
+
+    let (chosen, skipped): (Vec<_>, Vec<_>) =
+        categories.iter().partition(|(slug, _)| selection.action(slug).is_some());
+    let (by_severity, other): (Vec<_>, Vec<_>) = skipped
+        .into_iter()
+        .partition(|(slug, _)| matches!(selection.explain(slug), SelectionReason::OutsideSeverity { .. }));
+
+    let preview = SelectionPreview::new(params, categories, selection);
+    println!("\nSelected ({}):", chosen.len());
+    for (slug, _) in &chosen {
+        let action = selection.action(slug).map_or("skip", |action| action.as_str());
+        println!(
+            "  {:5} {:30} {:>4} rules  {}",
+            action,
+            slug,
+            preview.category(slug).map_or(0, |c| c.rule_count),
+            selection.explain(slug)
+        );
+    }
+
+    if !by_severity.is_empty() {
+        println!("\nSkipped by severity ({}):", by_severity.len());
+        for (slug, category) in &by_severity {
+            println!("  {:30} {}", slug, params.severity_for(slug, category));
+        }
+    }
+
+    if !other.is_empty() {
+        println!("\nNot selected ({}):", other.len());
+        for (slug, _) in &other {
+            println!("  {:30} {}", slug, selection.explain(slug));
+        }
+    }
+
+    println!("\nNothing written (--dry-run).");
+}
+
+/// Print the features the selection's rules will break, on stderr so it never mixes with `--output -`
+pub fn advise(params: &GenerateParams, categories: &[(String, Category)], selection: &CategorySelection) {
+    let features = BrokenFeature::for_selection(params, categories, selection);
+    if features.is_empty() {
+        eprintln!("This configuration breaks none of the features its categories list.");
+        return;
+    }
+    eprintln!("This configuration will break:");
+    for broken in &features {
+        eprintln!("  {:32} {}", broken.feature, broken.categories.join(", "));
+    }
+    eprintln!();
+}
@@ -0,0 +1,119 @@
+//! Verification that `deny-process` paths exist on the local machine, and resolving
+//! app bundles to the executable Little Snitch rules name.
+
+use apple_ecocide::Category;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A `deny-process` path that does not exist on this system
+pub struct MissingProcess<'a> {
+    pub slug: &'a str,
+    pub path: &'a str,
+    /// Similarly named files next to the missing path, likely renamed daemons
+    pub candidates: Vec<PathBuf>,
+}
+
+/// Result of checking every `deny-process` path in a set of categories
+pub struct ProcessReport<'a> {
+    pub total: usize,
+    pub missing: Vec<MissingProcess<'a>>,
+}
+
+/// Stat every `deny-process` path in the given categories
+pub fn verify<'a>(categories: impl IntoIterator<Item = &'a (String, Category)>) -> ProcessReport<'a> {
+    let mut report = ProcessReport {
+        total: 0,
+        missing: Vec::new(),
+    };
+
+    for (slug, category) in categories {
+        for path in category.rules.iter().filter_map(|r| r.deny_process.as_deref()) {
+            report.total += 1;
+            if !Path::new(path).exists() {
+                report.missing.push(MissingProcess {
+                    slug,
+                    path,
+                    candidates: renamed_candidates(Path::new(path)),
+                });
+            }
+        }
+    }
+
+    report
+}
+
+/// Verify the given categories and print a report, returning whether every path exists
+pub fn run<'a>(categories: impl IntoIterator<Item = &'a (String, Category)>) -> bool {
+    if !cfg!(target_os = "macos") {
+        eprintln!("Warning: not running on macOS; every process path will be reported missing");
+    }
+    print_report(&verify(categories), "")
+}
+
+/// Print a report, returning whether every path exists
+pub fn print_report(report: &ProcessReport, indent: &str) -> bool {
+    if report.missing.is_empty() {
+        println!(
+            "{}[ok]   All {} deny-process paths exist on this system",
+            indent, report.total
+        );
+        return true;
+    }
+
+    println!(
+        "{}[warn] {} of {} deny-process paths do not exist on this system:",
+        indent,
+        report.missing.len(),
+        report.total
+    );
+    for missing in &report.missing {
+        println!("{}         {:30} {}", indent, missing.slug, missing.path);
+        for candidate in &missing.candidates {
+            println!("{}         {:30}   possibly renamed to {}", indent, "", candidate.display());
+        }
+    }
+    false
+}
+
+/// The executable Little Snitch sees connecting for `path`
+///
+/// For an `.app` bundle this is `Contents/MacOS/` plus the `CFBundleExecutable` of
+/// its XML `Info.plist`, or the bundle name when the plist is missing or binary, as
+/// when generating on another machine. Other paths are returned unchanged.
+pub fn app_executable(path: &Path) -> String {
+    let Some(bundle) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".app")) else {
+        return path.display().to_string();
+    };
+    let executable = fs::read_to_string(path.join("Contents/Info.plist"))
+        .ok()
+        .and_then(|plist| plist_string(&plist, "CFBundleExecutable"))
+        .unwrap_or_else(|| bundle.to_string());
+    path.join("Contents/MacOS").join(executable).display().to_string()
+}
+
+/// The `<string>` value following `<key>key</key>` in an XML property list
+fn plist_string(plist: &str, key: &str) -> Option<String> {
+    let after_key = &plist[plist.find(&format!("<key>{}</key>", key))?..];
+    let value = &after_key[after_key.find("<string>")? + "<string>".len()..];
+    Some(value[..value.find("</string>")?].trim().to_string())
+}
+
+fn renamed_candidates(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(|n| n.to_str())) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut candidates: Vec<(f64, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let candidate = entry.file_name().into_string().ok()?;
+            let score = strsim::normalized_damerau_levenshtein(name, &candidate);
+            (score >= 0.7).then(|| (score, entry.path()))
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+    candidates.into_iter().take(3).map(|(_, path)| path).collect()
+}
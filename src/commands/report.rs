@@ -0,0 +1,221 @@
+//! Human-readable reports of a blocking policy (`--format report-md|report-html`).
+
+use apple_ecocide::{Category, CategorySelection, Domain, GenerateParams, LsRulesOutput};
+use std::fmt::Write;
+
+/// One selected category with the rules that apply on the target system
+struct Section<'a> {
+    slug: &'a str,
+    category: &'a Category,
+    action: &'static str,
+    processes: Vec<&'a str>,
+    domains: Vec<(&'a str, &'a [Domain])>,
+}
+
+fn sections<'a>(
+    selected: &[&'a (String, Category)],
+    selection: &CategorySelection,
+    params: &GenerateParams,
+) -> Vec<Section<'a>> {
+    selected
+        .iter()
+        .map(|(slug, category)| {
+            let denied = selection.denied.contains(slug);
+            let rules: Vec<_> = category.rules.iter().filter(|r| params.includes_rule(r)).collect();
+            Section {
+                slug,
+                category,
+                action: if denied { "deny" } else { "allow" },
+                processes: rules
+                    .iter()
+                    .filter_map(|r| if denied { &r.deny_process } else { &r.allow_process }.as_deref())
+                    .collect(),
+                domains: rules
+                    .iter()
+                    .filter(|r| !r.domains.is_empty())
+                    .map(|r| (r.notes.as_str(), r.domains.as_slice()))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn impact_lines(impact: &str) -> impl Iterator<Item = &str> {
+    impact
+        .lines()
+        .map(|l| l.trim().trim_start_matches("- ").trim())
+        .filter(|l| !l.is_empty())
+}
+
+/// Render a Markdown report of the selected categories and their rules
+pub fn markdown(
+    output: &LsRulesOutput,
+    selected: &[&(String, Category)],
+    selection: &CategorySelection,
+    params: &GenerateParams,
+) -> String {
+    let sections = sections(selected, selection, params);
+    let mut md = String::new();
+
+    let _ = writeln!(md, "# {}\n\n{}\n", output.name, output.description);
+    let _ = writeln!(md, "| Category | Action | Severity | Processes | Domains |");
+    let _ = writeln!(md, "|----------|--------|----------|-----------|---------|");
+    for s in &sections {
+        let domains: usize = s.domains.iter().map(|(_, d)| d.len()).sum();
+        let _ = writeln!(
+            md,
+            "| {} (`{}`) | {} | {} | {} | {} |",
+            s.category.name,
+            s.slug,
+            s.action,
+            s.category.severity,
+            s.processes.len(),
+            domains
+        );
+    }
+
+    for s in &sections {
+        let _ = writeln!(md, "\n## {} (`{}`)\n", s.category.name, s.slug);
+        let _ = writeln!(md, "**Action:** {} · **Severity:** {}\n", s.action, s.category.severity);
+        let _ = writeln!(md, "{}\n", s.category.description);
+
+        let impact: Vec<_> = impact_lines(&s.category.impact).collect();
+        if !impact.is_empty() {
+            let _ = writeln!(md, "**Impact:**\n");
+            for line in impact {
+                let _ = writeln!(md, "- {}", line);
+            }
+            md.push('\n');
+        }
+
+        if !s.processes.is_empty() {
+            let label = if s.action == "deny" { "Blocked" } else { "Allowed" };
+            let _ = writeln!(md, "**{} processes:**\n", label);
+            for process in &s.processes {
+                let _ = writeln!(md, "- `{}`", process);
+            }
+            md.push('\n');
+        }
+
+        if !s.domains.is_empty() {
+            let verb = if s.action == "deny" { "Blocked" } else { "Allowed" };
+            let _ = writeln!(md, "**{} domains:**\n", verb);
+            for (notes, domains) in &s.domains {
+                let _ = writeln!(md, "- {}", notes);
+                for domain in domains.iter() {
+                    let _ = writeln!(md, "  - `{}`", domain);
+                }
+            }
+        }
+    }
+
+    md
+}
+
+/// Render a standalone HTML report of the selected categories and their rules
+pub fn html(
+    output: &LsRulesOutput,
+    selected: &[&(String, Category)],
+    selection: &CategorySelection,
+    params: &GenerateParams,
+) -> String {
+    let sections = sections(selected, selection, params);
+    let mut html = String::new();
+
+    let _ = writeln!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>",
+        escape(&output.name)
+    );
+    let _ = writeln!(
+        html,
+        "<style>body{{font-family:-apple-system,sans-serif;max-width:60rem;margin:2rem auto;padding:0 1rem}}\
+         table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:.25rem .5rem;text-align:left}}\
+         code{{font-size:.9em}}.deny{{color:#b00}}.allow{{color:#070}}</style>\n</head>\n<body>"
+    );
+    let _ = writeln!(
+        html,
+        "<h1>{}</h1>\n<p>{}</p>",
+        escape(&output.name),
+        escape(&output.description)
+    );
+
+    let _ = writeln!(
+        html,
+        "<table>\n<tr><th>Category</th><th>Action</th><th>Severity</th><th>Processes</th><th>Domains</th></tr>"
+    );
+    for s in &sections {
+        let domains: usize = s.domains.iter().map(|(_, d)| d.len()).sum();
+        let _ = writeln!(
+            html,
+            "<tr><td><a href=\"#{}\">{}</a></td><td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(s.slug),
+            escape(&s.category.name),
+            s.action,
+            s.action,
+            s.category.severity,
+            s.processes.len(),
+            domains
+        );
+    }
+    let _ = writeln!(html, "</table>");
+
+    for s in &sections {
+        let _ = writeln!(
+            html,
+            "<h2 id=\"{}\">{} (<code>{}</code>)</h2>",
+            escape(s.slug),
+            escape(&s.category.name),
+            escape(s.slug)
+        );
+        let _ = writeln!(
+            html,
+            "<p><strong>Action:</strong> <span class=\"{}\">{}</span> · <strong>Severity:</strong> {}</p>\n<p>{}</p>",
+            s.action,
+            s.action,
+            s.category.severity,
+            escape(&s.category.description)
+        );
+
+        let impact: Vec<_> = impact_lines(&s.category.impact).collect();
+        if !impact.is_empty() {
+            let _ = writeln!(html, "<h3>Impact</h3>\n<ul>");
+            for line in impact {
+                let _ = writeln!(html, "<li>{}</li>", escape(line));
+            }
+            let _ = writeln!(html, "</ul>");
+        }
+
+        if !s.processes.is_empty() {
+            let label = if s.action == "deny" { "Blocked" } else { "Allowed" };
+            let _ = writeln!(html, "<h3>{} processes</h3>\n<ul>", label);
+            for process in &s.processes {
+                let _ = writeln!(html, "<li><code>{}</code></li>", escape(process));
+            }
+            let _ = writeln!(html, "</ul>");
+        }
+
+        if !s.domains.is_empty() {
+            let verb = if s.action == "deny" { "Blocked" } else { "Allowed" };
+            let _ = writeln!(html, "<h3>{} domains</h3>\n<ul>", verb);
+            for (notes, domains) in &s.domains {
+                let _ = writeln!(html, "<li>{}\n<ul>", escape(notes));
+                for domain in domains.iter() {
+                    let _ = writeln!(html, "<li><code>{}</code></li>", escape(domain));
+                }
+                let _ = writeln!(html, "</ul></li>");
+            }
+            let _ = writeln!(html, "</ul>");
+        }
+    }
+
+    let _ = writeln!(html, "</body>\n</html>");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,132 @@
+//! Integrity metadata for distributed rule files: content hashes in the description,
+//! detached `.sha256` checksums and, with the `sign` feature, minisign signatures.
+
+#[cfg(feature = "sign")]
+use anyhow::Context;
+use anyhow::Result;
+use apple_ecocide::{unix_time, Date, LsRulesOutput};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Start of the description line written by [`stamp`]
+const INTEGRITY_PREFIX: &str = "Integrity: ";
+
+/// Add the SHA-256 of the rules, and the generation time unless `reproducible`, to the description
+///
+/// The hash covers the `rules` array serialized as compact JSON, which
+/// `jq -j -c .rules <file> | shasum -a 256` reproduces. A line left by an earlier
+/// stamp is replaced, so parts split from a stamped output can be stamped again.
+pub fn stamp(output: &mut LsRulesOutput, reproducible: bool) -> Result<()> {
+    let hash = hex(&Sha256::digest(serde_json::to_vec(&output.rules)?));
+    let mut line = format!("{}sha256 {} of the rules", INTEGRITY_PREFIX, hash);
+    if let Some(secs) = unix_time().filter(|_| !reproducible) {
+        line.push_str(&format!(
+            ", generated {}T{:02}:{:02}:{:02}Z",
+            Date::from_unix(secs),
+            secs % 86_400 / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        ));
+    }
+
+    let mut description: Vec<&str> = output
+        .description
+        .lines()
+        .filter(|l| !l.starts_with(INTEGRITY_PREFIX))
+        .collect();
+    description.push(&line);
+    output.description = description.join("\n");
+    Ok(())
+}
+
+/// Write `<path>.sha256` for `content`, in the format `shasum -a 256 -c` checks
+pub fn write_checksum(path: &Path, content: &[u8]) -> Result<()> {
+    let line = format!("{}  {}\n", hex(&Sha256::digest(content)), file_name(path));
+    super::write::write_if_changed(&sidecar(path, "sha256"), line.as_bytes(), 0)?;
+    Ok(())
+}
+
+/// `path` with `extension` appended, e.g. `rules.lsrules.sha256`
+fn sidecar(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// An unencrypted minisign secret key, as written by `minisign -G -W`
+#[cfg(feature = "sign")]
+pub struct SigningKey {
+    key_id: [u8; 8],
+    keypair: ring::signature::Ed25519KeyPair,
+}
+
+#[cfg(feature = "sign")]
+impl SigningKey {
+    /// Read a minisign secret key file
+    pub fn load(path: &Path) -> Result<Self> {
+        use base64::engine::general_purpose::STANDARD;
+        use base64::Engine;
+        use blake2::{digest::consts::U32, Blake2b};
+
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read: {}", path.display()))?;
+        let invalid = || anyhow::anyhow!("{} is not a minisign secret key", path.display());
+        let encoded = content.lines().find(|l| !l.is_empty() && !l.starts_with("untrusted comment:")).ok_or_else(invalid)?;
+        let key = STANDARD.decode(encoded.trim()).map_err(|_| invalid())?;
+
+        // sig_alg, kdf_alg, chk_alg, kdf salt and limits, then key id, secret key and checksum
+        if key.len() != 158 || &key[..2] != b"Ed" || &key[4..6] != b"B2" {
+            return Err(invalid());
+        }
+        if &key[2..4] != b"\0\0" {
+            anyhow::bail!(
+                "{} is encrypted with a password; create a signing key without one (minisign -G -W)",
+                path.display()
+            );
+        }
+        let (key_id, secret, checksum) = (&key[54..62], &key[62..126], &key[126..158]);
+        let expected = Blake2b::<U32>::new().chain_update(&key[..2]).chain_update(key_id).chain_update(secret).finalize();
+        if expected.as_slice() != checksum {
+            return Err(invalid());
+        }
+
+        let keypair = ring::signature::Ed25519KeyPair::from_seed_and_public_key(&secret[..32], &secret[32..])
+            .map_err(|_| invalid())?;
+        Ok(SigningKey {
+            key_id: key_id.try_into().expect("8-byte key id"),
+            keypair,
+        })
+    }
+
+    /// Write `<path>.minisig`, a prehashed minisign signature of `content` that
+    /// `minisign -Vm <path> -p <public key>` verifies
+    pub fn write_signature(&self, path: &Path, content: &[u8]) -> Result<()> {
+        use base64::engine::general_purpose::STANDARD;
+        use base64::Engine;
+        use blake2::Blake2b512;
+
+        let mut signature = b"ED".to_vec();
+        signature.extend_from_slice(&self.key_id);
+        signature.extend_from_slice(self.keypair.sign(&Blake2b512::digest(content)).as_ref());
+
+        let trusted = format!("timestamp:{}\tfile:{}\thashed", unix_time().unwrap_or_default(), file_name(path));
+        let mut global = signature[10..].to_vec();
+        global.extend_from_slice(trusted.as_bytes());
+        let minisig = format!(
+            "untrusted comment: signature from apple-ecocide\n{}\ntrusted comment: {}\n{}\n",
+            STANDARD.encode(&signature),
+            trusted,
+            STANDARD.encode(self.keypair.sign(&global))
+        );
+        std::fs::write(sidecar(path, "minisig"), minisig)
+            .with_context(|| format!("Failed to write: {}", sidecar(path, "minisig").display()))
+    }
+}
@@ -0,0 +1,234 @@
+//! `apple-ecocide snapshot`: captures the current connections on macOS and
+//! reports which of them the selected rules would block.
+
+use super::check::resolve_addresses;
+use anyhow::{Context, Result};
+use apple_ecocide::{Action, Category, CategorySelection};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::process::Command;
+
+/// An open connection to a remote address, as listed by `lsof -i`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Connection {
+    pid: u32,
+    /// Command name as shown by `lsof`
+    command: String,
+    protocol: String,
+    remote: SocketAddr,
+}
+
+#[derive(Serialize)]
+struct Snapshot<'a> {
+    connections: usize,
+    processes: usize,
+    /// Category domains that resolved, whose addresses connections were matched against
+    resolved_domains: usize,
+    /// Connections the selected rules deny
+    blocked: Vec<Match<'a>>,
+    /// Connections the selected rules explicitly allow
+    allowed: Vec<Match<'a>>,
+    /// Connections belonging to categories the selection skips
+    unselected: Vec<Match<'a>>,
+    /// Connections no category matches
+    unmatched: usize,
+}
+
+#[derive(Serialize)]
+struct Match<'a> {
+    slug: &'a str,
+    pid: u32,
+    /// Executable path, or the command name when it could not be read
+    process: String,
+    protocol: String,
+    remote: String,
+    /// Category domains resolving to the remote address
+    domains: Vec<&'a str>,
+    /// Whether the category has a rule for the process itself
+    process_rule: bool,
+}
+
+/// Capture the current connections and print which ones `selection` would affect
+pub fn run(
+    categories: &[(String, Category)],
+    selection: &CategorySelection,
+    concurrency: usize,
+    json: bool,
+) -> Result<()> {
+    if !cfg!(target_os = "macos") {
+        anyhow::bail!("snapshot inspects live connections with lsof and is only available on macOS");
+    }
+
+    let connections = capture()?;
+    let paths = process_paths();
+
+    // Connections carry addresses, not hostnames, so category domains are resolved
+    // to find which addresses belong to them
+    let mut domains: Vec<&str> = categories
+        .iter()
+        .flat_map(|(_, category)| category.rules.iter().flat_map(|r| &r.domains))
+        .map(|domain| domain.as_str())
+        .collect();
+    domains.sort_unstable();
+    domains.dedup();
+    if !json {
+        eprintln!("Resolving {} category domains...", domains.len());
+    }
+    let resolved = resolve_addresses(&domains, concurrency);
+    let mut by_address: HashMap<IpAddr, BTreeSet<&str>> = HashMap::new();
+    for (domain, lookup) in &resolved {
+        for address in lookup.addresses() {
+            by_address.entry(*address).or_default().insert(domain);
+        }
+    }
+
+    let mut snapshot = Snapshot {
+        connections: connections.len(),
+        processes: connections.iter().map(|c| c.pid).collect::<BTreeSet<_>>().len(),
+        resolved_domains: resolved.values().filter(|lookup| !lookup.addresses().is_empty()).count(),
+        blocked: Vec::new(),
+        allowed: Vec::new(),
+        unselected: Vec::new(),
+        unmatched: 0,
+    };
+
+    for connection in &connections {
+        let process = paths.get(&connection.pid).cloned().unwrap_or_else(|| connection.command.clone());
+        let addressed = by_address.get(&connection.remote.ip());
+        let mut matched = false;
+
+        for (slug, category) in categories {
+            let domains: Vec<&str> = category
+                .rules
+                .iter()
+                .flat_map(|r| &r.domains)
+                .map(|domain| domain.as_str())
+                .filter(|domain| addressed.is_some_and(|found| found.contains(domain)))
+                .collect();
+            let process_rule = category
+                .rules
+                .iter()
+                .any(|r| [&r.deny_process, &r.allow_process].into_iter().flatten().any(|p| *p == process));
+            if domains.is_empty() && !process_rule {
+                continue;
+            }
+
+            matched = true;
+            let entry = Match {
+                slug,
+                pid: connection.pid,
+                process: process.clone(),
+                protocol: connection.protocol.clone(),
+                remote: connection.remote.to_string(),
+                domains,
+                process_rule,
+            };
+            match selection.action(slug) {
+                Some(Action::Deny) => snapshot.blocked.push(entry),
+                Some(Action::Allow) => snapshot.allowed.push(entry),
+                None => snapshot.unselected.push(entry),
+            }
+        }
+        if !matched {
+            snapshot.unmatched += 1;
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&snapshot)?);
+    } else {
+        print(&snapshot);
+    }
+    Ok(())
+}
+
+fn print(snapshot: &Snapshot) {
+    println!(
+        "{} connections from {} processes (matched against {} resolved category domains)",
+        snapshot.connections, snapshot.processes, snapshot.resolved_domains
+    );
+
+    for (title, matches) in [
+        ("Would be blocked", &snapshot.blocked),
+        ("Allowed by the rules", &snapshot.allowed),
+        ("In categories not selected", &snapshot.unselected),
+    ] {
+        if matches.is_empty() {
+            continue;
+        }
+        println!("\n{} ({}):", title, matches.len());
+        for m in matches {
+            let via = if m.process_rule {
+                "process rule".to_string()
+            } else {
+                m.domains.join(", ")
+            };
+            println!("  {:30} {:>6}  {} {} -> {}  ({})", m.slug, m.pid, m.process, m.protocol, m.remote, via);
+        }
+    }
+
+    println!("\n{} connections match no category.", snapshot.unmatched);
+    if snapshot.blocked.is_empty() {
+        println!("Nothing currently connected would be blocked.");
+    }
+}
+
+/// List open connections with a remote address
+fn capture() -> Result<Vec<Connection>> {
+    let output = Command::new("lsof")
+        .args(["-i", "-n", "-P", "-F", "pcPn"])
+        .output()
+        .context("Failed to run lsof")?;
+    // lsof exits 1 when some sockets could not be listed, e.g. those of other users
+    if !output.status.success() && output.stdout.is_empty() {
+        anyhow::bail!("lsof failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(parse_lsof(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `lsof -F pcPn` output: `p`, `c`, `P` and `n` fields, one per line, where
+/// `n` is `local->remote` for connected sockets
+fn parse_lsof(output: &str) -> Vec<Connection> {
+    let mut connections = BTreeSet::new();
+    let (mut pid, mut command, mut protocol) = (0, String::new(), String::new());
+
+    for line in output.lines() {
+        let Some(field) = line.chars().next() else {
+            continue;
+        };
+        let value = &line[field.len_utf8()..];
+        match field {
+            'p' => pid = value.parse().unwrap_or(0),
+            'c' => command = value.to_string(),
+            'P' => protocol = value.to_string(),
+            'n' => {
+                let remote = value.split_once("->").and_then(|(_, remote)| remote.parse::<SocketAddr>().ok());
+                if let Some(remote) = remote.filter(|remote| !remote.ip().is_loopback()) {
+                    connections.insert(Connection {
+                        pid,
+                        command: command.clone(),
+                        protocol: protocol.clone(),
+                        remote,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    connections.into_iter().collect()
+}
+
+/// Executable path of every running process, keyed by pid
+fn process_paths() -> HashMap<u32, String> {
+    let Ok(output) = Command::new("ps").args(["-axo", "pid=,comm="]).output() else {
+        return HashMap::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, path) = line.trim().split_once(' ')?;
+            Some((pid.parse().ok()?, path.trim().to_string()))
+        })
+        .collect()
+}
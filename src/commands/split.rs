@@ -0,0 +1,46 @@
+//! Splitting the selected categories into separate rule groups (`--split-by`).
+
+use apple_ecocide::{Category, CategorySelection, GenerateParams};
+use std::collections::BTreeMap;
+
+/// How the selection is split into files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitBy {
+    /// One file per category
+    Category,
+    /// One file per vendor, the slug prefix before the first '-' (e.g. `google`)
+    Vendor,
+    /// One file per (effective) severity level
+    Severity,
+}
+
+/// Group the selected categories, keyed by the file stem each group is written to
+pub fn groups(
+    params: &GenerateParams,
+    categories: &[(String, Category)],
+    selection: &CategorySelection,
+    by: SplitBy,
+) -> BTreeMap<String, CategorySelection> {
+    let mut groups: BTreeMap<String, CategorySelection> = BTreeMap::new();
+
+    for (slug, category) in categories {
+        let denied = selection.denied.contains(slug);
+        if !denied && !selection.allowed.contains(slug) {
+            continue;
+        }
+
+        let key = match by {
+            SplitBy::Category => slug.replace('/', "-"),
+            SplitBy::Vendor => slug.split(['/', '-']).next().unwrap_or(slug).to_string(),
+            SplitBy::Severity => params.severity_for(slug, category).as_str().to_string(),
+        };
+        let group = groups.entry(key).or_default();
+        if denied {
+            group.denied.insert(slug.clone());
+        } else {
+            group.allowed.insert(slug.clone());
+        }
+    }
+
+    groups
+}
@@ -0,0 +1,39 @@
+//! `apple-ecocide stale`: categories whose rules have not been verified recently.
+
+use anyhow::Result;
+use apple_ecocide::{Category, Date};
+
+/// Print the selected categories not verified within `days`, returning whether all are fresh
+///
+/// Categories without a `last-verified` date are always reported.
+pub fn run(selected: &[&(String, Category)], days: i64) -> Result<bool> {
+    let today = Date::today().ok_or_else(|| anyhow::anyhow!("The current date is unavailable"))?;
+
+    let stale: Vec<(&str, Option<(Date, i64)>)> = selected
+        .iter()
+        .filter_map(|(slug, category)| match category.last_verified {
+            Some(date) if date.days_until(today) <= days => None,
+            Some(date) => Some((slug.as_str(), Some((date, date.days_until(today))))),
+            None => Some((slug.as_str(), None)),
+        })
+        .collect();
+
+    if stale.is_empty() {
+        println!("[ok]   All {} categories were verified within {} days", selected.len(), days);
+        return Ok(true);
+    }
+
+    println!(
+        "[warn] {} of {} categories were not verified within {} days:",
+        stale.len(),
+        selected.len(),
+        days
+    );
+    for (slug, verified) in stale {
+        match verified {
+            Some((date, age)) => println!("         {:30} last verified {} ({} days ago)", slug, date, age),
+            None => println!("         {:30} never verified", slug),
+        }
+    }
+    Ok(false)
+}
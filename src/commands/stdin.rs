@@ -0,0 +1,122 @@
+//! Reading category files piped in on stdin (`--categories -`).
+
+use apple_ecocide::{parse_category, slug_for_path, Category, CategorySource, Error, Result};
+use std::io::{self, Read};
+
+/// Categories piped in on stdin, either as a tar archive or a stream of TOML files
+///
+/// A TOML stream separates files with `==> name.toml <==` headers, the format
+/// printed by `tail -n +1 *.toml`. Slugs come from the file paths, relative to the
+/// top-level directory when every file shares one (as `tar -c categories` produces).
+pub struct StdinSource;
+
+impl CategorySource for StdinSource {
+    fn load(&self) -> Result<Vec<(String, Category)>> {
+        let mut input = Vec::new();
+        io::stdin().read_to_end(&mut input).map_err(stdin_error)?;
+
+        let files = if is_tar(&input) {
+            read_tar(&input)?
+        } else {
+            let text = String::from_utf8(input).map_err(|_| invalid("categories are not valid UTF-8"))?;
+            read_stream(&text)?
+        };
+
+        if files.is_empty() {
+            return Err(invalid("no categories found"));
+        }
+        let mut categories = with_slugs(files);
+        categories.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(categories)
+    }
+
+    fn describe(&self) -> String {
+        "from stdin".into()
+    }
+}
+
+fn stdin_error(source: io::Error) -> Error {
+    Error::Io {
+        path: "stdin".into(),
+        source,
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    stdin_error(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+fn is_tar(input: &[u8]) -> bool {
+    input.get(257..262) == Some(b"ustar")
+}
+
+fn read_tar(input: &[u8]) -> Result<Vec<(String, Category)>> {
+    let mut categories = Vec::new();
+    let mut archive = tar::Archive::new(input);
+
+    for entry in archive.entries().map_err(stdin_error)? {
+        let mut entry = entry.map_err(stdin_error)?;
+        let path = entry.path().map_err(stdin_error)?.into_owned();
+        if !entry.header().entry_type().is_file() || path.extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+        let mut content = String::new();
+        entry.read_to_string(&mut content).map_err(|source| Error::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let category = parse_category(&path.display().to_string(), &content)?;
+        categories.push((path.to_string_lossy().into_owned(), category));
+    }
+
+    Ok(categories)
+}
+
+fn read_stream(text: &str) -> Result<Vec<(String, Category)>> {
+    let mut files: Vec<(&str, String)> = Vec::new();
+
+    for line in text.lines() {
+        if let Some(name) = header_name(line) {
+            files.push((name, String::new()));
+        } else if let Some((_, content)) = files.last_mut() {
+            content.push_str(line);
+            content.push('\n');
+        } else if !line.trim().is_empty() {
+            return Err(invalid("expected a '==> name.toml <==' header before category content"));
+        }
+    }
+
+    files
+        .into_iter()
+        .map(|(name, content)| {
+            let category = parse_category(name, &content)?;
+            Ok((name.to_string(), category))
+        })
+        .collect()
+}
+
+fn header_name(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix("==>")?
+        .strip_suffix("<==")
+        .map(str::trim)
+}
+
+/// Replace file paths with slugs, dropping a top-level directory shared by every file
+fn with_slugs(files: Vec<(String, Category)>) -> Vec<(String, Category)> {
+    let slugs: Vec<String> = files.iter().map(|(path, _)| slug_for_path(path)).collect();
+    let root = slugs
+        .first()
+        .and_then(|slug| slug.split_once('/'))
+        .map(|(root, _)| format!("{}/", root))
+        .filter(|root| slugs.iter().all(|slug| slug.starts_with(root.as_str())));
+
+    slugs
+        .into_iter()
+        .zip(files)
+        .map(|(slug, (_, category))| match &root {
+            Some(root) => (slug[root.len()..].to_string(), category),
+            None => (slug, category),
+        })
+        .collect()
+}
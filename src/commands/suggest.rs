@@ -0,0 +1,176 @@
+//! `apple-ecocide suggest --from-log`: matches connections from a Little Snitch
+//! traffic log against the categories to suggest which ones to enable.
+
+use super::import::read_source;
+use super::traffic::parse_log;
+use anyhow::Result;
+use apple_ecocide::{Action, Category, CategorySelection, DomainIndex, Severity};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Connections to one host in the log
+#[derive(Debug, Default, Clone, Copy)]
+struct Traffic {
+    connections: u64,
+    denied: u64,
+}
+
+impl Traffic {
+    fn add(&mut self, other: Traffic) {
+        self.connections += other.connections;
+        self.denied += other.denied;
+    }
+}
+
+#[derive(Serialize)]
+struct Suggestions<'a> {
+    log: &'a str,
+    /// Log entries with a hostname
+    entries: usize,
+    /// Log entries without one, e.g. connections to bare IP addresses
+    skipped: usize,
+    connections: u64,
+    hosts: usize,
+    /// Categories covering at least one logged host, most connections first
+    categories: Vec<CategoryTraffic<'a>>,
+    /// Seen categories the current selection does not use, as `--include` arguments
+    suggested: Vec<&'a str>,
+    /// Logged hosts no category covers, most connections first
+    uncategorized: Vec<HostTraffic>,
+}
+
+#[derive(Serialize)]
+struct CategoryTraffic<'a> {
+    slug: &'a str,
+    name: &'a str,
+    severity: Severity,
+    impact: &'a str,
+    /// What the current selection does with the category, if it is selected
+    action: Option<Action>,
+    connections: u64,
+    denied: u64,
+    hosts: BTreeSet<String>,
+}
+
+#[derive(Serialize)]
+struct HostTraffic {
+    host: String,
+    connections: u64,
+    denied: u64,
+}
+
+/// Print the categories covering the hosts in the traffic log at `source`
+pub fn run(categories: &[(String, Category)], selection: &CategorySelection, source: &str, json: bool) -> Result<()> {
+    let content = read_source(source)?;
+    let log = parse_log(&content);
+    let entries: Vec<_> = log.entries.iter().filter(|entry| entry.host.is_some()).collect();
+    let skipped = log.entries.len() - entries.len();
+    if entries.is_empty() {
+        anyhow::bail!(
+            "No hostnames found in {}. Export the log with `littlesnitch log-traffic > traffic.csv`",
+            source
+        );
+    }
+
+    let mut hosts: BTreeMap<String, Traffic> = BTreeMap::new();
+    for entry in &entries {
+        let traffic = Traffic {
+            connections: entry.connections,
+            denied: entry.denied,
+        };
+        hosts.entry(entry.host.clone().unwrap_or_default()).or_default().add(traffic);
+    }
+
+    let index = DomainIndex::new(categories);
+    let mut seen: BTreeMap<&str, CategoryTraffic> = BTreeMap::new();
+    let mut uncategorized = Vec::new();
+    for (host, traffic) in &hosts {
+        let slugs = index.lookup(host);
+        if slugs.is_empty() {
+            uncategorized.push(HostTraffic {
+                host: host.clone(),
+                connections: traffic.connections,
+                denied: traffic.denied,
+            });
+        }
+        for slug in slugs {
+            let Some((slug, category)) = categories.iter().find(|(s, _)| s == slug) else {
+                continue;
+            };
+            let entry = seen.entry(slug).or_insert_with(|| CategoryTraffic {
+                slug,
+                name: &category.name,
+                severity: category.severity,
+                impact: &category.impact,
+                action: selection.action(slug),
+                connections: 0,
+                denied: 0,
+                hosts: BTreeSet::new(),
+            });
+            entry.connections += traffic.connections;
+            entry.denied += traffic.denied;
+            entry.hosts.insert(host.clone());
+        }
+    }
+
+    let mut seen: Vec<CategoryTraffic> = seen.into_values().collect();
+    seen.sort_by(|a, b| b.connections.cmp(&a.connections).then(a.slug.cmp(b.slug)));
+    uncategorized.sort_by(|a, b| b.connections.cmp(&a.connections).then(a.host.cmp(&b.host)));
+
+    let suggestions = Suggestions {
+        log: source,
+        entries: entries.len(),
+        skipped,
+        connections: hosts.values().map(|t| t.connections).sum(),
+        hosts: hosts.len(),
+        suggested: seen.iter().filter(|c| c.action.is_none()).map(|c| c.slug).collect(),
+        categories: seen,
+        uncategorized,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&suggestions)?);
+    } else {
+        print(&suggestions);
+    }
+    Ok(())
+}
+
+fn print(suggestions: &Suggestions) {
+    println!(
+        "{} connections to {} hosts in {}",
+        suggestions.connections, suggestions.hosts, suggestions.log
+    );
+    if suggestions.skipped > 0 {
+        println!("Skipped {} entries without a hostname", suggestions.skipped);
+    }
+
+    if suggestions.categories.is_empty() {
+        println!("\nNo logged host belongs to a category.");
+    } else {
+        println!("\nCategories seen in the log ({}):", suggestions.categories.len());
+        for category in &suggestions.categories {
+            let action = category.action.map_or("-", |action| action.as_str());
+            println!(
+                "  {:5} {:30} {:>7} connections  {:>3} hosts  {}",
+                action,
+                category.slug,
+                category.connections,
+                category.hosts.len(),
+                category.severity
+            );
+        }
+    }
+
+    if !suggestions.suggested.is_empty() {
+        println!("\nNot selected yet; review their impact with --list -v, then enable them with:");
+        println!("  --include {}", suggestions.suggested.join(" "));
+    }
+
+    if !suggestions.uncategorized.is_empty() {
+        println!("\nHosts no category covers ({}):", suggestions.uncategorized.len());
+        for host in &suggestions.uncategorized {
+            println!("  {:>7}  {}", host.connections, host.host);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Run summaries printed after generating rules.
+
+use apple_ecocide::{CategorySelection, LsRulesOutput};
+use serde::Serialize;
+
+/// What happened to the generated output
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Written,
+    Unchanged,
+    /// `--check` found the file out of date and left it untouched
+    Outdated,
+    Stdout,
+    Clipboard,
+}
+
+/// Machine-readable summary of a generation run (`--json-summary`)
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub status: Status,
+    pub output: String,
+    pub categories: SelectedCategories,
+    pub rules: RuleCounts,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SelectedCategories {
+    pub denied: Vec<String>,
+    pub allowed: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleCounts {
+    pub total: usize,
+    pub allow: usize,
+    pub deny: usize,
+}
+
+impl RunSummary {
+    pub fn new(
+        status: Status,
+        output: String,
+        rules: &LsRulesOutput,
+        selection: &CategorySelection,
+        warnings: Vec<String>,
+    ) -> Self {
+        let mut denied: Vec<String> = selection.denied.iter().cloned().collect();
+        let mut allowed: Vec<String> = selection.allowed.iter().cloned().collect();
+        denied.sort();
+        allowed.sort();
+
+        let allow = rules.rules.iter().filter(|r| r.action == "allow").count();
+        RunSummary {
+            status,
+            output,
+            categories: SelectedCategories { denied, allowed },
+            rules: RuleCounts {
+                total: rules.rules.len(),
+                allow,
+                deny: rules.rules.len() - allow,
+            },
+            warnings,
+        }
+    }
+
+    /// The one-line human summary
+    pub fn message(&self) -> String {
+        match self.status {
+            Status::Unchanged => {
+                return format!("Unchanged {} ({} rules), not rewritten", self.output, self.rules.total);
+            }
+            Status::Outdated => {
+                return format!("Outdated {}, regenerating would write {} rules", self.output, self.rules.total);
+            }
+            Status::Clipboard => {
+                return format!("Copied {} rules to the clipboard", self.rules.total);
+            }
+            Status::Written | Status::Stdout => {}
+        }
+
+        let total_categories = self.categories.denied.len() + self.categories.allowed.len();
+        if self.categories.allowed.is_empty() {
+            format!(
+                "Generated {} with {} rules ({} deny) from {} categories",
+                self.output, self.rules.total, self.rules.deny, total_categories
+            )
+        } else {
+            format!(
+                "Generated {} with {} rules ({} allow, {} deny) from {} categories",
+                self.output, self.rules.total, self.rules.allow, self.rules.deny, total_categories
+            )
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Little Snitch traffic logs, as exported with `littlesnitch log-traffic`.
+
+use apple_ecocide::{Domain, DomainSyntax};
+
+/// Header names of the columns read from CSV logs, as written by
+/// `littlesnitch log-traffic` and by spreadsheet exports of it
+const HOST_COLUMNS: &[&str] = &["remotehostname", "remote hostname", "remote host", "hostname", "host", "domain"];
+const PROCESS_COLUMNS: &[&str] = &["connectingexecutable", "connecting executable", "executable", "process", "path"];
+const COUNT_COLUMNS: &[&str] = &["connectcount", "connect count", "connections", "count"];
+const DENIED_COLUMNS: &[&str] = &["denycount", "deny count", "denied"];
+
+/// One line of a traffic log
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// Remote hostname, if the connection had one
+    pub host: Option<String>,
+    /// Executable that made the connection
+    pub process: Option<String>,
+    /// Connection attempts
+    pub connections: u64,
+    /// Attempts Little Snitch denied
+    pub denied: u64,
+}
+
+/// A parsed traffic log
+pub struct TrafficLog {
+    pub entries: Vec<LogEntry>,
+    /// Whether the log records denied attempts; without it `denied` is always 0
+    pub records_denials: bool,
+}
+
+/// Parse a traffic log
+///
+/// Reads the CSV written by `littlesnitch log-traffic` (or any CSV or TSV with a
+/// hostname column), and otherwise takes the first hostname on each line, which
+/// covers plain host lists and text logs.
+pub fn parse_log(content: &str) -> TrafficLog {
+    let syntax = DomainSyntax { allow_underscores: true };
+    let host = |value: &str| {
+        Domain::parse_with(value.trim().trim_matches('"'), syntax)
+            .ok()
+            .map(|domain| domain.as_str().to_string())
+    };
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+
+    let header = lines.next().unwrap_or_default();
+    let delimiter = if header.contains('\t') { '\t' } else { ',' };
+    let columns: Vec<String> = header
+        .split(delimiter)
+        .map(|c| c.trim().trim_matches('"').to_lowercase())
+        .collect();
+    let column = |names: &[&str]| columns.iter().position(|c| names.contains(&c.as_str()));
+
+    let Some(host_column) = column(HOST_COLUMNS) else {
+        let entries = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| LogEntry {
+                host: line.split([' ', '\t', ',', ';']).find_map(host),
+                process: None,
+                connections: 1,
+                denied: 0,
+            })
+            .collect();
+        return TrafficLog {
+            entries,
+            records_denials: false,
+        };
+    };
+    let process_column = column(PROCESS_COLUMNS);
+    let count_column = column(COUNT_COLUMNS);
+    let denied_column = column(DENIED_COLUMNS);
+
+    let entries = lines
+        .map(|line| {
+            let fields: Vec<&str> = line.split(delimiter).map(|value| value.trim().trim_matches('"')).collect();
+            let number = |index: Option<usize>, default| {
+                index
+                    .and_then(|i| fields.get(i))
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(default)
+            };
+            LogEntry {
+                host: fields.get(host_column).and_then(|value| host(value)),
+                process: process_column
+                    .and_then(|i| fields.get(i))
+                    .filter(|value| !value.is_empty())
+                    .map(|value| value.to_string()),
+                connections: number(count_column, 1),
+                denied: number(denied_column, 0),
+            }
+        })
+        .collect();
+    TrafficLog {
+        entries,
+        records_denials: denied_column.is_some(),
+    }
+}
@@ -0,0 +1,220 @@
+//! `apple-ecocide update`: checks for and installs newer category data without
+//! reinstalling the binary.
+
+use anyhow::{Context, Result};
+use apple_ecocide::{load_changelog, parse_changelog, releases_since, CategorySource, DirectorySource, Release};
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// The published changelog, whose newest release is the latest category data
+pub const RELEASES_URL: &str = "https://raw.githubusercontent.com/bobstanton/apple-ecocide/main/data/changelog.toml";
+
+/// The published category bundle: a tar archive of `categories/` and `changelog.toml`
+pub const BUNDLE_URL: &str = "https://bobstanton.github.io/apple-ecocide/categories.tar";
+
+#[derive(Serialize)]
+struct UpdateCheck<'a> {
+    /// Category data release this binary embeds
+    embedded: Version<'a>,
+    /// Release installed by `update --fetch`, if any
+    fetched: Option<Version<'a>>,
+    /// Newest published release
+    latest: Version<'a>,
+    /// Whether newer category data than the embedded and fetched ones was published
+    stale: bool,
+    /// Published releases newer than the data in use, newest first
+    releases: Vec<&'a Release>,
+}
+
+#[derive(Serialize)]
+struct Version<'a> {
+    version: &'a str,
+    date: &'a str,
+}
+
+impl<'a> From<&'a Release> for Version<'a> {
+    fn from(release: &'a Release) -> Self {
+        Version {
+            version: &release.version,
+            date: &release.date,
+        }
+    }
+}
+
+/// Compare the category data in use with the changelog published at `url`
+///
+/// The data in use is the embedded data, or the fetched bundle when that is newer.
+/// Returns whether it is stale.
+pub fn check(url: &str, json: bool) -> Result<bool> {
+    let embedded = load_changelog()?;
+    let embedded = embedded.first().context("The embedded changelog lists no releases")?;
+    let fetched = fetched_bundle();
+    let fetched = fetched.as_ref().map(|(_, release)| release);
+    let current = match fetched {
+        Some(release) if is_newer(release, embedded) => release,
+        _ => embedded,
+    };
+
+    let context = || format!("Failed to fetch release metadata from {}", url);
+    let content = ureq::get(url).call().with_context(context)?.into_string().with_context(context)?;
+    let published = parse_changelog(&content).with_context(|| format!("Invalid release metadata from {}", url))?;
+    let latest = published.first().with_context(|| format!("No releases listed at {}", url))?;
+    let releases = releases_since(&published, &current.version)?;
+
+    let result = UpdateCheck {
+        embedded: embedded.into(),
+        fetched: fetched.map(Version::from),
+        latest: latest.into(),
+        stale: !releases.is_empty(),
+        releases,
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(result.stale);
+    }
+
+    let origin = if std::ptr::eq(current, embedded) { "embeds" } else { "uses fetched categories" };
+    if !result.stale {
+        println!("Categories are up to date ({}, {})", current.version, current.date);
+        return Ok(false);
+    }
+    println!(
+        "This binary ships stale categories: it {} {} ({}), but {} ({}) is available.\n",
+        origin, current.version, current.date, latest.version, latest.date
+    );
+    for release in &result.releases {
+        super::changelog::print_release(release);
+    }
+    println!("Run `apple-ecocide update --fetch` or install the latest release to get the new categories.");
+    Ok(true)
+}
+
+/// Download the category bundle at `url` into the user data directory
+///
+/// The bundle is validated before it replaces a previously fetched one, so a
+/// broken download never leaves unusable categories behind.
+pub fn fetch(url: &str) -> Result<()> {
+    let dir = data_dir().context("No user data directory found; set APPLE_ECOCIDE_DATA_DIR")?;
+    let context = || format!("Failed to download categories from {}", url);
+    let mut archive = Vec::new();
+    ureq::get(url)
+        .call()
+        .with_context(context)?
+        .into_reader()
+        .read_to_end(&mut archive)
+        .with_context(context)?;
+
+    let staging = dir.join("bundle.new");
+    if staging.exists() {
+        fs::remove_dir_all(&staging).with_context(|| format!("Failed to remove: {}", staging.display()))?;
+    }
+    fs::create_dir_all(&staging).with_context(|| format!("Failed to create: {}", staging.display()))?;
+    let unpacked = unpack(&archive, &staging).and_then(|()| read_bundle(&staging));
+    let (categories, release) = match unpacked {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e.context(format!("Invalid category bundle from {}", url)));
+        }
+    };
+    let bundle = dir.join("bundle");
+    if bundle.exists() {
+        fs::remove_dir_all(&bundle).with_context(|| format!("Failed to remove: {}", bundle.display()))?;
+    }
+    fs::rename(&staging, &bundle).with_context(|| format!("Failed to write: {}", bundle.display()))?;
+
+    println!(
+        "Installed category data {} ({}, {} categories) in {}",
+        release.version,
+        release.date,
+        categories,
+        bundle.display()
+    );
+    let embedded = load_changelog()?;
+    match embedded.first() {
+        Some(embedded) if !is_newer(&release, embedded) => println!(
+            "The embedded categories ({}) are as new, so they stay in use until a newer bundle is fetched.",
+            embedded.version
+        ),
+        _ => println!("Later runs use it instead of the embedded categories; pass --embedded-only to opt out."),
+    }
+    Ok(())
+}
+
+/// Categories directory of the fetched bundle, when it is newer than the embedded data
+///
+/// Without embedded data (`no-embed` builds) any fetched bundle is used.
+pub fn fetched_categories() -> Option<PathBuf> {
+    let (dir, release) = fetched_bundle()?;
+    let newer = cfg!(feature = "no-embed")
+        || load_changelog()
+            .ok()
+            .and_then(|embedded| embedded.first().map(|embedded| is_newer(&release, embedded)))
+            .unwrap_or(true);
+    newer.then(|| dir.join("categories"))
+}
+
+/// The installed bundle directory and its newest release
+fn fetched_bundle() -> Option<(PathBuf, Release)> {
+    let dir = data_dir()?.join("bundle");
+    let changelog = fs::read_to_string(dir.join("changelog.toml")).ok()?;
+    let release = parse_changelog(&changelog).ok()?.into_iter().next()?;
+    Some((dir, release))
+}
+
+fn is_newer(release: &Release, than: &Release) -> bool {
+    releases_since(std::slice::from_ref(release), &than.version).is_ok_and(|newer| !newer.is_empty())
+}
+
+/// Write the bundle's `changelog.toml` and `categories/**/*.toml` files into `dir`
+fn unpack(archive: &[u8], dir: &Path) -> Result<()> {
+    for entry in tar::Archive::new(archive).entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let safe = path.components().all(|c| matches!(c, Component::Normal(_)));
+        let wanted = path == Path::new("changelog.toml")
+            || (path.starts_with("categories") && path.extension().is_some_and(|ext| ext == "toml"));
+        if !entry.header().entry_type().is_file() || !safe || !wanted {
+            continue;
+        }
+
+        let target = dir.join(&path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        fs::write(&target, content).with_context(|| format!("Failed to write: {}", target.display()))?;
+    }
+    Ok(())
+}
+
+/// Load an unpacked bundle, returning its category count and newest release
+fn read_bundle(dir: &Path) -> Result<(usize, Release)> {
+    let changelog = fs::read_to_string(dir.join("changelog.toml")).context("The bundle has no changelog.toml")?;
+    let release = parse_changelog(&changelog)?.into_iter().next().context("The bundle's changelog lists no releases")?;
+    let categories = DirectorySource::new(dir.join("categories")).load()?;
+    if categories.is_empty() {
+        anyhow::bail!("The bundle has no categories");
+    }
+    Ok((categories.len(), release))
+}
+
+/// Where fetched category data is kept: `APPLE_ECOCIDE_DATA_DIR`, otherwise
+/// `~/Library/Application Support/apple-ecocide` on macOS and
+/// `$XDG_DATA_HOME/apple-ecocide` (`~/.local/share/apple-ecocide`) elsewhere
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("APPLE_ECOCIDE_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    if cfg!(target_os = "macos") {
+        return home.map(|home| home.join("Library/Application Support/apple-ecocide"));
+    }
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| home.join(".local/share")))
+        .map(|dir| dir.join("apple-ecocide"))
+}
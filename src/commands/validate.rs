@@ -0,0 +1,107 @@
+//! `apple-ecocide validate`: lint category files, e.g. in CI for contributions.
+
+use anyhow::Result;
+use apple_ecocide::Category;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Longest rule note accepted in strict mode, keeping Little Snitch's rule list readable
+const MAX_NOTES_LEN: usize = 100;
+
+/// A problem found in a category file
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub slug: String,
+    /// 1-based index of the `[[rules]]` entry, if the finding concerns one rule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<usize>,
+    /// Stable identifier of the check, e.g. `unsorted-domains`
+    pub check: &'static str,
+    pub message: String,
+}
+
+/// Lint every category; `strict` adds the formatting checks expected of contributions
+///
+/// Missing fields such as `severity` and invalid domains are already rejected when
+/// the files are parsed; domains that had to be normalized are reported.
+pub fn findings(categories: &[(String, Category)], strict: bool) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut push = |slug: &str, rule: Option<usize>, check, message: String| {
+        findings.push(Finding {
+            slug: slug.to_string(),
+            rule,
+            check,
+            message,
+        });
+    };
+    let mut seen: BTreeMap<&str, &str> = BTreeMap::new();
+
+    for (slug, category) in categories {
+        for (original, normalized) in &category.normalized {
+            if original.contains("://") {
+                push(slug, None, "url-domain", format!("'{}' is a URL; its host '{}' is used", original, normalized));
+            } else {
+                push(slug, None, "normalized-domain", format!("'{}' was normalized to '{}'", original, normalized));
+            }
+        }
+        if strict && category.impact.trim().is_empty() {
+            push(slug, None, "empty-impact", "impact is empty".into());
+        }
+
+        let mut in_category = HashSet::new();
+        for (i, rule) in category.rules.iter().enumerate() {
+            let index = Some(i + 1);
+            if rule.domains.is_empty()
+                && rule.deny_process.is_none()
+                && rule.allow_process.is_none()
+                && rule.remote.is_none()
+            {
+                push(slug, index, "empty-rule", "rule has no domains, process, or remote".into());
+            }
+
+            if strict && rule.notes.trim().is_empty() {
+                push(slug, index, "notes-length", "notes are empty".into());
+            } else if strict && rule.notes.chars().count() > MAX_NOTES_LEN {
+                push(slug, index, "notes-length", format!("notes are longer than {} characters", MAX_NOTES_LEN));
+            }
+
+            if strict && rule.domains.windows(2).any(|pair| pair[0] > pair[1]) {
+                push(slug, index, "unsorted-domains", "domains are not sorted".into());
+            }
+
+            for domain in &rule.domains {
+                if !in_category.insert(domain.as_str()) {
+                    push(slug, index, "duplicate-domain", format!("'{}' is listed more than once", domain));
+                } else if let Some(other) = seen.get(domain.as_str()).filter(|_| strict) {
+                    push(slug, index, "duplicate-domain", format!("'{}' is also listed in {}", domain, other));
+                } else {
+                    seen.entry(domain.as_str()).or_insert(slug);
+                }
+            }
+        }
+    }
+
+    findings
+}
+
+/// Print the findings as text or JSON, returning whether there were none
+pub fn run(categories: &[(String, Category)], strict: bool, json: bool) -> Result<bool> {
+    let findings = findings(categories, strict);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&findings)?);
+    } else if findings.is_empty() {
+        println!("[ok]   {} categories passed validation", categories.len());
+    } else {
+        println!("[warn] {} problems found:", findings.len());
+        for finding in &findings {
+            let location = match finding.rule {
+                Some(rule) => format!("{} (rule {})", finding.slug, rule),
+                None => finding.slug.clone(),
+            };
+            println!("         {:40} {:18} {}", location, finding.check, finding.message);
+        }
+    }
+
+    Ok(findings.is_empty())
+}
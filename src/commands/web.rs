@@ -0,0 +1,436 @@
+//! `apple-ecocide web`: serves the web UI and a JSON API on localhost.
+
+use anyhow::Result;
+use apple_ecocide::{
+    generate_from_categories, get_category_info, localize_categories, select_categories, Answers, Category, Domain, GenerateParams, LsVersion,
+    NotesStyle, OutputFormat, QUESTIONS, SearchIndex, SelectionDiff, SelectionPreview,
+};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+const INDEX_HTML: &str = include_str!("../../web/index.html");
+
+/// Stand-in for the wasm-pack module that answers the UI's calls from the JSON API,
+/// so the UI works without building the WebAssembly package
+const API_MODULE: &str = r#"// Served by `apple-ecocide web`: the generator runs in the local CLI process.
+function request(path, body) {
+    const xhr = new XMLHttpRequest();
+    xhr.open(body === undefined ? 'GET' : 'POST', path, false);
+    xhr.send(body);
+    if (xhr.status !== 200) {
+        throw new Error(JSON.parse(xhr.responseText).error);
+    }
+    return xhr;
+}
+
+function call(path, body) {
+    return request(path, body).responseText;
+}
+
+export default async function init() {}
+
+let locale = '';
+
+export function set_locale(lang) {
+    locale = lang;
+}
+
+export function generate_rules(params) {
+    const json = call('/api/generate?lang=' + encodeURIComponent(locale), JSON.stringify(params));
+    return { output: JSON.parse(json), json };
+}
+
+export function generate(format, params) {
+    return call('/api/generate?format=' + encodeURIComponent(format) + '&lang=' + encodeURIComponent(locale), JSON.stringify(params));
+}
+
+export function download(format, params) {
+    const xhr = request('/api/generate?format=' + encodeURIComponent(format) + '&lang=' + encodeURIComponent(locale), JSON.stringify(params));
+    const filename = /filename="(.*)"/.exec(xhr.getResponseHeader('Content-Disposition'))[1];
+    return {
+        bytes: new TextEncoder().encode(xhr.responseText),
+        filename: decodeURIComponent(filename),
+        mime_type: xhr.getResponseHeader('Content-Type').split(';')[0],
+    };
+}
+
+export function explain_selection(params) {
+    return JSON.parse(call('/api/explain?lang=' + encodeURIComponent(locale), JSON.stringify(params)));
+}
+
+export function preview_selection(params) {
+    return JSON.parse(call('/api/preview?lang=' + encodeURIComponent(locale), JSON.stringify(params)));
+}
+
+export function add_custom_category() {
+    throw new Error('Custom categories need the WebAssembly build: restart with --pkg web/pkg, or pass --categories');
+}
+
+export function questions() {
+    return JSON.parse(call('/api/questions'));
+}
+
+export function params_from_answers(answers) {
+    return JSON.parse(call('/api/answers', JSON.stringify(answers)));
+}
+
+export function diff_rules(params_a, params_b) {
+    return JSON.parse(call('/api/diff', JSON.stringify({ from: params_a, to: params_b })));
+}
+
+export function list_categories() {
+    return JSON.parse(call('/api/categories?lang=' + encodeURIComponent(locale)));
+}
+
+export function search_categories(query) {
+    return JSON.parse(call('/api/search?q=' + encodeURIComponent(query) + '&lang=' + encodeURIComponent(locale)));
+}
+
+export function suggest_patterns(prefix) {
+    return JSON.parse(call('/api/suggest?prefix=' + encodeURIComponent(prefix)));
+}
+
+export function get_category_details(slug) {
+    return JSON.parse(call('/api/categories/' + encodeURIComponent(slug) + '?lang=' + encodeURIComponent(locale)));
+}
+
+export function get_version() {
+    return JSON.parse(call('/api/version')).version;
+}
+"#;
+
+/// Serve until interrupted
+///
+/// `pkg` optionally points at a wasm-pack build (`web/pkg`) to serve instead of the
+/// built-in module backed by the JSON API.
+pub fn run(categories: &[(String, Category)], host: &str, port: u16, pkg: Option<&Path>) -> Result<()> {
+    let server = Server::http((host, port)).map_err(|e| anyhow::anyhow!("Failed to listen on {}:{}: {}", host, port, e))?;
+    println!("Serving the apple-ecocide web UI at http://{}:{}/ (Ctrl-C to stop)", host, port);
+
+    for mut request in server.incoming_requests() {
+        let response = handle(&mut request, categories, pkg);
+        let _ = request.respond(response);
+    }
+    Ok(())
+}
+
+pub type HttpResponse = Response<std::io::Cursor<Vec<u8>>>;
+
+fn handle(request: &mut Request, categories: &[(String, Category)], pkg: Option<&Path>) -> HttpResponse {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let post = request.method() == &Method::Post;
+    if request.method() != &Method::Get && !(post && matches!(path, "/api/generate" | "/api/explain" | "/api/preview" | "/api/diff" | "/api/answers")) {
+        return error(405, "Only GET requests and POST /api/generate, /api/explain, /api/preview, /api/diff or /api/answers are supported");
+    }
+
+    let lang = query_param(query, "lang");
+    let localized;
+    let categories = if lang.is_empty() {
+        categories
+    } else {
+        let mut copy = categories.to_vec();
+        localize_categories(&mut copy, &lang);
+        localized = copy;
+        &localized
+    };
+
+    match path {
+        "/" | "/index.html" => respond(200, "text/html; charset=utf-8", INDEX_HTML),
+        "/api/version" => json_response(&json!({ "version": apple_ecocide::get_version() })),
+        "/api/categories" => json_response(&get_category_info(categories)),
+        "/api/search" => json_response(&SearchIndex::new(categories).search(&query_param(query, "q"))),
+        "/api/suggest" => json_response(&SearchIndex::new(categories).suggest_patterns(&query_param(query, "prefix"))),
+        "/api/generate" if post => match posted_params(request) {
+            Ok(params) => respond_generated(categories, &params, &query_param(query, "format")),
+            Err(response) => response,
+        },
+        "/api/generate" => generate(categories, query),
+        "/api/explain" if post => match posted_params(request) {
+            Ok(params) => respond_explained(categories, &params),
+            Err(response) => response,
+        },
+        "/api/explain" => match query_params(query) {
+            Ok(params) => respond_explained(categories, &params),
+            Err(response) => response,
+        },
+        "/api/preview" if post => match posted_params(request) {
+            Ok(params) => respond_preview(categories, &params),
+            Err(response) => response,
+        },
+        "/api/preview" => match query_params(query) {
+            Ok(params) => respond_preview(categories, &params),
+            Err(response) => response,
+        },
+        "/api/diff" if post => match posted::<DiffRequest>(request) {
+            Ok(diff) => match (diff.from.validate(), diff.to.validate()) {
+                (Ok(()), Ok(())) => json_response(&SelectionDiff::new(&diff.from, &diff.to, categories)),
+                (Err(e), _) | (_, Err(e)) => error(400, &e.to_string()),
+            },
+            Err(response) => response,
+        },
+        "/api/diff" => error(405, "POST a JSON body with the 'from' and 'to' parameters"),
+        "/api/questions" => json_response(&QUESTIONS),
+        "/api/answers" if post => match posted::<Answers>(request) {
+            Ok(answers) => match answers.to_params(categories) {
+                Ok(params) => json_response(&params),
+                Err(e) => error(400, &e.to_string()),
+            },
+            Err(response) => response,
+        },
+        "/api/answers" => error(405, "POST a JSON body with the 'severity' and 'uses' answers"),
+        _ => {
+            if let Some(slug) = path.strip_prefix("/api/categories/") {
+                category_details(categories, &percent_decode(slug))
+            } else if let Some(file) = path.strip_prefix("/pkg/") {
+                pkg_file(pkg, &percent_decode(file))
+            } else {
+                error(404, "Not found")
+            }
+        }
+    }
+}
+
+/// Decoded value of a query string parameter, empty if absent
+pub fn query_param(query: &str, key: &str) -> String {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| percent_decode(v))
+        .unwrap_or_default()
+}
+
+fn generate(categories: &[(String, Category)], query: &str) -> HttpResponse {
+    match query_params(query) {
+        Ok(params) => respond_generated(categories, &params, &query_param(query, "format")),
+        Err(response) => response,
+    }
+}
+
+/// Why each category is denied, allowed or skipped for the `/api/generate` parameters
+fn respond_explained(categories: &[(String, Category)], params: &GenerateParams) -> HttpResponse {
+    let selection = select_categories(params, categories);
+    let explanation: Vec<_> = categories
+        .iter()
+        .map(|(slug, _)| {
+            let reason = selection.explain(slug);
+            json!({
+                "slug": slug,
+                "action": selection.action(slug),
+                "message": reason.to_string(),
+                "reason": reason,
+            })
+        })
+        .collect();
+    json_response(&explanation)
+}
+
+/// Rule and domain counts of the selection, without generating the rules
+fn respond_preview(categories: &[(String, Category)], params: &GenerateParams) -> HttpResponse {
+    let selection = select_categories(params, categories);
+    json_response(&SelectionPreview::new(params, categories, &selection))
+}
+
+/// Validated parameters from the web UI's query string, or a 400 response; it always
+/// selects every category matching them
+fn query_params(query: &str) -> Result<GenerateParams, HttpResponse> {
+    let param = |key: &str| query_param(query, key);
+    let list = |value: String| -> Vec<String> {
+        value
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    };
+
+    let name = param("name");
+    let params = GenerateParams {
+        mode: param("mode").parse().unwrap_or_default(),
+        severity: param("severity").parse().unwrap_or_default(),
+        min_severity: None,
+        severity_overrides: Vec::new(),
+        include: list(param("include")),
+        exclude: list(param("exclude")),
+        all: true,
+        name: (!name.is_empty()).then_some(name),
+        ls_version: LsVersion::default(),
+        macos_version: None,
+        reproducible: false,
+        notes_style: NotesStyle::default(),
+        notes_template: None,
+        description_template: None,
+        sort: None,
+        include_disabled: false,
+        app: None,
+        provenance_notes: false,
+        asn_prefixes: BTreeMap::new(),
+    };
+    params.validate().map_err(|e| error(400, &e.to_string()))?;
+    Ok(params)
+}
+
+/// A JSON `GenerateParams` body of a POST request, e.g. a saved profile
+pub fn posted_params(request: &mut Request) -> Result<GenerateParams, HttpResponse> {
+    let params: GenerateParams = posted(request)?;
+    params.validate().map_err(|e| error(400, &e.to_string()))?;
+    Ok(params)
+}
+
+/// The two parameter sets `POST /api/diff` compares
+#[derive(Deserialize)]
+struct DiffRequest {
+    from: GenerateParams,
+    to: GenerateParams,
+}
+
+fn posted<T: DeserializeOwned>(request: &mut Request) -> Result<T, HttpResponse> {
+    let mut body = String::new();
+    if let Err(e) = request.as_reader().read_to_string(&mut body) {
+        return Err(error(400, &e.to_string()));
+    }
+    serde_json::from_str(&body).map_err(|e| error(400, &format!("Invalid parameters: {}", e)))
+}
+
+/// The generated rules as `.lsrules` JSON, or in another `format` when one is given
+pub fn respond_generated(categories: &[(String, Category)], params: &GenerateParams, format: &str) -> HttpResponse {
+    match generated(categories, params, format) {
+        Ok(file) => file.into_response(),
+        Err(response) => response,
+    }
+}
+
+/// A generated rules file, ready to send
+#[derive(Clone)]
+pub struct GeneratedFile {
+    pub body: Vec<u8>,
+    pub format: OutputFormat,
+    /// Download name derived from the rule group name
+    pub file_name: String,
+}
+
+impl GeneratedFile {
+    pub fn into_response(self) -> HttpResponse {
+        let content_type = match self.format {
+            OutputFormat::Lsrules => self.format.mime_type().to_string(),
+            format => format!("{}; charset=utf-8", format.mime_type()),
+        };
+        // Percent-encoded so any rule group name fits the header
+        let disposition = format!(r#"inline; filename="{}""#, percent_encode(&self.file_name));
+        respond(200, &content_type, self.body)
+            .with_header(Header::from_bytes("Content-Disposition", disposition).expect("valid header"))
+    }
+}
+
+/// Generate the rules for `params`, rendered as `format` (`.lsrules` when empty)
+pub fn generated(categories: &[(String, Category)], params: &GenerateParams, format: &str) -> Result<GeneratedFile, HttpResponse> {
+    let format: OutputFormat = match format {
+        "" => OutputFormat::Lsrules,
+        format => format.parse().map_err(|e: apple_ecocide::Error| error(400, &e.to_string()))?,
+    };
+    let output = generate_from_categories(params, categories).map_err(|e| error(400, &e.to_string()))?;
+    let body = output.to_format(format).map_err(|e| error(400, &e.to_string()))?;
+    Ok(GeneratedFile {
+        body: body.into_bytes(),
+        format,
+        file_name: format.file_name(&output.name),
+    })
+}
+
+pub fn category_details(categories: &[(String, Category)], slug: &str) -> HttpResponse {
+    let Some((_, category)) = categories.iter().find(|(s, _)| s == slug) else {
+        return error(404, &apple_ecocide::Error::UnknownCategory(slug.to_string()).to_string());
+    };
+
+    let domains: Vec<&str> = category.rules.iter().flat_map(|r| &r.domains).map(Domain::as_str).collect();
+    let processes: Vec<&str> = category.rules.iter().filter_map(|r| r.deny_process.as_deref()).collect();
+    json_response(&json!({
+        "slug": slug,
+        "name": category.name,
+        "description": category.description,
+        "severity": category.severity,
+        "impact": category.impact,
+        "domains": domains,
+        "processes": processes,
+    }))
+}
+
+fn pkg_file(pkg: Option<&Path>, file: &str) -> HttpResponse {
+    let Some(dir) = pkg else {
+        return if file == "apple_ecocide.js" {
+            respond(200, "text/javascript; charset=utf-8", API_MODULE)
+        } else {
+            error(404, "Not found")
+        };
+    };
+
+    let relative = Path::new(file);
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return error(404, "Not found");
+    }
+    match fs::read(dir.join(relative)) {
+        Ok(bytes) => respond(200, content_type(relative), bytes),
+        Err(_) => error(404, "Not found"),
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("wasm") => "application/wasm",
+        Some("json") => "application/json",
+        Some("html") => "text/html; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+pub fn respond(status: u16, content_type: &str, body: impl Into<Vec<u8>>) -> HttpResponse {
+    let header = Header::from_bytes("Content-Type", content_type).expect("valid header");
+    Response::from_data(body.into()).with_status_code(status).with_header(header)
+}
+
+pub fn json_response(value: &impl serde::Serialize) -> HttpResponse {
+    let mut body = Vec::new();
+    match serde_json::to_writer_pretty(&mut body, value) {
+        Ok(()) => respond(200, "application/json", body),
+        Err(e) => error(500, &e.to_string()),
+    }
+}
+
+pub fn error(status: u16, message: &str) -> HttpResponse {
+    respond(status, "application/json", json!({ "error": message }).to_string())
+}
+
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = value.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 2;
+            }
+            (b'+', _) => out.push(b' '),
+            (b, _) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,137 @@
+//! Safe writing of generated output files.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Write `content` unless the file already holds exactly that, returning whether it was written
+///
+/// The new content goes to a temporary file in the same directory which is then
+/// renamed over the target, so an interrupted run never leaves a truncated file.
+/// With `backups > 0` the previous file is kept as `<file>.bak`, and older
+/// generations as `<file>.bak.2` up to `<file>.bak.<backups>`. A symlinked target
+/// is written through the link, and an existing file keeps its permissions and,
+/// where allowed, its owner.
+pub fn write_if_changed(path: &Path, content: &[u8], backups: usize) -> Result<bool> {
+    if is_current(path, content) {
+        return Ok(false);
+    }
+
+    let existing = fs::metadata(path).ok();
+    let path = &match existing {
+        Some(_) => fs::canonicalize(path).with_context(|| format!("Failed to resolve: {}", path.display()))?,
+        None => path.to_path_buf(),
+    };
+    let temp = temp_path(path);
+    let result = write_temp(&temp, content).and_then(|()| {
+        if let Some(metadata) = &existing {
+            fs::set_permissions(&temp, metadata.permissions())
+                .with_context(|| format!("Failed to set permissions: {}", temp.display()))?;
+            preserve_owner(&temp, metadata);
+        }
+        if backups > 0 && path.exists() {
+            rotate_backups(path, backups)?;
+        }
+        fs::rename(&temp, path).with_context(|| format!("Failed to write: {}", path.display()))
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result.map(|()| true)
+}
+
+/// Check whether the file already holds exactly `content`
+pub fn is_current(path: &Path, content: &[u8]) -> bool {
+    fs::read(path).is_ok_and(|existing| existing == content)
+}
+
+fn write_temp(temp: &Path, content: &[u8]) -> Result<()> {
+    let mut file = File::create(temp).with_context(|| format!("Failed to create: {}", temp.display()))?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Give the temporary file the target's owner, where allowed; only root can
+/// hand a file to another user, so failures keep the current owner
+#[cfg(unix)]
+fn preserve_owner(temp: &Path, metadata: &fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+    let _ = std::os::unix::fs::chown(temp, Some(metadata.uid()), Some(metadata.gid()));
+}
+
+#[cfg(not(unix))]
+fn preserve_owner(_temp: &Path, _metadata: &fs::Metadata) {}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+/// Path of backup generation `n` (1 is the most recent)
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    if n == 1 {
+        name.push(".bak");
+    } else {
+        name.push(format!(".bak.{}", n));
+    }
+    PathBuf::from(name)
+}
+
+fn rotate_backups(path: &Path, backups: usize) -> Result<()> {
+    for n in (1..backups).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, n + 1))
+                .with_context(|| format!("Failed to rotate backup: {}", from.display()))?;
+        }
+    }
+    let backup = backup_path(path, 1);
+    fs::copy(path, &backup).with_context(|| format!("Failed to write backup: {}", backup.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("apple-ecocide-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn unchanged_content_is_not_rewritten() {
+        let dir = temp_dir("write-unchanged");
+        let path = dir.join("rules.lsrules");
+        assert!(write_if_changed(&path, b"one", 2).unwrap());
+        assert!(!write_if_changed(&path, b"one", 2).unwrap());
+        assert!(write_if_changed(&path, b"two", 2).unwrap());
+        assert_eq!(fs::read(&path).unwrap(), b"two");
+        assert_eq!(fs::read(backup_path(&path, 1)).unwrap(), b"one");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_and_permissions_survive_a_rewrite() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = temp_dir("write-symlink");
+        let target = dir.join("shared.lsrules");
+        let link = dir.join("rules.lsrules");
+        fs::write(&target, "old").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o640)).unwrap();
+        symlink(&target, &link).unwrap();
+
+        assert!(write_if_changed(&link, b"new", 0).unwrap());
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+        assert_eq!(fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o640);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
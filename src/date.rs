@@ -0,0 +1,169 @@
+//! Calendar dates used for category provenance and generated headers.
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Seconds since the Unix epoch, or `SOURCE_DATE_EPOCH` when set for reproducible builds
+///
+/// Returns `None` when no clock is available, as in plain WebAssembly builds.
+pub fn unix_time() -> Option<u64> {
+    // The system clock is unavailable to plain WebAssembly builds
+    if cfg!(target_arch = "wasm32") {
+        return None;
+    }
+
+    match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => epoch.trim().parse::<u64>().ok(),
+        Err(_) => Some(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?
+                .as_secs(),
+        ),
+    }
+}
+
+/// A calendar date written as `YYYY-MM-DD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Today's date (UTC), honoring `SOURCE_DATE_EPOCH` for reproducible builds
+    ///
+    /// Returns `None` when no clock is available, as in plain WebAssembly builds.
+    pub fn today() -> Option<Date> {
+        unix_time().map(Date::from_unix)
+    }
+
+    /// The UTC date of a Unix timestamp in seconds
+    pub fn from_unix(secs: u64) -> Date {
+        Date::from_days((secs / 86_400) as i64)
+    }
+
+    /// Number of days from `self` until `later` (negative if `later` is earlier)
+    pub fn days_until(&self, later: Date) -> i64 {
+        later.days() - self.days()
+    }
+
+    /// The date `days` days later (earlier if negative)
+    ///
+    /// Returns `None` when that date cannot be written as `YYYY-MM-DD`.
+    pub fn checked_add_days(&self, days: i64) -> Option<Date> {
+        let days = self.days().checked_add(days)?;
+        let (first, last) = (Date { year: 0, month: 1, day: 1 }, Date { year: 9999, month: 12, day: 31 });
+        (first.days()..=last.days()).contains(&days).then(|| Date::from_days(days))
+    }
+
+    /// Days since 1970-01-01 (Howard Hinnant's days-from-civil algorithm)
+    fn days(&self) -> i64 {
+        let year = self.year - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let mp = (i64::from(self.month) + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    /// Civil-from-days conversion, the inverse of `days`
+    fn from_days(days: i64) -> Date {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let doe = days.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        Date {
+            year: yoe + era * 400 + i64::from(month <= 2),
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+}
+
+impl FromStr for Date {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidDate(s.to_string());
+        let mut parts = s.trim().splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let (year, month, day) = (next()?, next()?, next()?);
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return Err(invalid());
+        }
+
+        let date = Date {
+            year: year.parse().map_err(|_| invalid())?,
+            month: month.parse().map_err(|_| invalid())?,
+            day: day.parse().map_err(|_| invalid())?,
+        };
+        // Reject impossible days such as 2024-02-30 by round-tripping through the day count
+        if !(1..=12).contains(&date.month) || date.day == 0 || Date::from_days(date.days()) != date {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<Date> for String {
+    fn from(d: Date) -> Self {
+        d.to_string()
+    }
+}
+
+impl std::fmt::Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> Date {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_displays() {
+        assert_eq!(date("2024-02-29").to_string(), "2024-02-29");
+        for invalid in ["2023-02-29", "2024-13-01", "2024-1-01", "24-01-01", "2024-01-00", "10000-01-01"] {
+            assert!(invalid.parse::<Date>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn adds_days_across_months_and_years() {
+        assert_eq!(date("2024-02-28").checked_add_days(1), Some(date("2024-02-29")));
+        assert_eq!(date("2024-12-31").checked_add_days(1), Some(date("2025-01-01")));
+        assert_eq!(date("2025-03-01").checked_add_days(-1), Some(date("2025-02-28")));
+        assert_eq!(date("2024-01-01").days_until(date("2025-01-01")), 366);
+        assert_eq!(Date::from_unix(0), date("1970-01-01"));
+    }
+
+    #[test]
+    fn adding_days_stays_within_four_digit_years() {
+        assert_eq!(date("9999-12-31").checked_add_days(1), None);
+        assert_eq!(date("0000-01-01").checked_add_days(-1), None);
+        assert_eq!(date("2025-01-01").checked_add_days(i64::MAX), None);
+        assert_eq!(date("2025-01-01").checked_add_days(i64::MIN), None);
+    }
+}
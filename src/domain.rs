@@ -0,0 +1,215 @@
+//! Domain names in category rules, normalized and validated when parsed.
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// Syntax accepted by [`Domain::parse_with`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DomainSyntax {
+    /// Accept `_` in labels, as used by service records such as `_dmarc.example.com`
+    pub allow_underscores: bool,
+}
+
+/// A validated hostname such as `gateway.icloud.com`
+///
+/// Parsing normalizes the input first (see [`normalize_domain`]), then requires at
+/// least two labels of 1-63 letters, digits and inner hyphens, at most 253 characters
+/// overall, and a top-level label that is not all digits.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Domain {
+    name: String,
+    /// The text as written, when normalization changed it
+    written: Option<String>,
+}
+
+impl Domain {
+    /// Normalize and validate with the default syntax, which rejects underscores
+    pub fn parse(input: &str) -> Result<Domain> {
+        Domain::parse_with(input, DomainSyntax::default())
+    }
+
+    /// Normalize and validate with the given syntax
+    pub fn parse_with(input: &str, syntax: DomainSyntax) -> Result<Domain> {
+        let name = normalize_domain(input);
+        let invalid = |reason: &str| Error::InvalidDomain {
+            domain: input.to_string(),
+            reason: reason.to_string(),
+        };
+
+        if name.is_empty() {
+            return Err(invalid("it is empty"));
+        }
+        if name.len() > 253 {
+            return Err(invalid("it is longer than 253 characters"));
+        }
+        if let Some(c) = name.chars().find(|c| c.is_whitespace()) {
+            return Err(invalid(&format!("it contains whitespace ({:?})", c)));
+        }
+        if !name.contains('.') {
+            return Err(invalid("it needs at least two labels, e.g. 'example.com'"));
+        }
+        for label in name.split('.') {
+            if label.is_empty() {
+                return Err(invalid("it contains an empty label"));
+            }
+            if label.len() > 63 {
+                return Err(invalid(&format!("label '{}' is longer than 63 characters", label)));
+            }
+            if label.starts_with('-') || label.ends_with('-') {
+                return Err(invalid(&format!("label '{}' starts or ends with a hyphen", label)));
+            }
+            if label.contains('_') && !syntax.allow_underscores {
+                return Err(invalid(&format!("label '{}' contains an underscore", label)));
+            }
+            if let Some(c) = label.chars().find(|&c| !(c.is_ascii_alphanumeric() || c == '-' || c == '_')) {
+                return Err(invalid(&format!("label '{}' contains '{}'", label, c)));
+            }
+        }
+        if name.rsplit('.').next().is_some_and(|tld| tld.bytes().all(|b| b.is_ascii_digit())) {
+            return Err(invalid("it is an IP address or has a numeric top-level label"));
+        }
+
+        let written = (name != input).then(|| input.to_string());
+        Ok(Domain { name, written })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    /// The domain as written in the category file, if normalization changed it
+    pub fn written(&self) -> Option<&str> {
+        self.written.as_deref()
+    }
+}
+
+impl FromStr for Domain {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Domain::parse(s)
+    }
+}
+
+impl TryFrom<String> for Domain {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        Domain::parse(&s)
+    }
+}
+
+impl From<Domain> for String {
+    fn from(d: Domain) -> Self {
+        d.name
+    }
+}
+
+impl std::ops::Deref for Domain {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl AsRef<str> for Domain {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Borrow<str> for Domain {
+    fn borrow(&self) -> &str {
+        &self.name
+    }
+}
+
+impl std::fmt::Display for Domain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+// Domains compare by their normalized name only
+
+impl PartialEq for Domain {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Domain {}
+
+impl Hash for Domain {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl PartialOrd for Domain {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Domain {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+
+/// Normalize a domain to the form Little Snitch matches against
+///
+/// Lowercases, reduces a pasted URL (e.g. copied from a HAR file) to its host by
+/// stripping the scheme, credentials, port, path, query and fragment,
+/// drops a leading `*.` wildcard and trailing dots, and converts internationalized
+/// names to punycode (`bücher.example` becomes `xn--bcher-kva.example`).
+pub fn normalize_domain(domain: &str) -> String {
+    let mut host = domain.trim();
+    if let Some((_, rest)) = host.split_once("://") {
+        host = rest;
+    }
+    host = host.split(['/', '?', '#']).next().unwrap_or_default();
+    if let Some((_, rest)) = host.rsplit_once('@') {
+        host = rest;
+    }
+    if let Some((name, port)) = host.rsplit_once(':') {
+        if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) {
+            host = name;
+        }
+    }
+    host = host.strip_prefix("*.").unwrap_or(host);
+    host = host.trim_end_matches('.');
+
+    idna::domain_to_ascii(host).unwrap_or_else(|_| host.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_urls_wildcards_and_case() {
+        assert_eq!(normalize_domain(" Gateway.iCloud.com. "), "gateway.icloud.com");
+        assert_eq!(normalize_domain("https://user@example.com:8443/path?q#f"), "example.com");
+        assert_eq!(normalize_domain("*.example.com"), "example.com");
+        assert_eq!(normalize_domain("bücher.example"), "xn--bcher-kva.example");
+    }
+
+    #[test]
+    fn validates_hostnames() {
+        let domain = Domain::parse("https://Example.com/").unwrap();
+        assert_eq!(domain.as_str(), "example.com");
+        assert_eq!(domain.written(), Some("https://Example.com/"));
+        for invalid in ["", "localhost", "exa mple.com", "a..com", "-a.com", "example.123", "_dmarc.example.com"] {
+            assert!(Domain::parse(invalid).is_err(), "{}", invalid);
+        }
+        let syntax = DomainSyntax { allow_underscores: true };
+        assert!(Domain::parse_with("_dmarc.example.com", syntax).is_ok());
+    }
+}
@@ -0,0 +1,155 @@
+//! Error types returned by the library.
+
+#[cfg(feature = "miette")]
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use thiserror::Error;
+
+/// Result alias used throughout the library
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors that can occur while loading categories or generating rules
+///
+/// With the `miette` feature, parse errors carry their source file as a
+/// `miette::Diagnostic`, so a report handler can show the offending snippet.
+#[derive(Debug, Error)]
+#[cfg_attr(feature = "miette", derive(Diagnostic))]
+pub enum Error {
+    /// An embedded category file could not be read from the binary
+    #[error("Failed to load embedded category: {name}")]
+    EmbeddedLoad { name: String },
+
+    /// No category source was given and the build has no embedded categories
+    #[error("No categories loaded. This build has no embedded categories; provide them explicitly")]
+    NoCategories,
+
+    /// A category file is not valid UTF-8
+    #[error("Invalid UTF-8 in category: {file}")]
+    InvalidUtf8 { file: String },
+
+    /// A category file or directory could not be read
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Category files could not be downloaded
+    #[error("Failed to download {url}: {message}")]
+    Fetch { url: String, message: String },
+
+    /// A category file is not valid TOML or does not match the category schema
+    #[error(transparent)]
+    #[cfg_attr(feature = "miette", diagnostic(transparent))]
+    Parse(Box<ParseError>),
+
+    /// Generation parameters that contradict each other
+    #[error("Invalid parameters: {0}")]
+    InvalidParams(String),
+
+    /// The selection parameters did not select any category
+    #[error("No categories selected. Use include patterns or enable 'all'.")]
+    EmptySelection,
+
+    /// Include patterns that did not match any category (strict mode)
+    #[error("Patterns did not match any category: {}", .0.join(", "))]
+    UnmatchedPatterns(Vec<String>),
+
+    /// A category slug that does not exist in the loaded catalog
+    #[error("Category not found: {0}")]
+    UnknownCategory(String),
+
+    /// An unrecognized mode string
+    #[error("Invalid mode '{0}'. Expected 'block' or 'allow'")]
+    InvalidMode(String),
+
+    /// An unrecognized severity string
+    #[error("Invalid severity '{0}'. Expected 'minimal', 'recommended', or 'aggressive'")]
+    InvalidSeverity(String),
+
+    /// An unrecognized Little Snitch version
+    #[error("Invalid Little Snitch version '{0}'. Expected 4, 5, or 6")]
+    InvalidLsVersion(String),
+
+    /// An unrecognized notes style
+    #[error("Invalid notes style '{0}'. Expected 'full', 'minimal', or 'none'")]
+    InvalidNotesStyle(String),
+
+    /// An unrecognized rule sort order
+    #[error("Invalid sort order '{0}'. Expected 'action', 'category', 'domain', or 'priority'")]
+    InvalidSort(String),
+
+    /// An unrecognized remote endpoint constant
+    #[error("Invalid remote '{0}'. Expected 'local-net', 'multicast', 'broadcast', 'bonjour', 'dns-servers', or 'bpf'")]
+    InvalidRemote(String),
+
+    /// A malformed macOS version string
+    #[error("Invalid macOS version '{0}'. Expected e.g. '14' or '14.4'")]
+    InvalidMacosVersion(String),
+
+    /// A domain in a category rule is not a valid hostname
+    #[error("Invalid domain '{domain}': {reason}")]
+    InvalidDomain { domain: String, reason: String },
+
+    /// A malformed calendar date
+    #[error("Invalid date '{0}'. Expected YYYY-MM-DD, e.g. '2024-05-01'")]
+    InvalidDate(String),
+
+    /// A malformed crate version string
+    #[error("Invalid version '{0}'. Expected e.g. '0.3.0'")]
+    InvalidVersion(String),
+
+    /// An unrecognized output format
+    #[error("Invalid output format '{0}'. Expected 'lsrules', 'hosts', 'adguard', 'dnsmasq', or 'domains'")]
+    InvalidFormat(String),
+
+    /// The output could not be serialized to JSON
+    #[error("JSON serialization error: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+impl Error {
+    pub(crate) fn io(path: &std::path::Path, source: std::io::Error) -> Self {
+        Error::Io {
+            path: path.display().to_string(),
+            source,
+        }
+    }
+
+    /// Build a parse error from a TOML error, resolving its span to a line and column
+    pub(crate) fn parse(file: &str, content: &str, err: toml::de::Error) -> Self {
+        let span = err.span().filter(|span| content.get(..span.start).is_some());
+        let position = span.as_ref().map(|span| {
+            let before = &content[..span.start];
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+        });
+        Error::Parse(Box::new(ParseError {
+            file: file.to_string(),
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+            message: err.message().to_string(),
+            #[cfg(feature = "miette")]
+            source_code: NamedSource::new(file, content.to_string()).with_language("TOML"),
+            #[cfg(feature = "miette")]
+            span: span.map(SourceSpan::from),
+        }))
+    }
+}
+
+/// A TOML parse error; with the `miette` feature, it keeps the file contents for rendering the offending snippet
+#[derive(Debug, Error)]
+#[cfg_attr(feature = "miette", derive(Diagnostic), diagnostic(code(apple_ecocide::parse)))]
+#[error("Failed to parse category {file}{}: {message}", line.map(|l| format!(" (line {l}, column {})", column.unwrap_or(1))).unwrap_or_default())]
+pub struct ParseError {
+    pub file: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+    #[cfg(feature = "miette")]
+    #[source_code]
+    source_code: NamedSource<String>,
+    #[cfg(feature = "miette")]
+    #[label("{message}")]
+    span: Option<SourceSpan>,
+}
@@ -0,0 +1,351 @@
+//! macOS features mapped to the categories and rules that switch them off.
+//!
+//! Backs `--block-feature`, so users can ask for "no Siri" or "no AirPlay"
+//! without knowing which categories hold the rules.
+
+use crate::{Category, Error, Result, Severity};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A user-facing macOS feature and what blocks it
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Feature {
+    /// Name used on the command line, e.g. `spotlight-suggestions`
+    pub id: &'static str,
+    /// Name as macOS shows it
+    pub name: &'static str,
+    pub blocks: &'static [FeatureRules],
+}
+
+/// Rules of one category that a feature needs blocked
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FeatureRules {
+    pub category: &'static str,
+    /// Notes of the rules to block; empty blocks the whole category
+    pub rules: &'static [&'static str],
+}
+
+const fn whole(category: &'static str) -> FeatureRules {
+    FeatureRules { category, rules: &[] }
+}
+
+/// Every feature `--block-feature` accepts, by id
+pub const FEATURES: &[Feature] = &[
+    Feature {
+        id: "airdrop",
+        name: "AirDrop",
+        blocks: &[FeatureRules {
+            category: "apple-icloud",
+            rules: &["Block Sharing daemon (AirDrop) from all network access"],
+        }],
+    },
+    Feature {
+        id: "airplay",
+        name: "AirPlay",
+        blocks: &[FeatureRules {
+            category: "apple-music",
+            rules: &[
+                "Block Music device discovery agent from all network access",
+                "Block Media Remote agent from all network access",
+                "Block Media Remote daemon from all network access",
+            ],
+        }],
+    },
+    Feature {
+        id: "app-store",
+        name: "App Store",
+        blocks: &[whole("apple-appstore")],
+    },
+    Feature {
+        id: "apple-intelligence",
+        name: "Apple Intelligence",
+        blocks: &[whole("apple-intelligence")],
+    },
+    Feature {
+        id: "apple-pay",
+        name: "Apple Pay and Wallet",
+        blocks: &[whole("apple-pay")],
+    },
+    Feature {
+        id: "books",
+        name: "Apple Books",
+        blocks: &[whole("apple-books")],
+    },
+    Feature {
+        id: "dictation",
+        name: "Dictation",
+        blocks: &[FeatureRules {
+            category: "apple-siri",
+            rules: &[
+                "Dictation and voice trigger services",
+                "Block Core Speech daemon from all network access",
+                "Block System Core Speech daemon from all network access",
+            ],
+        }],
+    },
+    Feature {
+        id: "facetime",
+        name: "FaceTime",
+        blocks: &[FeatureRules {
+            category: "apple-messaging",
+            rules: &[
+                "Block Call Services daemon (FaceTime) from all network access",
+                "Block AV Conferencing daemon (FaceTime) from all network access",
+                "Block FaceTime Conversation Service from all network access",
+            ],
+        }],
+    },
+    Feature {
+        id: "find-my",
+        name: "Find My",
+        blocks: &[whole("apple-find-my")],
+    },
+    Feature {
+        id: "game-center",
+        name: "Game Center",
+        blocks: &[whole("apple-game-center")],
+    },
+    Feature {
+        id: "handoff",
+        name: "Handoff and Universal Clipboard",
+        blocks: &[FeatureRules {
+            category: "apple-icloud",
+            rules: &[
+                "Block Rapport daemon (Handoff/Continuity) from all network access",
+                "Block User Activity daemon (Handoff) from all network access",
+            ],
+        }],
+    },
+    Feature {
+        id: "home",
+        name: "Home",
+        blocks: &[whole("apple-homekit")],
+    },
+    Feature {
+        id: "icloud",
+        name: "iCloud",
+        blocks: &[whole("apple-icloud")],
+    },
+    Feature {
+        id: "icloud-photos",
+        name: "iCloud Photos",
+        blocks: &[FeatureRules {
+            category: "apple-icloud",
+            rules: &["iCloud Photos", "iCloud Live Photos", "Block Photo Library daemon from all network access"],
+        }],
+    },
+    Feature {
+        id: "imessage",
+        name: "iMessage",
+        blocks: &[FeatureRules {
+            category: "apple-messaging",
+            rules: &[
+                "Block Messages app from all network access",
+                "Block iMessage agent from all network access",
+                "Block iMessage Persistence Agent from all network access",
+            ],
+        }],
+    },
+    Feature {
+        id: "location-services",
+        name: "Location Services",
+        blocks: &[whole("apple-location")],
+    },
+    Feature {
+        id: "maps",
+        name: "Maps",
+        blocks: &[whole("apple-maps")],
+    },
+    Feature {
+        id: "music",
+        name: "Apple Music",
+        blocks: &[whole("apple-music")],
+    },
+    Feature {
+        id: "news",
+        name: "Apple News",
+        blocks: &[whole("apple-news")],
+    },
+    Feature {
+        id: "podcasts",
+        name: "Podcasts",
+        blocks: &[whole("apple-podcasts")],
+    },
+    Feature {
+        id: "private-relay",
+        name: "iCloud Private Relay",
+        blocks: &[FeatureRules {
+            category: "apple-icloud",
+            rules: &["iCloud Private Relay and Oblivious HTTP"],
+        }],
+    },
+    Feature {
+        id: "screen-time",
+        name: "Screen Time",
+        blocks: &[whole("apple-screentime")],
+    },
+    Feature {
+        id: "siri",
+        name: "Siri",
+        blocks: &[whole("apple-siri")],
+    },
+    Feature {
+        id: "spotlight-suggestions",
+        name: "Spotlight and Safari Suggestions",
+        blocks: &[FeatureRules {
+            category: "apple-siri",
+            rules: &[
+                "Block parsecd (Siri suggestions) from all network access",
+                "Parsec feedback/analytics (Siri Suggestions telemetry)",
+                "Block Parsec feedback daemon from all network access",
+                "Block Spotlight Knowledge daemon from all network access",
+            ],
+        }],
+    },
+    Feature {
+        id: "stocks",
+        name: "Stocks",
+        blocks: &[whole("apple-stocks")],
+    },
+    Feature {
+        id: "tv",
+        name: "Apple TV",
+        blocks: &[whole("apple-tv")],
+    },
+    Feature {
+        id: "weather",
+        name: "Weather",
+        blocks: &[whole("apple-weather")],
+    },
+];
+
+/// What blocking a set of features adds to a selection
+#[derive(Debug, Clone, Default)]
+pub struct FeatureSelection {
+    /// Categories blocked as a whole
+    pub include: Vec<String>,
+    /// One category per partially blocked feature, named `feature-<id>`, holding just its rules
+    pub categories: Vec<(String, Category)>,
+}
+
+impl Feature {
+    /// The feature with this id
+    pub fn find(id: &str) -> Option<&'static Feature> {
+        FEATURES.iter().find(|feature| feature.id == id)
+    }
+
+    /// Resolve features against the loaded categories
+    ///
+    /// Categories made for partially blocked features get `severity`, so that they
+    /// are selected at the severity being generated for. Rules of categories another
+    /// feature blocks as a whole are left out rather than generated twice.
+    pub fn select(
+        features: &[&Feature],
+        categories: &[(String, Category)],
+        severity: Severity,
+    ) -> Result<FeatureSelection> {
+        let by_slug: BTreeMap<&str, &Category> = categories.iter().map(|(slug, c)| (slug.as_str(), c)).collect();
+        let whole: BTreeSet<&str> = features
+            .iter()
+            .flat_map(|feature| feature.blocks)
+            .filter(|block| block.rules.is_empty())
+            .map(|block| block.category)
+            .collect();
+        let mut selection = FeatureSelection {
+            include: whole.iter().map(|slug| slug.to_string()).collect(),
+            categories: Vec::new(),
+        };
+        for feature in features {
+            let mut rules = Vec::new();
+            for block in feature.blocks {
+                let category = by_slug
+                    .get(block.category)
+                    .ok_or_else(|| Error::UnknownCategory(block.category.to_string()))?;
+                if whole.contains(block.category) {
+                    continue;
+                }
+                for notes in block.rules {
+                    let rule = category.rules.iter().find(|rule| rule.notes == *notes).ok_or_else(|| {
+                        Error::InvalidParams(format!("{} has no rule '{}' for feature '{}'", block.category, notes, feature.id))
+                    })?;
+                    rules.push(rule.clone());
+                }
+            }
+            if rules.is_empty() {
+                continue;
+            }
+            selection.categories.push((
+                format!("feature-{}", feature.id),
+                Category {
+                    name: feature.name.to_string(),
+                    description: format!("Blocks {}", feature.name),
+                    severity,
+                    breakage: None,
+                    impact: format!("- {} stops working", feature.name),
+                    sources: Vec::new(),
+                    last_verified: None,
+                    asns: Vec::new(),
+                    rules,
+                    translations: BTreeMap::new(),
+                    normalized: Vec::new(),
+                },
+            ));
+        }
+        Ok(selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "no-embed"))]
+    #[test]
+    fn every_feature_resolves_against_the_embedded_categories() {
+        let categories = crate::embedded_categories().unwrap();
+        for feature in FEATURES {
+            Feature::select(&[feature], categories, Severity::Recommended).unwrap();
+        }
+    }
+
+    #[test]
+    fn partial_features_get_their_own_category() {
+        let icloud = Category::from_toml_str(
+            r#"
+name = "iCloud"
+description = "Blocks iCloud"
+severity = "aggressive"
+impact = "- iCloud stops syncing"
+
+[[rules]]
+notes = "Block Sharing daemon (AirDrop) from all network access"
+deny-process = "/usr/libexec/sharingd"
+
+[[rules]]
+notes = "iCloud Photos"
+domains = ["photos.icloud.com"]
+"#,
+        )
+        .unwrap();
+        let categories = vec![("apple-icloud".to_string(), icloud)];
+        let airdrop = Feature::find("airdrop").unwrap();
+
+        let selection = Feature::select(&[airdrop], &categories, Severity::Minimal).unwrap();
+        assert!(selection.include.is_empty());
+        let [(slug, category)] = selection.categories.as_slice() else {
+            panic!("expected one category, got {:?}", selection.categories);
+        };
+        assert_eq!(slug, "feature-airdrop");
+        assert_eq!(category.severity, Severity::Minimal);
+        assert_eq!(category.rules.len(), 1);
+
+        // Blocking all of iCloud covers AirDrop already
+        let icloud = Feature::find("icloud").unwrap();
+        let selection = Feature::select(&[airdrop, icloud], &categories, Severity::Minimal).unwrap();
+        assert_eq!(selection.include, ["apple-icloud"]);
+        assert!(selection.categories.is_empty());
+
+        let siri = Feature::find("siri").unwrap();
+        assert!(matches!(Feature::select(&[siri], &categories, Severity::Minimal), Err(Error::UnknownCategory(_))));
+    }
+}
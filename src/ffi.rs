@@ -0,0 +1,123 @@
+//! C bindings for embedding the generator in native apps, declared in `include/apple_ecocide.h`.
+//!
+//! Strings cross the boundary as NUL-terminated UTF-8. Strings returned by
+//! `apple_ecocide_generate` and `apple_ecocide_list_categories` are owned by the
+//! caller and must be released with `apple_ecocide_free`; on failure they return
+//! NULL and `apple_ecocide_last_error` describes why.
+
+use crate::bindings::{set_categories, with_categories};
+use crate::{generate_from_categories, get_category_info, CategorySource, DirectorySource, Error, GenerateParams};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, turning its result into a caller-owned string or NULL with the error recorded
+fn to_c_string(f: impl FnOnce() -> Result<String, Error>) -> *mut c_char {
+    let result = f().and_then(|value| CString::new(value).map_err(|e| Error::InvalidParams(e.to_string())));
+    match result {
+        Ok(value) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = None);
+            value.into_raw()
+        }
+        Err(e) => {
+            set_last_error(&e);
+            ptr::null_mut()
+        }
+    }
+}
+
+fn set_last_error(error: &Error) {
+    let message = CString::new(error.to_string().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Read a C string argument, rejecting NULL and invalid UTF-8
+///
+/// # Safety
+/// `value` must be NULL or point to a NUL-terminated string.
+unsafe fn str_arg<'a>(value: *const c_char, name: &str) -> Result<&'a str, Error> {
+    if value.is_null() {
+        return Err(Error::InvalidParams(format!("{} is NULL", name)));
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|_| Error::InvalidParams(format!("{} is not valid UTF-8", name)))
+}
+
+/// Generate a `.lsrules` file from JSON parameters
+///
+/// `params_json` uses the kebab-case `GenerateParams` keys, e.g.
+/// `{"mode": "block", "include": ["apple-*"], "ls-version": "5"}`; omitted keys keep
+/// their defaults. Returns the pretty-printed rules JSON, or NULL on error.
+///
+/// # Safety
+/// `params_json` must be NULL or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn apple_ecocide_generate(params_json: *const c_char) -> *mut c_char {
+    to_c_string(|| {
+        let params: GenerateParams = serde_json::from_str(str_arg(params_json, "params_json")?)
+            .map_err(|e| Error::InvalidParams(e.to_string()))?;
+        params.validate()?;
+        with_categories(|categories| generate_from_categories(&params, categories)?.to_json_pretty())
+    })
+}
+
+/// List the available categories as a JSON array of category metadata
+///
+/// Returns NULL on error.
+#[no_mangle]
+pub extern "C" fn apple_ecocide_list_categories() -> *mut c_char {
+    to_c_string(|| with_categories(|categories| Ok(serde_json::to_string(&get_category_info(categories))?)))
+}
+
+/// Load categories from a directory of `.toml` files instead of the embedded ones
+///
+/// Returns 0 on success and -1 on error. Required before any other call when the
+/// library is built with the `no-embed` feature.
+///
+/// # Safety
+/// `path` must be NULL or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn apple_ecocide_load_categories(path: *const c_char) -> i32 {
+    let loaded = str_arg(path, "path").and_then(|path| DirectorySource::new(path).load());
+    match loaded {
+        Ok(categories) => {
+            set_categories(categories);
+            0
+        }
+        Err(e) => {
+            set_last_error(&e);
+            -1
+        }
+    }
+}
+
+/// The error of the last failed call on this thread, or NULL
+///
+/// The string is owned by the library and valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn apple_ecocide_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// The library version, e.g. "0.1.0"; a static string that must not be freed
+#[no_mangle]
+pub extern "C" fn apple_ecocide_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Release a string returned by this library; NULL is ignored
+///
+/// # Safety
+/// `value` must be NULL or a string returned by `apple_ecocide_generate` or
+/// `apple_ecocide_list_categories` that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn apple_ecocide_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
@@ -0,0 +1,187 @@
+//! Renderings of the generated rules for blockers other than Little Snitch.
+
+use crate::{Error, LsRulesOutput, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+/// File format of the generated output
+///
+/// Every format but [`Lsrules`](OutputFormat::Lsrules) is a DNS blocklist: it lists the
+/// domains of the enabled deny rules and drops process and allow rules, which DNS
+/// blockers cannot express.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Little Snitch rule group (JSON)
+    #[default]
+    Lsrules,
+    /// `/etc/hosts` entries pointing each domain at `0.0.0.0`; subdomains are not covered
+    Hosts,
+    /// AdGuard / uBlock Origin filter list (`||domain^`), covering subdomains
+    Adguard,
+    /// dnsmasq configuration (`address=/domain/0.0.0.0`), covering subdomains
+    Dnsmasq,
+    /// One domain per line, e.g. for Pi-hole or NextDNS
+    Domains,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "lsrules" => Ok(OutputFormat::Lsrules),
+            "hosts" => Ok(OutputFormat::Hosts),
+            "adguard" => Ok(OutputFormat::Adguard),
+            "dnsmasq" => Ok(OutputFormat::Dnsmasq),
+            "domains" => Ok(OutputFormat::Domains),
+            _ => Err(Error::InvalidFormat(s.to_string())),
+        }
+    }
+}
+
+impl OutputFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Lsrules => "lsrules",
+            OutputFormat::Hosts => "hosts",
+            OutputFormat::Adguard => "adguard",
+            OutputFormat::Dnsmasq => "dnsmasq",
+            OutputFormat::Domains => "domains",
+        }
+    }
+
+    /// File extension for downloads, without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Lsrules => "lsrules",
+            OutputFormat::Hosts => "hosts",
+            OutputFormat::Dnsmasq => "conf",
+            OutputFormat::Adguard | OutputFormat::Domains => "txt",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Lsrules => "application/json",
+            _ => "text/plain",
+        }
+    }
+
+    /// Download file name for a rule group named `name`, e.g. `My rules.lsrules`
+    ///
+    /// Characters file systems reject are replaced with `-`.
+    pub fn file_name(&self, name: &str) -> String {
+        let stem: String = name
+            .trim()
+            .chars()
+            .map(|c| if c.is_control() || r#"/\:*?"<>|"#.contains(c) { '-' } else { c })
+            .collect();
+        let stem = stem.trim_matches(['.', ' ']);
+        format!("{}.{}", if stem.is_empty() { "apple-ecocide" } else { stem }, self.extension())
+    }
+
+    /// Comment prefix of the format's header lines
+    fn comment(&self) -> &'static str {
+        match self {
+            OutputFormat::Adguard => "!",
+            _ => "#",
+        }
+    }
+
+    fn entry(&self, domain: &str) -> String {
+        match self {
+            OutputFormat::Lsrules | OutputFormat::Domains => domain.to_string(),
+            OutputFormat::Hosts => format!("0.0.0.0 {}", domain),
+            OutputFormat::Adguard => format!("||{}^", domain),
+            OutputFormat::Dnsmasq => format!("address=/{}/0.0.0.0", domain),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl LsRulesOutput<'_> {
+    /// Render as `format`: pretty-printed JSON for `lsrules`, otherwise a blocklist
+    ///
+    /// Fails when a blocklist would be empty, e.g. when only process-wide rules were selected,
+    /// or would block domains system-wide that the rules block for one app only.
+    pub fn to_format(&self, format: OutputFormat) -> Result<String> {
+        if format == OutputFormat::Lsrules {
+            return self.to_json_pretty();
+        }
+        if let Some(rule) = self.rules.iter().find(|rule| !rule.remote_domains.is_empty() && rule.process != "any") {
+            return Err(Error::InvalidParams(format!(
+                "cannot write rules for {} as {}; blocklists apply to every app",
+                rule.process, format
+            )));
+        }
+
+        let domains: BTreeSet<&str> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.action == "deny" && rule.disabled != Some(true))
+            .flat_map(|rule| &rule.remote_domains)
+            .map(|domain| domain.as_ref())
+            .collect();
+        if domains.is_empty() {
+            return Err(Error::InvalidParams(format!(
+                "no blocked domains to write as {}; blocklists cannot express process-wide rules",
+                format
+            )));
+        }
+
+        let comment = format.comment();
+        let mut out = format!("{} Title: {}\n", comment, self.name);
+        for line in self.description.lines() {
+            out.push_str(format!("{} {}", comment, line).trim_end());
+            out.push('\n');
+        }
+        out.push('\n');
+        for domain in domains {
+            out.push_str(&format.entry(domain));
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_from_categories, Category, GenerateParams};
+
+    #[test]
+    fn blocklists_refuse_app_scoped_rules() {
+        let category = Category::from_toml_str(
+            r#"
+name = "Siri"
+description = "Blocks Siri"
+severity = "minimal"
+impact = "- Siri stops working"
+
+[[rules]]
+notes = "Siri"
+domains = ["guzzoni.apple.com"]
+"#,
+        )
+        .unwrap();
+        let categories = vec![("apple-siri".to_string(), category)];
+        let params = GenerateParams::default();
+        let output = generate_from_categories(&params, &categories).unwrap();
+        assert!(output.to_format(OutputFormat::Domains).unwrap().ends_with("\nguzzoni.apple.com\n"));
+
+        let scoped = GenerateParams {
+            app: Some("/Applications/Safari.app/Contents/MacOS/Safari".into()),
+            ..GenerateParams::default()
+        };
+        let output = generate_from_categories(&scoped, &categories).unwrap();
+        assert!(output.to_format(OutputFormat::Hosts).is_err());
+        assert!(output.to_format(OutputFormat::Lsrules).is_ok());
+    }
+}
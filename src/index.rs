@@ -0,0 +1,80 @@
+//! Reverse lookup from domains to the categories that contain them.
+
+use crate::{normalize_domain, Category};
+use std::collections::HashMap;
+
+/// Index mapping each category domain to the slugs of the categories listing it
+#[derive(Debug, Default)]
+pub struct DomainIndex {
+    domains: HashMap<String, Vec<String>>,
+}
+
+impl DomainIndex {
+    /// Build an index over every domain rule in the given categories
+    pub fn new<'a>(categories: impl IntoIterator<Item = &'a (String, Category)>) -> Self {
+        let mut domains: HashMap<String, Vec<String>> = HashMap::new();
+        for (slug, category) in categories {
+            for domain in category.rules.iter().flat_map(|r| &r.domains) {
+                let slugs = domains.entry(normalize_domain(domain)).or_default();
+                if !slugs.contains(slug) {
+                    slugs.push(slug.clone());
+                }
+            }
+        }
+        DomainIndex { domains }
+    }
+
+    /// Find the categories whose rules cover `domain`
+    ///
+    /// Matching is suffix-aware like Little Snitch's `remote-domains`: a category
+    /// listing `icloud.com` also covers `gateway.icloud.com`.
+    pub fn lookup(&self, domain: &str) -> Vec<&str> {
+        let domain = normalize_domain(domain);
+        let mut found: Vec<&str> = Vec::new();
+
+        let mut suffix = domain.as_str();
+        loop {
+            if let Some(slugs) = self.domains.get(suffix) {
+                for slug in slugs {
+                    if !found.contains(&slug.as_str()) {
+                        found.push(slug);
+                    }
+                }
+            }
+            match suffix.split_once('.') {
+                Some((_, rest)) => suffix = rest,
+                None => break,
+            }
+        }
+
+        found.sort_unstable();
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_matches_subdomains_and_normalizes() {
+        let category = Category::from_toml_str(
+            r#"
+name = "iCloud"
+description = "Blocks iCloud"
+severity = "recommended"
+impact = "- iCloud stops syncing"
+
+[[rules]]
+notes = "iCloud"
+domains = ["icloud.com"]
+"#,
+        )
+        .unwrap();
+        let categories = vec![("apple-icloud".to_string(), category)];
+        let index = DomainIndex::new(&categories);
+        assert_eq!(index.lookup("gateway.icloud.com"), ["apple-icloud"]);
+        assert_eq!(index.lookup("https://Gateway.iCloud.com./path"), ["apple-icloud"]);
+        assert!(index.lookup("example.com").is_empty());
+    }
+}
@@ -3,10 +3,67 @@
 //! This module contains the platform-agnostic logic for loading categories,
 //! selecting rules, and building the output JSON.
 
-use glob::Pattern;
+#[cfg(not(feature = "no-embed"))]
 use rust_embed::Embed;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::str::FromStr;
+
+mod changelog;
+mod date;
+mod domain;
+mod error;
+mod features;
+mod format;
+mod index;
+mod locale;
+mod macos;
+mod params;
+mod patterns;
+mod preview;
+mod questionnaire;
+mod rules;
+mod search;
+mod source;
+mod template;
+
+pub use changelog::{load_changelog, parse_changelog, releases_since, CategoryChange, Release};
+pub use date::{unix_time, Date};
+pub use domain::{normalize_domain, Domain, DomainSyntax};
+pub use error::{Error, ParseError, Result};
+pub use features::{Feature, FeatureRules, FeatureSelection, FEATURES};
+pub use format::OutputFormat;
+pub use index::DomainIndex;
+pub use locale::{localize_categories, Translation};
+pub use macos::MacosVersion;
+pub use params::GenerateParamsBuilder;
+pub use patterns::CompiledPatterns;
+pub use preview::{ActionChange, BrokenFeature, CategoryPreview, SelectionDiff, SelectionPreview};
+pub use questionnaire::{Answers, Question, QUESTIONS};
+pub use rules::{Action, RuleIter};
+pub use search::{SearchHit, SearchIndex, SearchMatch};
+#[cfg(feature = "cli")]
+pub use source::RemoteSource;
+#[cfg(not(feature = "no-embed"))]
+pub use source::EmbeddedSource;
+pub use source::{CategorySource, DirectorySource, InMemorySource};
+
+#[cfg(any(feature = "ffi", feature = "uniffi", feature = "napi"))]
+mod bindings;
+
+#[cfg(feature = "ffi")]
+mod ffi;
+
+#[cfg(feature = "uniffi")]
+mod swift;
+
+#[cfg(feature = "napi")]
+mod node;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
 
 #[cfg(target_arch = "wasm32")]
 mod wasm;
@@ -14,13 +71,17 @@ mod wasm;
 #[cfg(target_arch = "wasm32")]
 pub use wasm::*;
 
+/// The category files compiled into the library, unless built with `no-embed`
+#[cfg(not(feature = "no-embed"))]
 #[derive(Embed)]
 #[folder = "categories/"]
-#[include = "*.toml"]
+#[include = "**/*.toml"]
 pub struct EmbeddedCategories;
 
 /// Mode for rule generation
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "lowercase")]
 pub enum Mode {
     /// Block specified categories (or all with --all)
     #[default]
@@ -29,15 +90,19 @@ pub enum Mode {
     Allow,
 }
 
-impl Mode {
-    pub fn from_str(s: &str) -> Option<Self> {
+impl FromStr for Mode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
-            "block" => Some(Mode::Block),
-            "allow" => Some(Mode::Allow),
-            _ => None,
+            "block" => Ok(Mode::Block),
+            "allow" => Ok(Mode::Allow),
+            _ => Err(Error::InvalidMode(s.to_string())),
         }
     }
+}
 
+impl Mode {
     pub fn as_str(&self) -> &'static str {
         match self {
             Mode::Block => "block",
@@ -48,6 +113,7 @@ impl Mode {
 
 /// Severity level for categories
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
     /// Minimal blocking - only the most egregious tracking
@@ -59,16 +125,20 @@ pub enum Severity {
     Aggressive,
 }
 
-impl Severity {
-    pub fn from_str(s: &str) -> Option<Self> {
+impl FromStr for Severity {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
-            "minimal" => Some(Severity::Minimal),
-            "recommended" => Some(Severity::Recommended),
-            "aggressive" => Some(Severity::Aggressive),
-            _ => None,
+            "minimal" => Ok(Severity::Minimal),
+            "recommended" => Ok(Severity::Recommended),
+            "aggressive" => Ok(Severity::Aggressive),
+            _ => Err(Error::InvalidSeverity(s.to_string())),
         }
     }
+}
 
+impl Severity {
     pub fn as_str(&self) -> &'static str {
         match self {
             Severity::Minimal => "minimal",
@@ -84,67 +154,760 @@ impl std::fmt::Display for Severity {
     }
 }
 
+/// How much everyday functionality blocking a category is expected to break
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Breakage {
+    /// Nothing visible stops working
+    None,
+    /// A single app or rarely used feature stops working
+    Low,
+    /// Features many people rely on stop working or degrade
+    Medium,
+    /// Core system functionality such as sync, updates or messaging stops working
+    High,
+}
+
+impl Breakage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Breakage::None => "none",
+            Breakage::Low => "low",
+            Breakage::Medium => "medium",
+            Breakage::High => "high",
+        }
+    }
+}
+
+impl std::fmt::Display for Breakage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Little Snitch release targeted by the generated rules file
+///
+/// `action`, `process`, `remote-domains`, `remote-addresses`, `protocol` and
+/// `notes` are written the same way for every version; [`LsVersion::adapt`]
+/// strips or drops what older versions reject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Deserialize, Serialize)]
+pub enum LsVersion {
+    /// Little Snitch 4: no rule priorities, disabled rules or `via` processes, and
+    /// only the `any`, `local-net`, `multicast` and `broadcast` remote endpoints
+    #[serde(rename = "4")]
+    V4,
+    /// Little Snitch 5
+    #[serde(rename = "5")]
+    V5,
+    /// Little Snitch 6
+    #[default]
+    #[serde(rename = "6")]
+    V6,
+}
+
+impl FromStr for LsVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().trim_start_matches('v') {
+            "4" => Ok(LsVersion::V4),
+            "5" => Ok(LsVersion::V5),
+            "6" => Ok(LsVersion::V6),
+            _ => Err(Error::InvalidLsVersion(s.to_string())),
+        }
+    }
+}
+
+impl LsVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LsVersion::V4 => "4",
+            LsVersion::V5 => "5",
+            LsVersion::V6 => "6",
+        }
+    }
+
+    /// Whether rules may carry a `priority` key
+    pub fn supports_priority(&self) -> bool {
+        *self >= LsVersion::V5
+    }
+
+    /// Whether rules may carry a `disabled` key
+    pub fn supports_disabled(&self) -> bool {
+        *self >= LsVersion::V5
+    }
+
+    /// Whether rules may carry a `via` key
+    pub fn supports_via(&self) -> bool {
+        *self >= LsVersion::V5
+    }
+
+    /// Whether rules may use the `remote` endpoint, e.g. `bonjour`
+    pub fn supports_remote(&self, remote: &str) -> bool {
+        *self >= LsVersion::V5 || matches!(remote, "any" | "local-net" | "multicast" | "broadcast")
+    }
+
+    /// Strip keys this version rejects, or drop the rule if it cannot be expressed
+    ///
+    /// Rules are dropped rather than stripped when the key narrows them, since
+    /// without it they would match far more than intended.
+    pub fn adapt<'a>(&self, mut rule: LsRule<'a>) -> Option<LsRule<'a>> {
+        if rule.remote.is_some_and(|remote| !self.supports_remote(remote)) {
+            return None;
+        }
+        if rule.via.is_some() && !self.supports_via() {
+            return None;
+        }
+        if !self.supports_priority() {
+            rule.priority = None;
+        }
+        if !self.supports_disabled() {
+            if rule.disabled == Some(true) {
+                return None;
+            }
+            rule.disabled = None;
+        }
+        Some(rule)
+    }
+}
+
+impl std::fmt::Display for LsVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How the `notes` field of each generated rule is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotesStyle {
+    /// Category slug followed by the rule notes, e.g. `[apple-ads] Ad tracking`
+    #[default]
+    Full,
+    /// Only the rule notes
+    Minimal,
+    /// No notes
+    None,
+}
+
+impl FromStr for NotesStyle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(NotesStyle::Full),
+            "minimal" => Ok(NotesStyle::Minimal),
+            "none" => Ok(NotesStyle::None),
+            _ => Err(Error::InvalidNotesStyle(s.to_string())),
+        }
+    }
+}
+
+impl NotesStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotesStyle::Full => "full",
+            NotesStyle::Minimal => "minimal",
+            NotesStyle::None => "none",
+        }
+    }
+}
+
 /// A category file containing rules for a specific service/feature
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(try_from = "locale::RawCategory", into = "locale::RawCategory")]
 pub struct Category {
     pub name: String,
     pub description: String,
     pub severity: Severity,
+    /// Expected breakage when blocked; [`Category::impact_score`] estimates it from
+    /// the severity when not given
+    pub breakage: Option<Breakage>,
     pub impact: String,
+    /// Where the rules were researched, e.g. blog posts or packet captures
+    pub sources: Vec<String>,
+    /// When the rules were last checked against a current macOS release
+    pub last_verified: Option<Date>,
+    /// Autonomous systems operated for this service, expanded into address rules via `asn_prefixes`
+    pub asns: Vec<u32>,
     pub rules: Vec<CategoryRule>,
+    /// Name, description and impact in other languages, keyed by lowercase language tag
+    pub translations: BTreeMap<String, Translation>,
+    /// Domains rewritten by [`normalize_domain`] while loading, as `(original, normalized)`
+    pub normalized: Vec<(String, String)>,
+}
+
+impl Category {
+    /// Parse a category from TOML content held in memory
+    ///
+    /// Same as [`parse_category`], for callers that have no file name to report in errors.
+    pub fn from_toml_str(content: &str) -> Result<Category> {
+        parse_category("<string>", content)
+    }
+
+    /// How worthwhile blocking the category is, from 0 to 100; higher means enable it first
+    ///
+    /// The privacy benefit comes from the severity (minimal categories hold the most
+    /// egregious tracking) and rises when rules block whole processes rather than
+    /// single domains. It is scaled down by the breakage risk, taken from `breakage`
+    /// or estimated from the severity.
+    pub fn impact_score(&self) -> u8 {
+        let benefit: f64 = match self.severity {
+            Severity::Minimal => 1.0,
+            Severity::Recommended => 0.75,
+            Severity::Aggressive => 0.5,
+        };
+        let blocks_processes = self.rules.iter().any(|rule| rule.deny_process.is_some());
+        let benefit = benefit * if blocks_processes { 1.0 } else { 0.85 };
+
+        let risk: f64 = match (self.breakage, self.severity) {
+            (Some(Breakage::None), _) => 0.0,
+            (Some(Breakage::Low), _) => 0.2,
+            (Some(Breakage::Medium), _) => 0.5,
+            (Some(Breakage::High), _) => 0.85,
+            (None, Severity::Minimal) => 0.1,
+            (None, Severity::Recommended) => 0.3,
+            (None, Severity::Aggressive) => 0.6,
+        };
+        (100.0 * benefit * (1.0 - risk)).round() as u8
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct CategoryRule {
     pub notes: String,
-    #[serde(default)]
-    pub domains: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub domains: Vec<Domain>,
     /// Process path to block from all network access
-    #[serde(rename = "deny-process")]
+    #[serde(rename = "deny-process", skip_serializing_if = "Option::is_none")]
     pub deny_process: Option<String>,
+    /// Process path to allow full network access in allow mode
+    #[serde(rename = "allow-process", skip_serializing_if = "Option::is_none")]
+    pub allow_process: Option<String>,
+    /// Oldest macOS release this rule applies to
+    #[serde(rename = "min-macos", skip_serializing_if = "Option::is_none")]
+    pub min_macos: Option<MacosVersion>,
+    /// Newest macOS release this rule applies to
+    #[serde(rename = "max-macos", skip_serializing_if = "Option::is_none")]
+    pub max_macos: Option<MacosVersion>,
+    /// Helper process (e.g. `nsurlsessiond`) making the connections on behalf of the rule's process
+    #[serde(rename = "via-process", skip_serializing_if = "Option::is_none")]
+    pub via_process: Option<String>,
+    /// Special remote endpoint such as `bonjour`; scopes the process rule to it, or applies to any process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<Remote>,
+    /// Little Snitch rule priority; process rules default to `high`, domain rules to `regular`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    /// Ship the rule disabled so users opt in from Little Snitch (omitted unless `include_disabled`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+    /// Features that stop working while the rule blocks, e.g. `AirDrop`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub breaks: Vec<String>,
+}
+
+impl CategoryRule {
+    /// Whether this rule applies to the given macOS version (`None` matches every rule)
+    pub fn applies_to(&self, macos: Option<MacosVersion>) -> bool {
+        macos.is_none_or(|v| {
+            self.min_macos.is_none_or(|min| v >= min) && self.max_macos.is_none_or(|max| v <= max)
+        })
+    }
+
+    /// The remote endpoint to emit as its own rule for any process, when it isn't scoping a process rule
+    fn standalone_remote(&self) -> Option<Remote> {
+        self.remote.filter(|_| self.deny_process.is_none() && self.allow_process.is_none())
+    }
+}
+
+/// Little Snitch's special remote endpoint constants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Remote {
+    /// Hosts on the local network
+    LocalNet,
+    /// Multicast addresses
+    Multicast,
+    /// Broadcast addresses
+    Broadcast,
+    /// Bonjour service discovery (e.g. AirPlay)
+    Bonjour,
+    /// The configured DNS servers
+    DnsServers,
+    /// Raw packets via the Berkeley Packet Filter
+    Bpf,
+}
+
+impl FromStr for Remote {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "local-net" => Ok(Remote::LocalNet),
+            "multicast" => Ok(Remote::Multicast),
+            "broadcast" => Ok(Remote::Broadcast),
+            "bonjour" => Ok(Remote::Bonjour),
+            "dns-servers" => Ok(Remote::DnsServers),
+            "bpf" => Ok(Remote::Bpf),
+            _ => Err(Error::InvalidRemote(s.to_string())),
+        }
+    }
+}
+
+impl Remote {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Remote::LocalNet => "local-net",
+            Remote::Multicast => "multicast",
+            Remote::Broadcast => "broadcast",
+            Remote::Bonjour => "bonjour",
+            Remote::DnsServers => "dns-servers",
+            Remote::Bpf => "bpf",
+        }
+    }
+}
+
+/// Precedence of a rule in Little Snitch; `high` rules win over regular user rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Regular,
+    High,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Regular => "regular",
+            Priority::High => "high",
+        }
+    }
+
+    /// The `priority` key of a generated rule; regular is Little Snitch's default and omitted
+    fn output(self) -> Option<&'static str> {
+        (self == Priority::High).then_some(self.as_str())
+    }
 }
 
 /// Output format for Little Snitch rules
-#[derive(Debug, Serialize)]
-pub struct LsRulesOutput {
+///
+/// Processes, domains and address ranges borrow from the categories and parameters
+/// they were generated from; [`LsRulesOutput::into_owned`] detaches them.
+#[derive(Debug, Clone, Serialize)]
+pub struct LsRulesOutput<'a> {
     pub name: String,
     pub description: String,
-    pub rules: Vec<LsRule>,
+    pub rules: Vec<LsRule<'a>>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct LsRule {
+impl LsRulesOutput<'_> {
+    /// Copy every borrowed string so the output outlives its categories
+    pub fn into_owned(self) -> LsRulesOutput<'static> {
+        LsRulesOutput {
+            name: self.name,
+            description: self.description,
+            rules: self.rules.into_iter().map(LsRule::into_owned).collect(),
+        }
+    }
+
+    /// Serialize as minified JSON, the smallest payload for hosted subscriptions
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Serialize as pretty-printed JSON
+    pub fn to_json_pretty(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Serialize as minified JSON straight into `writer`, without building the string first
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<()> {
+        Ok(serde_json::to_writer(writer, self)?)
+    }
+
+    /// Serialize as pretty-printed JSON straight into `writer`, without building the string first
+    ///
+    /// Writes happen field by field, so wrap unbuffered writers such as files or sockets
+    /// in a [`std::io::BufWriter`].
+    pub fn write_json_pretty<W: Write>(&self, writer: W) -> Result<()> {
+        Ok(serde_json::to_writer_pretty(writer, self)?)
+    }
+
+    /// Flatten the rules into one `action process remote` entry per domain or process,
+    /// giving a canonical, order-independent view for comparing outputs
+    pub fn entries(&self) -> BTreeSet<String> {
+        let mut entries = BTreeSet::new();
+        for rule in &self.rules {
+            let suffix = if rule.disabled == Some(true) { " (disabled)" } else { "" };
+            let process = match &rule.via {
+                Some(via) => format!("{} via {}", rule.process, via),
+                None => rule.process.to_string(),
+            };
+            if rule.remote_domains.is_empty() && rule.remote_addresses.is_empty() {
+                let remote = rule.remote.unwrap_or("any");
+                entries.insert(format!("{} {} {}{}", rule.action, process, remote, suffix));
+            }
+            for remote in rule.remote_domains.iter().chain(&rule.remote_addresses) {
+                entries.insert(format!("{} {} {}{}", rule.action, process, remote, suffix));
+            }
+        }
+        entries
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LsRule<'a> {
     pub action: &'static str,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub priority: Option<&'static str>,
-    pub process: String,
+    pub process: Cow<'a, str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub via: Option<Cow<'a, str>>,
     #[serde(rename = "remote-domains", skip_serializing_if = "Vec::is_empty")]
-    pub remote_domains: Vec<String>,
+    pub remote_domains: Vec<Cow<'a, str>>,
+    /// CIDR address ranges, e.g. from `asn_prefixes`
+    #[serde(rename = "remote-addresses", skip_serializing_if = "Vec::is_empty")]
+    pub remote_addresses: Vec<Cow<'a, str>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub remote: Option<&'static str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub protocol: Option<&'static str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disabled: Option<bool>,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub notes: String,
 }
 
+impl LsRule<'_> {
+    /// First remote domain, or the process for process-wide rules
+    fn sort_domain(&self) -> &str {
+        self.remote_domains.first().unwrap_or(&self.process)
+    }
+
+    /// Copy every borrowed string so the rule outlives its category
+    pub fn into_owned(self) -> LsRule<'static> {
+        let owned = |values: Vec<Cow<'_, str>>| values.into_iter().map(|v| Cow::Owned(v.into_owned())).collect();
+        LsRule {
+            action: self.action,
+            priority: self.priority,
+            process: Cow::Owned(self.process.into_owned()),
+            via: self.via.map(|via| Cow::Owned(via.into_owned())),
+            remote_domains: owned(self.remote_domains),
+            remote_addresses: owned(self.remote_addresses),
+            remote: self.remote,
+            protocol: self.protocol,
+            disabled: self.disabled,
+            notes: self.notes,
+        }
+    }
+}
+
+/// Order of rules in the generated file, which Little Snitch lists them in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSort {
+    /// Deny rules first, then allow rules
+    Action,
+    /// Grouped by category slug
+    Category,
+    /// Alphabetical by first remote domain (or process)
+    Domain,
+    /// High-priority rules first
+    Priority,
+}
+
+impl FromStr for RuleSort {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "action" => Ok(RuleSort::Action),
+            "category" => Ok(RuleSort::Category),
+            "domain" => Ok(RuleSort::Domain),
+            "priority" => Ok(RuleSort::Priority),
+            _ => Err(Error::InvalidSort(s.to_string())),
+        }
+    }
+}
+
+impl RuleSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleSort::Action => "action",
+            RuleSort::Category => "category",
+            RuleSort::Domain => "domain",
+            RuleSort::Priority => "priority",
+        }
+    }
+}
+
 /// Selection result containing both denied and allowed categories
-#[derive(Default, Debug)]
+///
+/// Selections compose like sets, e.g. the categories aggressive severity adds on top
+/// of recommended: `aggressive.difference(&recommended)`.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct CategorySelection {
     pub denied: HashSet<String>,
     pub allowed: HashSet<String>,
+    /// Include patterns that did not match any category
+    pub unmatched: Vec<String>,
+    /// Why each category of the catalog was denied, allowed or skipped
+    pub reasons: HashMap<String, SelectionReason>,
+}
+
+impl CategorySelection {
+    /// Whether no category is denied or allowed
+    pub fn is_empty(&self) -> bool {
+        self.denied.is_empty() && self.allowed.is_empty()
+    }
+
+    /// What the selection does with a category; `None` if it is skipped
+    pub fn action(&self, slug: &str) -> Option<Action> {
+        if self.denied.contains(slug) {
+            Some(Action::Deny)
+        } else if self.allowed.contains(slug) {
+            Some(Action::Allow)
+        } else {
+            None
+        }
+    }
+
+    /// Which pattern, mode or severity decision denied, allowed or skipped a category
+    pub fn explain(&self, slug: &str) -> SelectionReason {
+        self.reasons.get(slug).cloned().unwrap_or(SelectionReason::Unknown)
+    }
+
+    /// Categories selected by either; a category denied by one and allowed by the other is denied
+    pub fn union(&self, other: &CategorySelection) -> CategorySelection {
+        let denied: HashSet<String> = self.denied.union(&other.denied).cloned().collect();
+        let allowed = self
+            .allowed
+            .union(&other.allowed)
+            .filter(|slug| !denied.contains(*slug))
+            .cloned()
+            .collect();
+        let mut unmatched = self.unmatched.clone();
+        unmatched.extend(other.unmatched.iter().filter(|p| !self.unmatched.contains(p)).cloned());
+        CategorySelection {
+            denied,
+            allowed,
+            unmatched,
+            ..Default::default()
+        }
+        .with_reasons_from(&[self, other])
+    }
+
+    /// Categories selected by both with the same action
+    pub fn intersection(&self, other: &CategorySelection) -> CategorySelection {
+        CategorySelection {
+            denied: self.denied.intersection(&other.denied).cloned().collect(),
+            allowed: self.allowed.intersection(&other.allowed).cloned().collect(),
+            unmatched: self.unmatched.clone(),
+            ..Default::default()
+        }
+        .with_reasons_from(&[self, other])
+    }
+
+    /// Categories selected here that `other` does not select with the same action
+    pub fn difference(&self, other: &CategorySelection) -> CategorySelection {
+        CategorySelection {
+            denied: self.denied.difference(&other.denied).cloned().collect(),
+            allowed: self.allowed.difference(&other.allowed).cloned().collect(),
+            unmatched: self.unmatched.clone(),
+            ..Default::default()
+        }
+        .with_reasons_from(&[self, other])
+    }
+
+    /// Deny every category of the catalog this selection neither denies nor allows
+    pub fn invert(&self, categories: &[(String, Category)]) -> CategorySelection {
+        CategorySelection {
+            denied: categories
+                .iter()
+                .map(|(slug, _)| slug)
+                .filter(|slug| self.action(slug).is_none())
+                .cloned()
+                .collect(),
+            reasons: categories
+                .iter()
+                .map(|(slug, _)| (slug.clone(), SelectionReason::Combined))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Keep the reason of an operand that treats each category the same way as the
+    /// combined selection, or mark the category as combined
+    fn with_reasons_from(mut self, operands: &[&CategorySelection]) -> CategorySelection {
+        let slugs: HashSet<&String> = operands.iter().flat_map(|s| s.reasons.keys()).collect();
+        self.reasons = slugs
+            .into_iter()
+            .map(|slug| {
+                let action = self.action(slug);
+                let reason = operands
+                    .iter()
+                    .find(|s| s.reasons.contains_key(slug) && s.action(slug) == action)
+                    .map_or(SelectionReason::Combined, |s| s.explain(slug));
+                (slug.clone(), reason)
+            })
+            .collect();
+        self
+    }
+}
+
+/// Why a category was denied, allowed or skipped, see [`CategorySelection::explain`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum SelectionReason {
+    /// Block mode without include patterns, or with `all`, denies every category
+    AllCategories,
+    /// An include pattern matched: denied in block mode, allowed in allow mode.
+    /// `None` when a list starting with a negation kept the category
+    Included { pattern: Option<String> },
+    /// No include pattern matched: denied in allow mode, skipped in block mode
+    NotIncluded,
+    /// A negated include pattern such as `!apple-ads` removed the category
+    Negated { pattern: String },
+    /// An exclude pattern matched
+    Excluded { pattern: String },
+    /// The category's severity, after overrides, is outside the selected range
+    OutsideSeverity { severity: Severity },
+    /// Decided by combining selections with union, intersection, difference or invert
+    Combined,
+    /// The slug is not in the catalog
+    Unknown,
+}
+
+impl std::fmt::Display for SelectionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectionReason::AllCategories => f.write_str("block mode selects every category"),
+            SelectionReason::Included { pattern: Some(pattern) } => write!(f, "matched include pattern '{}'", pattern),
+            SelectionReason::Included { pattern: None } => f.write_str("not removed by a negated include pattern"),
+            SelectionReason::NotIncluded => f.write_str("no include pattern matched"),
+            SelectionReason::Negated { pattern } => write!(f, "removed by include pattern '{}'", pattern),
+            SelectionReason::Excluded { pattern } => write!(f, "matched exclude pattern '{}'", pattern),
+            SelectionReason::OutsideSeverity { severity } => {
+                write!(f, "severity '{}' is outside the selected range", severity)
+            }
+            SelectionReason::Combined => f.write_str("decided by combining selections"),
+            SelectionReason::Unknown => f.write_str("not in the catalog"),
+        }
+    }
 }
 
 /// Parameters for generating rules
-#[derive(Debug, Default)]
+///
+/// Serializes with kebab-case keys, so a profile can be stored as TOML or JSON and
+/// read back; missing keys take their defaults. Use [`GenerateParams::builder`] to
+/// construct validated parameters in code.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
 pub struct GenerateParams {
     pub mode: Mode,
     pub severity: Severity,
+    /// Lowest severity to include; `None` includes everything up to `severity`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_severity: Option<Severity>,
+    /// Category patterns re-ranked to a different severity; later entries win
+    pub severity_overrides: Vec<(String, Severity)>,
     pub include: Vec<String>,
     pub exclude: Vec<String>,
     pub all: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// Little Snitch release the output must be compatible with
+    pub ls_version: LsVersion,
+    /// macOS release to generate for; rules outside their version range are skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macos_version: Option<MacosVersion>,
+    /// Sort rules and domains canonically and omit the version from the description
+    pub reproducible: bool,
+    /// How rule notes are written when no `notes_template` is given
+    pub notes_style: NotesStyle,
+    /// Template for rule notes with `{slug}`, `{name}`, `{notes}`, `{severity}`, `{action}`,
+    /// `{sources}` and `{last_verified}` placeholders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes_template: Option<String>,
+    /// Append the category's sources and last-verified date to the notes
+    pub provenance_notes: bool,
+    /// Template replacing the generated description; `name` accepts the same placeholders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description_template: Option<String>,
+    /// Reorder rules; `None` keeps process denies, then domain denies, then allows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<RuleSort>,
+    /// Emit rules marked `disabled` in their category as disabled rules instead of omitting them
+    pub include_disabled: bool,
+    /// Executable that domain and address rules apply to instead of any process, e.g.
+    /// `/Applications/Safari.app/Contents/MacOS/Safari`; the categories' process rules
+    /// target other processes and are left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    /// Announced CIDR prefixes per AS number; categories listing one of these ASNs
+    /// get a `remote-addresses` rule covering connections that bypass DNS.
+    /// Looked up at run time, so not part of a saved profile
+    #[serde(skip)]
+    pub asn_prefixes: BTreeMap<u32, Vec<String>>,
+}
+
+impl GenerateParams {
+    /// Whether a category rule is generated: it applies to the target macOS and,
+    /// if marked disabled, disabled rules are included
+    pub fn includes_rule(&self, rule: &CategoryRule) -> bool {
+        rule.applies_to(self.macos_version) && (!rule.disabled || self.include_disabled)
+    }
+
+    /// Notes of a generated rule, following `notes_template` or `notes_style`
+    pub fn notes_for(&self, slug: &str, category: &Category, rule: &CategoryRule, action: &str) -> String {
+        let sources = category.sources.join(", ");
+        let last_verified = category.last_verified.map(|d| d.to_string()).unwrap_or_default();
+        let notes = if let Some(template) = &self.notes_template {
+            template::render(
+                template,
+                &[
+                    ("slug", slug),
+                    ("name", &category.name),
+                    ("notes", &rule.notes),
+                    ("severity", self.severity_for(slug, category).as_str()),
+                    ("action", action),
+                    ("sources", &sources),
+                    ("last_verified", &last_verified),
+                ],
+            )
+        } else {
+            match self.notes_style {
+                NotesStyle::Full => format!("[{}] {}", slug, rule.notes),
+                NotesStyle::Minimal => rule.notes.clone(),
+                NotesStyle::None => String::new(),
+            }
+        };
+
+        if !self.provenance_notes || notes.is_empty() {
+            return notes;
+        }
+        let provenance: Vec<String> = [
+            (!sources.is_empty()).then(|| format!("sources: {}", sources)),
+            (!last_verified.is_empty()).then(|| format!("verified {}", last_verified)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if provenance.is_empty() {
+            return notes;
+        }
+        format!("{} ({})", notes, provenance.join("; "))
+    }
+
+    /// Severity of a category after applying any matching override
+    pub fn severity_for(&self, slug: &str, category: &Category) -> Severity {
+        self.severity_overrides
+            .iter()
+            .rev()
+            .find(|(pattern, _)| matches_pattern(slug, pattern))
+            .map_or(category.severity, |(_, severity)| *severity)
+    }
 }
 
 /// Category metadata for listing (used by UI)
@@ -154,23 +917,75 @@ pub struct CategoryInfo {
     pub name: String,
     pub description: String,
     pub severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breakage: Option<Breakage>,
+    /// See [`Category::impact_score`]
+    pub score: u8,
     pub impact: String,
     pub rule_count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    #[serde(rename = "last-verified", skip_serializing_if = "Option::is_none")]
+    pub last_verified: Option<Date>,
+}
+
+/// Parse a category from TOML content, using `file` to identify it in errors
+pub fn parse_category(file: &str, content: &str) -> Result<Category> {
+    toml::from_str(content).map_err(|e| Error::parse(file, content, e))
+}
+
+/// Derive a category slug from a file path relative to the categories directory
+///
+/// Nested directories become hierarchical slugs: `apple/telemetry.toml` is `apple/telemetry`.
+pub fn slug_for_path(relative: &str) -> String {
+    let relative = relative.replace('\\', "/");
+    relative
+        .trim_end_matches(".toml")
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The group of a hierarchical slug, e.g. `apple` for `apple/telemetry`
+pub fn category_group(slug: &str) -> Option<&str> {
+    slug.rsplit_once('/').map(|(group, _)| group)
 }
 
 /// Load embedded categories from the binary
-pub fn load_embedded_categories() -> Result<Vec<(String, Category)>, String> {
+#[cfg(not(feature = "no-embed"))]
+pub fn load_embedded_categories() -> Result<Vec<(String, Category)>> {
+    Ok(embedded_categories()?.to_vec())
+}
+
+/// The embedded categories, parsed on first use and shared by every later call
+///
+/// The WebAssembly bindings call this for every request from the web UI, so the
+/// TOML is only parsed once per page load. Failures are not cached.
+#[cfg(not(feature = "no-embed"))]
+pub fn embedded_categories() -> Result<&'static [(String, Category)]> {
+    static CACHE: std::sync::OnceLock<Vec<(String, Category)>> = std::sync::OnceLock::new();
+
+    if let Some(categories) = CACHE.get() {
+        return Ok(categories);
+    }
+    let categories = parse_embedded_categories()?;
+    Ok(CACHE.get_or_init(|| categories))
+}
+
+#[cfg(not(feature = "no-embed"))]
+fn parse_embedded_categories() -> Result<Vec<(String, Category)>> {
     let mut categories = Vec::new();
 
     for name in EmbeddedCategories::iter().filter(|n| n.ends_with(".toml")) {
-        let content = EmbeddedCategories::get(&name)
-            .ok_or_else(|| format!("Failed to load embedded category: {}", name))?;
-        let content_str = std::str::from_utf8(content.data.as_ref())
-            .map_err(|_| format!("Invalid UTF-8 in category: {}", name))?;
-        let category: Category = toml::from_str(content_str)
-            .map_err(|e| format!("Failed to parse category {}: {}", name, e))?;
-        let slug = name.trim_end_matches(".toml").to_string();
-        categories.push((slug, category));
+        let content = EmbeddedCategories::get(&name).ok_or_else(|| Error::EmbeddedLoad {
+            name: name.to_string(),
+        })?;
+        let content_str = std::str::from_utf8(content.data.as_ref()).map_err(|_| Error::InvalidUtf8 {
+            file: name.to_string(),
+        })?;
+        let category = parse_category(&name, content_str)?;
+        categories.push((slug_for_path(&name), category));
     }
 
     categories.sort_by(|a, b| a.0.cmp(&b.0));
@@ -186,203 +1001,586 @@ pub fn get_category_info(categories: &[(String, Category)]) -> Vec<CategoryInfo>
             name: cat.name.clone(),
             description: cat.description.clone(),
             severity: cat.severity,
+            breakage: cat.breakage,
+            score: cat.impact_score(),
             impact: cat.impact.clone(),
             rule_count: cat.rules.len(),
+            sources: cat.sources.clone(),
+            last_verified: cat.last_verified,
         })
         .collect()
 }
 
 /// Check if a slug matches a single pattern (supports glob wildcards)
+///
+/// Wildcards match across `/`, so `apple*` covers `apple/telemetry`, and a plain
+/// group name such as `apple` selects every category nested under it.
 pub fn matches_pattern(slug: &str, pattern: &str) -> bool {
-    if pattern.contains(['*', '?', '[']) {
-        Pattern::new(pattern).is_ok_and(|p| p.matches(slug))
-    } else {
-        pattern == slug
-    }
+    patterns::matches_pattern(slug, pattern)
 }
 
 /// Check if a slug matches any of the given patterns
 pub fn matches_any_pattern(slug: &str, patterns: &[String]) -> bool {
-    patterns.iter().any(|p| matches_pattern(slug, p))
+    CompiledPatterns::new(patterns).matches_any(slug)
+}
+
+/// Check if a slug is selected by an ordered include list
+///
+/// Entries prefixed with `!` remove slugs matched by earlier entries, so later
+/// entries take precedence. A list starting with a negation begins from every slug.
+pub fn matches_include_list(slug: &str, patterns: &[String]) -> bool {
+    CompiledPatterns::new(patterns).matches_include_list(slug)
+}
+
+/// Return the patterns that do not match any category slug
+pub fn unmatched_patterns(patterns: &[String], categories: &[(String, Category)]) -> Vec<String> {
+    CompiledPatterns::new(patterns).unmatched(categories)
+}
+
+/// Suggest the category slugs closest to a mistyped pattern
+///
+/// Patterns containing wildcards never produce suggestions.
+pub fn suggest_categories<'a>(pattern: &str, categories: &'a [(String, Category)]) -> Vec<&'a str> {
+    let pattern = pattern.strip_prefix('!').unwrap_or(pattern);
+    if pattern.contains(['*', '?', '[']) {
+        return Vec::new();
+    }
+
+    let max_distance = (pattern.len() / 4).clamp(1, 3);
+    let mut candidates: Vec<(usize, &str)> = categories
+        .iter()
+        .map(|(slug, _)| (strsim::levenshtein(pattern, slug), slug.as_str()))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    candidates.sort();
+
+    let best = candidates.first().map(|(distance, _)| *distance);
+    candidates
+        .into_iter()
+        .take_while(|(distance, _)| Some(*distance) == best)
+        .take(3)
+        .map(|(_, slug)| slug)
+        .collect()
 }
 
 /// Select categories based on parameters
+///
+/// Every category of the catalog gets a [`SelectionReason`], see [`CategorySelection::explain`].
 pub fn select_categories(params: &GenerateParams, categories: &[(String, Category)]) -> CategorySelection {
-    let exclude_patterns = &params.exclude;
-    let include_patterns = &params.include;
+    let include_patterns = CompiledPatterns::new(&params.include);
+    let exclude_patterns = CompiledPatterns::new(&params.exclude);
+    let override_patterns: Vec<String> = params.severity_overrides.iter().map(|(p, _)| p.clone()).collect();
+    let override_patterns = CompiledPatterns::new(&override_patterns);
+    // Block mode with --all or default (no includes) denies every category within severity
+    let select_all = params.mode == Mode::Block && (include_patterns.is_empty() || params.all);
 
-    let within_severity = |cat: &Category| cat.severity <= params.severity;
-    let is_excluded =
-        |slug: &str| !exclude_patterns.is_empty() && matches_any_pattern(slug, exclude_patterns);
+    let mut selection = CategorySelection::default();
+    for (slug, cat) in categories {
+        let severity = override_patterns
+            .last_position(slug)
+            .map_or(cat.severity, |i| params.severity_overrides[i].1);
+        let within_severity = severity <= params.severity && params.min_severity.is_none_or(|min| severity >= min);
+        let excluded = exclude_patterns.first_match(slug).map(str::to_string);
 
-    match (&params.mode, !include_patterns.is_empty(), params.all) {
-        // Block mode with --all or default (no includes): deny all within severity (minus excludes)
-        (Mode::Block, false, _) | (Mode::Block, _, true) => CategorySelection {
-            denied: categories
-                .iter()
-                .filter(|(slug, cat)| within_severity(cat) && !is_excluded(slug))
-                .map(|(slug, _)| slug.clone())
-                .collect(),
-            ..Default::default()
-        },
+        let (action, reason) = if !within_severity {
+            (None, SelectionReason::OutsideSeverity { severity })
+        } else {
+            let included = match include_patterns.include_decision(slug) {
+                Ok(pattern) => Ok(SelectionReason::Included {
+                    pattern: pattern.map(str::to_string),
+                }),
+                Err(Some(pattern)) => Err(SelectionReason::Negated {
+                    pattern: pattern.to_string(),
+                }),
+                Err(None) => Err(SelectionReason::NotIncluded),
+            };
+            match (params.mode, included, excluded) {
+                (Mode::Block, _, Some(pattern)) => (None, SelectionReason::Excluded { pattern }),
+                (Mode::Block, _, None) if select_all => (Some(Action::Deny), SelectionReason::AllCategories),
+                (Mode::Block, Ok(reason), None) => (Some(Action::Deny), reason),
+                (Mode::Block, Err(reason), None) => (None, reason),
 
-        // Block mode with --include: deny matching categories within severity
-        (Mode::Block, true, false) => {
-            let mut selection = CategorySelection::default();
-            for (slug, cat) in categories {
-                if matches_any_pattern(slug, include_patterns) && !is_excluded(slug) {
-                    if within_severity(cat) {
-                        selection.denied.insert(slug.clone());
-                    }
-                    // In WASM we skip the warning - no stderr
-                }
-            }
-            selection
-        }
-
-        // Allow mode: allow specified, deny everything else
-        (Mode::Allow, _, _) => {
-            let mut selection = CategorySelection::default();
-            for (slug, cat) in categories {
-                if !within_severity(cat) {
-                    continue;
-                }
-
-                if matches_any_pattern(slug, include_patterns) {
-                    selection.allowed.insert(slug.clone());
-                } else if !is_excluded(slug) {
-                    selection.denied.insert(slug.clone());
-                }
+                // Allow mode: allow specified, deny everything else
+                (Mode::Allow, Ok(reason), _) => (Some(Action::Allow), reason),
+                (Mode::Allow, Err(_), Some(pattern)) => (None, SelectionReason::Excluded { pattern }),
+                (Mode::Allow, Err(reason), None) => (Some(Action::Deny), reason),
             }
-            selection
+        };
+
+        match action {
+            Some(action) => tracing::debug!(category = %slug, %action, %reason, "selected category"),
+            None => tracing::debug!(category = %slug, %reason, "skipped category"),
         }
+        match action {
+            Some(Action::Deny) => selection.denied.insert(slug.clone()),
+            Some(Action::Allow) => selection.allowed.insert(slug.clone()),
+            None => false,
+        };
+        selection.reasons.insert(slug.clone(), reason);
+    }
+
+    selection.unmatched = include_patterns.unmatched(categories);
+    for pattern in &selection.unmatched {
+        tracing::debug!(%pattern, "include pattern matched no category");
     }
+    tracing::info!(
+        denied = selection.denied.len(),
+        allowed = selection.allowed.len(),
+        "selected categories"
+    );
+    selection
 }
 
 /// Build the output structure
-pub fn build_output(params: &GenerateParams, categories: &[(String, Category)], selection: &CategorySelection) -> LsRulesOutput {
-    let mut rules = Vec::new();
+pub fn build_output<'a>(
+    params: &'a GenerateParams,
+    categories: &'a [(String, Category)],
+    selection: &CategorySelection,
+) -> LsRulesOutput<'a> {
+    // Each selected category, denied ones first, indexed in the order RuleIter visits them
+    let groups: HashMap<(&str, Action), (usize, &Category)> = [Action::Deny, Action::Allow]
+        .into_iter()
+        .flat_map(|action| {
+            let selected = match action {
+                Action::Deny => &selection.denied,
+                Action::Allow => &selection.allowed,
+            };
+            categories
+                .iter()
+                .filter(move |(slug, _)| selected.contains(slug))
+                .map(move |(slug, category)| (slug.as_str(), category, action))
+        })
+        .enumerate()
+        .map(|(index, (slug, category, action))| ((slug, action), (index, category)))
+        .collect();
 
-    // 1. Process-based deny rules first (high priority - blocks specific processes entirely)
-    for (slug, category) in categories
-        .iter()
-        .filter(|(s, _)| selection.denied.contains(s))
-    {
-        for rule in &category.rules {
-            if let Some(process) = &rule.deny_process {
-                rules.push(LsRule {
-                    action: "deny",
-                    priority: Some("high"),
-                    process: process.clone(),
-                    remote_domains: Vec::new(),
-                    remote: Some("any"),
-                    protocol: Some("any"),
-                    disabled: None,
-                    notes: format!("[{}] {}", slug, rule.notes),
-                });
-            }
+    let mut rules: Vec<(RulePhase, usize, &str, LsRule<'a>)> = Vec::new();
+    for (slug, rule, action) in RuleIter::new(params, categories, selection) {
+        let (group, category) = groups[&(slug, action)];
+        for (phase, ls_rule) in expand_rule(params, slug, category, rule, action) {
+            rules.push((phase, group, slug, ls_rule));
         }
     }
-
-    // 2. Domain-based deny rules (blocks domains for any process)
-    for (slug, category) in categories
-        .iter()
-        .filter(|(s, _)| selection.denied.contains(s))
-    {
-        for rule in &category.rules {
-            if !rule.domains.is_empty() {
-                rules.push(LsRule {
-                    action: "deny",
-                    priority: None,
-                    process: "any".into(),
-                    remote_domains: rule.domains.clone(),
-                    remote: None,
-                    protocol: None,
-                    disabled: None,
-                    notes: format!("[{}] {}", slug, rule.notes),
-                });
-            }
+    for (&(slug, action), &(group, category)) in &groups {
+        if let Some(rule) = address_rule(params, slug, category, action) {
+            rules.push((RulePhase::for_remote(action), group, slug, rule));
         }
     }
+    // Stable, so rules keep their category order within each phase and category
+    rules.sort_by_key(|(phase, group, _, _)| (*phase, *group));
 
-    // 3. Allow rules last (regular priority - only applies if no high-priority deny matched)
-    for (slug, category) in categories
-        .iter()
-        .filter(|(s, _)| selection.allowed.contains(s))
-    {
-        for rule in &category.rules {
-            if !rule.domains.is_empty() {
-                rules.push(LsRule {
-                    action: "allow",
-                    priority: None,
-                    process: "any".into(),
-                    remote_domains: rule.domains.clone(),
-                    remote: None,
-                    protocol: None,
-                    disabled: Some(false),
-                    notes: format!("[{}] {}", slug, rule.notes),
-                });
+    let mut rules: Vec<(&str, LsRule<'a>)> = rules
+        .into_iter()
+        .filter_map(|(_, _, slug, rule)| match params.ls_version.adapt(rule) {
+            Some(rule) => Some((slug, rule)),
+            None => {
+                tracing::debug!(category = slug, ls_version = %params.ls_version, "dropped rule unsupported by Little Snitch version");
+                None
+            }
+        })
+        .collect();
+    if params.reproducible {
+        for (slug, rule) in &mut rules {
+            let count = rule.remote_domains.len();
+            rule.remote_domains.sort();
+            rule.remote_domains.dedup();
+            if rule.remote_domains.len() < count {
+                tracing::debug!(
+                    category = *slug,
+                    removed = count - rule.remote_domains.len(),
+                    "removed duplicate domains"
+                );
             }
         }
+        rules.sort_by(|(_, a), (_, b)| {
+            (a.action, &a.process, &a.remote_domains, &a.notes)
+                .cmp(&(b.action, &b.process, &b.remote_domains, &b.notes))
+        });
     }
+    // Stable sorts keep the generated (or canonical) order within each group
+    match params.sort {
+        None => {}
+        Some(RuleSort::Action) => rules.sort_by_key(|(_, rule)| rule.action != "deny"),
+        Some(RuleSort::Category) => rules.sort_by_key(|(slug, _)| *slug),
+        Some(RuleSort::Domain) => rules.sort_by(|(_, a), (_, b)| a.sort_domain().cmp(b.sort_domain())),
+        Some(RuleSort::Priority) => rules.sort_by_key(|(_, rule)| rule.priority != Some("high")),
+    }
+    let rules: Vec<LsRule<'a>> = rules.into_iter().map(|(_, rule)| rule).collect();
 
-    let description = build_description(params, selection);
+    let values = HeaderValues::new(params, selection);
+    let name = match &params.name {
+        Some(name) => template::render(name, &values.pairs()),
+        None => "Apple Ecocide".into(),
+    };
+    let description = match &params.description_template {
+        Some(template) => template::render(template, &values.pairs()),
+        None => build_description(&values),
+    };
 
     LsRulesOutput {
-        name: params
-            .name
-            .clone()
-            .unwrap_or_else(|| "Apple Ecocide".into()),
+        name,
         description,
         rules,
     }
 }
 
-fn build_description(params: &GenerateParams, selection: &CategorySelection) -> String {
-    let mode_str = params.mode.as_str();
+/// Position of a generated rule in the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum RulePhase {
+    /// Process-based deny rules first (high priority - blocks specific processes entirely)
+    DenyProcess,
+    /// Domain, remote-endpoint and address deny rules (for any process)
+    DenyRemote,
+    /// Allow rules last (regular priority - only applies if no high-priority deny matched)
+    Allow,
+}
+
+impl RulePhase {
+    fn for_remote(action: Action) -> Self {
+        match action {
+            Action::Deny => RulePhase::DenyRemote,
+            Action::Allow => RulePhase::Allow,
+        }
+    }
+}
+
+/// The `.lsrules` entries a category rule expands to: its process, its domains
+/// and its standalone remote endpoint, each as a separate rule
+fn expand_rule<'a>(
+    params: &GenerateParams,
+    slug: &str,
+    category: &Category,
+    rule: &'a CategoryRule,
+    action: Action,
+) -> Vec<(RulePhase, LsRule<'a>)> {
+    let base = LsRule {
+        action: action.as_str(),
+        priority: rule.priority.and_then(Priority::output),
+        process: params.app.clone().map_or("any".into(), Cow::Owned),
+        via: rule.via_process.as_deref().map(Cow::Borrowed),
+        remote_domains: Vec::new(),
+        remote_addresses: Vec::new(),
+        remote: None,
+        protocol: None,
+        disabled: match action {
+            Action::Deny => rule.disabled.then_some(true),
+            Action::Allow => Some(rule.disabled),
+        },
+        notes: params.notes_for(slug, category, rule, action.as_str()),
+    };
+    let mut expanded = Vec::new();
+
+    let process = match action {
+        Action::Deny => &rule.deny_process,
+        Action::Allow => &rule.allow_process,
+    };
+    if process.is_some() && params.app.is_some() {
+        tracing::debug!(category = slug, "skipped process rule while scoped to an app");
+    } else if let Some(process) = process {
+        let (phase, priority) = match action {
+            Action::Deny => (RulePhase::DenyProcess, rule.priority.unwrap_or(Priority::High).output()),
+            Action::Allow => (RulePhase::Allow, base.priority),
+        };
+        expanded.push((phase, LsRule {
+            priority,
+            process: Cow::Borrowed(process),
+            remote: Some(rule.remote.as_ref().map_or("any", Remote::as_str)),
+            protocol: Some("any"),
+            ..base.clone()
+        }));
+    }
+    if !rule.domains.is_empty() {
+        expanded.push((RulePhase::for_remote(action), LsRule {
+            remote_domains: rule.domains.iter().map(|domain| Cow::Borrowed(domain.as_str())).collect(),
+            ..base.clone()
+        }));
+    }
+    if let Some(remote) = rule.standalone_remote() {
+        expanded.push((RulePhase::for_remote(action), LsRule {
+            remote: Some(remote.as_str()),
+            ..base
+        }));
+    }
+    expanded
+}
+
+/// A `remote-addresses` rule for the announced prefixes of a category's ASNs, if any are known
+fn address_rule<'a>(
+    params: &'a GenerateParams,
+    slug: &str,
+    category: &Category,
+    action: Action,
+) -> Option<LsRule<'a>> {
+    let addresses: Vec<Cow<'a, str>> = category
+        .asns
+        .iter()
+        .filter_map(|asn| params.asn_prefixes.get(asn))
+        .flatten()
+        .map(|prefix| Cow::Borrowed(prefix.as_str()))
+        .collect();
+    if addresses.is_empty() {
+        return None;
+    }
+
+    let asns: Vec<String> = category.asns.iter().map(|asn| format!("AS{}", asn)).collect();
+    let rule = CategoryRule {
+        notes: format!("Address ranges announced by {}", asns.join(", ")),
+        ..Default::default()
+    };
+    Some(LsRule {
+        action: action.as_str(),
+        priority: None,
+        process: params.app.as_deref().map_or("any".into(), Cow::Borrowed),
+        via: None,
+        remote_domains: Vec::new(),
+        remote_addresses: addresses,
+        remote: None,
+        protocol: None,
+        disabled: (action == Action::Allow).then_some(false),
+        notes: params.notes_for(slug, category, &rule, action.as_str()),
+    })
+}
+
+/// Values available to `name` and `description_template` placeholders
+struct HeaderValues {
+    generator: String,
+    version: &'static str,
+    date: String,
+    mode: &'static str,
+    app: String,
+    severity: String,
+    category_count: String,
+    denied: Vec<String>,
+    allowed: Vec<String>,
+}
+
+impl HeaderValues {
+    fn new(params: &GenerateParams, selection: &CategorySelection) -> Self {
+        let mut denied: Vec<String> = selection.denied.iter().cloned().collect();
+        denied.sort();
+        let mut allowed: Vec<String> = selection.allowed.iter().cloned().collect();
+        allowed.sort();
 
-    let mut denied: Vec<_> = selection.denied.iter().map(String::as_str).collect();
-    denied.sort();
-    let mut allowed: Vec<_> = selection.allowed.iter().map(String::as_str).collect();
-    allowed.sort();
+        HeaderValues {
+            generator: if params.reproducible {
+                "apple-ecocide".to_string()
+            } else {
+                format!("apple-ecocide v{}", env!("CARGO_PKG_VERSION"))
+            },
+            version: env!("CARGO_PKG_VERSION"),
+            date: Date::today().map(|d| d.to_string()).unwrap_or_default(),
+            mode: params.mode.as_str(),
+            app: params.app.clone().unwrap_or_default(),
+            severity: match params.min_severity {
+                Some(min) if min == params.severity => format!("{} only", min),
+                Some(min) => format!("{} to {}", min, params.severity),
+                _ => params.severity.to_string(),
+            },
+            category_count: (denied.len() + allowed.len()).to_string(),
+            denied,
+            allowed,
+        }
+    }
+
+    fn pairs(&self) -> Vec<(&str, String)> {
+        vec![
+            ("generator", self.generator.clone()),
+            ("version", self.version.to_string()),
+            ("date", self.date.clone()),
+            ("mode", self.mode.to_string()),
+            ("app", self.app.clone()),
+            ("severity", self.severity.clone()),
+            ("category_count", self.category_count.clone()),
+            ("denied", self.denied.join(", ")),
+            ("allowed", self.allowed.join(", ")),
+        ]
+    }
+}
 
-    if allowed.is_empty() {
+/// The default rule group description: generator, mode, severity and the selected categories
+fn build_description(values: &HeaderValues) -> String {
+    let mode = if values.app.is_empty() {
+        values.mode.to_string()
+    } else {
+        format!("{} for {}", values.mode, values.app)
+    };
+    if values.allowed.is_empty() {
         format!(
-            "Generated by apple-ecocide v{}. Mode: {}. Severity: {}. Denied ({}): {}",
-            env!("CARGO_PKG_VERSION"),
-            mode_str,
-            params.severity,
-            denied.len(),
-            denied.join(", ")
+            "Generated by {}. Mode: {}. Severity: {}. Denied ({}): {}",
+            values.generator,
+            mode,
+            values.severity,
+            values.denied.len(),
+            values.denied.join(", ")
         )
     } else {
         format!(
-            "Generated by apple-ecocide v{}. Mode: {}. Severity: {}. Allowed ({}): {}. Denied ({}): {}",
-            env!("CARGO_PKG_VERSION"),
-            mode_str,
-            params.severity,
-            allowed.len(),
-            allowed.join(", "),
-            denied.len(),
-            denied.join(", ")
+            "Generated by {}. Mode: {}. Severity: {}. Allowed ({}): {}. Denied ({}): {}",
+            values.generator,
+            mode,
+            values.severity,
+            values.allowed.len(),
+            values.allowed.join(", "),
+            values.denied.len(),
+            values.denied.join(", ")
         )
     }
 }
 
-/// Generate rules JSON string from parameters
-pub fn generate_rules_json(params: &GenerateParams) -> Result<String, String> {
-    let categories = load_embedded_categories()?;
-    let selection = select_categories(params, &categories);
+/// Select and build rules from categories supplied by the caller
+///
+/// `categories` pairs each slug with its category, e.g. built with [`Category::from_toml_str`]
+/// or constructed directly; nothing is read from disk or the embedded data.
+pub fn generate_from_categories<'a>(
+    params: &'a GenerateParams,
+    categories: &'a [(String, Category)],
+) -> Result<LsRulesOutput<'a>> {
+    let selection = select_categories(params, categories);
 
-    if selection.denied.is_empty() && selection.allowed.is_empty() {
-        return Err("No categories selected. Use include patterns or enable 'all'.".to_string());
+    if selection.is_empty() {
+        return Err(Error::EmptySelection);
     }
 
-    let output = build_output(params, &categories, &selection);
-    serde_json::to_string_pretty(&output).map_err(|e| format!("JSON serialization error: {}", e))
+    Ok(build_output(params, categories, &selection))
+}
+
+/// Generate rules JSON string from parameters, using the embedded categories
+#[cfg(not(feature = "no-embed"))]
+pub fn generate_rules_json(params: &GenerateParams) -> Result<String> {
+    generate_from_categories(params, embedded_categories()?)?.to_json_pretty()
+}
+
+/// Generate rules from the embedded categories and write them as pretty-printed JSON
+///
+/// Unlike [`generate_rules_json`], the JSON is serialized directly into `writer`
+/// rather than held in memory as one string.
+#[cfg(not(feature = "no-embed"))]
+pub fn write_rules<W: Write>(params: &GenerateParams, writer: W) -> Result<()> {
+    generate_from_categories(params, embedded_categories()?)?.write_json_pretty(writer)
 }
 
 /// Get version string
 pub fn get_version() -> &'static str {
     env!("CARGO_PKG_VERSION")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule() -> LsRule<'static> {
+        LsRule {
+            action: "deny",
+            priority: Some("high"),
+            process: "any".into(),
+            via: None,
+            remote_domains: vec!["example.com".into()],
+            remote_addresses: Vec::new(),
+            remote: None,
+            protocol: None,
+            disabled: None,
+            notes: String::new(),
+        }
+    }
+
+    #[test]
+    fn parse_errors_locate_the_problem() {
+        let err = parse_category("broken.toml", "name = \"Broken\"\nseverity = \"extreme\"\n").unwrap_err();
+        let Error::Parse(parse) = &err else {
+            panic!("expected a parse error, got {:?}", err);
+        };
+        assert_eq!((parse.file.as_str(), parse.line), ("broken.toml", Some(2)));
+        assert!(err.to_string().starts_with("Failed to parse category broken.toml (line 2, column"));
+    }
+
+    fn category(toml: &str) -> Category {
+        Category::from_toml_str(toml).unwrap()
+    }
+
+    #[test]
+    fn impact_score_weighs_benefit_against_breakage() {
+        let score = |severity: &str, breakage: &str, rule: &str| {
+            category(&format!(
+                "name = \"Test\"\ndescription = \"\"\nseverity = \"{}\"\n{}impact = \"\"\n\n[[rules]]\nnotes = \"Test\"\n{}\n",
+                severity, breakage, rule
+            ))
+            .impact_score()
+        };
+        let domain = "domains = [\"example.com\"]";
+        let process = "deny-process = \"/usr/libexec/test\"";
+        assert_eq!(score("minimal", "breakage = \"none\"\n", process), 100);
+        assert_eq!(score("minimal", "breakage = \"none\"\n", domain), 85);
+        assert_eq!(score("aggressive", "breakage = \"high\"\n", process), 8);
+        // Without a breakage rating the risk is estimated from the severity
+        assert_eq!(score("recommended", "", process), 53);
+    }
+
+    #[test]
+    fn selection_explains_each_category() {
+        let slugs = [("apple-ads", "minimal"), ("apple-maps", "minimal"), ("siri", "aggressive")];
+        let categories: Vec<(String, Category)> = slugs
+            .into_iter()
+            .map(|(slug, severity)| {
+                let toml = format!(
+                    "name = \"Test\"\ndescription = \"\"\nseverity = \"{}\"\nimpact = \"\"\n\n[[rules]]\nnotes = \"Test\"\ndomains = [\"example.com\"]\n",
+                    severity
+                );
+                (slug.to_string(), category(&toml))
+            })
+            .collect();
+
+        let params = GenerateParams::builder().include(["apple-*"]).exclude(["apple-maps"]).build().unwrap();
+        let selection = select_categories(&params, &categories);
+        assert_eq!(
+            selection.explain("apple-ads"),
+            SelectionReason::Included {
+                pattern: Some("apple-*".to_string())
+            }
+        );
+        assert_eq!(
+            selection.explain("apple-maps"),
+            SelectionReason::Excluded {
+                pattern: "apple-maps".to_string()
+            }
+        );
+        assert_eq!(
+            selection.explain("siri"),
+            SelectionReason::OutsideSeverity {
+                severity: Severity::Aggressive
+            }
+        );
+        assert_eq!(selection.explain("unknown"), SelectionReason::Unknown);
+
+        // Set operations keep a reason only while the combined selection agrees with it
+        let aggressive = GenerateParams::builder().severity(Severity::Aggressive).build().unwrap();
+        let added = select_categories(&aggressive, &categories).difference(&selection);
+        assert_eq!(added.explain("siri"), SelectionReason::AllCategories);
+        assert_eq!(added.explain("apple-ads"), SelectionReason::Combined);
+    }
+
+    #[test]
+    fn adapt_strips_priority_and_drops_disabled_rules_for_ls4() {
+        let adapted = LsVersion::V4.adapt(rule()).unwrap();
+        assert_eq!(adapted.priority, None);
+        assert_eq!(LsVersion::V6.adapt(rule()).unwrap().priority, Some("high"));
+
+        let disabled = LsRule { disabled: Some(true), ..rule() };
+        assert!(LsVersion::V4.adapt(disabled.clone()).is_none());
+        assert_eq!(LsVersion::V5.adapt(disabled).unwrap().disabled, Some(true));
+    }
+
+    #[test]
+    fn adapt_drops_remote_endpoints_ls4_lacks() {
+        let bonjour = LsRule { remote: Some("bonjour"), ..rule() };
+        assert!(LsVersion::V4.adapt(bonjour.clone()).is_none());
+        assert!(LsVersion::V5.adapt(bonjour).is_some());
+        assert!(LsVersion::V4.adapt(LsRule { remote: Some("local-net"), ..rule() }).is_some());
+    }
+
+    #[test]
+    fn adapt_drops_via_rules_for_ls4() {
+        let via = LsRule { via: Some("/usr/libexec/nsurlsessiond".into()), ..rule() };
+        assert!(LsVersion::V4.adapt(via.clone()).is_none());
+        assert_eq!(LsVersion::V5.adapt(via).unwrap().via.as_deref(), Some("/usr/libexec/nsurlsessiond"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Localized category names, descriptions and impact notes.
+//!
+//! Any of these fields may be a plain string or a table keyed by language, e.g.
+//! `description.en = "..."` and `description.de = "..."`. The `en` entry is required
+//! and is what the category shows when no other language is selected.
+
+use crate::{Breakage, Category, CategoryRule, Date, Severity};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Translated texts of a category in one language; missing fields fall back to English
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Translation {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub impact: Option<String>,
+}
+
+impl Category {
+    /// Replace the name, description and impact with their `lang` translations
+    ///
+    /// Regional tags fall back to the base language (`de-AT` uses `de`), and
+    /// untranslated fields keep their English text.
+    pub fn localize(&mut self, lang: &str) {
+        let lang = lang.trim().to_lowercase().replace('_', "-");
+        let base = lang.split('-').next().unwrap_or_default();
+        let Some(translation) = self.translations.get(&lang).or_else(|| self.translations.get(base)) else {
+            return;
+        };
+
+        let translation = translation.clone();
+        if let Some(name) = translation.name {
+            self.name = name;
+        }
+        if let Some(description) = translation.description {
+            self.description = description;
+        }
+        if let Some(impact) = translation.impact {
+            self.impact = impact;
+        }
+    }
+}
+
+/// Localize every category to `lang`
+pub fn localize_categories(categories: &mut [(String, Category)], lang: &str) {
+    for (_, category) in categories {
+        category.localize(lang);
+    }
+}
+
+/// A text field as written in a category file
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum Text {
+    Plain(String),
+    Localized(BTreeMap<String, String>),
+}
+
+impl Text {
+    /// Split into the English text and the translations keyed by lowercase language
+    fn split(self, field: &str) -> Result<(String, BTreeMap<String, String>), String> {
+        match self {
+            Text::Plain(text) => Ok((text, BTreeMap::new())),
+            Text::Localized(mut texts) => {
+                let english = texts
+                    .remove("en")
+                    .ok_or_else(|| format!("localized `{}` needs an `en` entry", field))?;
+                Ok((english, texts.into_iter().map(|(lang, text)| (lang.to_lowercase(), text)).collect()))
+            }
+        }
+    }
+
+    fn join(english: String, translations: BTreeMap<String, String>) -> Text {
+        if translations.is_empty() {
+            return Text::Plain(english);
+        }
+        let mut texts = translations;
+        texts.insert("en".into(), english);
+        Text::Localized(texts)
+    }
+}
+
+/// On-disk layout of a category, with possibly localized text fields
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RawCategory {
+    name: Text,
+    description: Text,
+    severity: Severity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    breakage: Option<Breakage>,
+    impact: Text,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sources: Vec<String>,
+    #[serde(rename = "last-verified", default, skip_serializing_if = "Option::is_none")]
+    last_verified: Option<Date>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    asns: Vec<u32>,
+    rules: Vec<CategoryRule>,
+}
+
+impl TryFrom<RawCategory> for Category {
+    type Error = String;
+
+    fn try_from(raw: RawCategory) -> Result<Self, String> {
+        let (name, names) = raw.name.split("name")?;
+        let (description, descriptions) = raw.description.split("description")?;
+        let (impact, impacts) = raw.impact.split("impact")?;
+
+        let mut translations: BTreeMap<String, Translation> = BTreeMap::new();
+        for (lang, text) in names {
+            translations.entry(lang).or_default().name = Some(text);
+        }
+        for (lang, text) in descriptions {
+            translations.entry(lang).or_default().description = Some(text);
+        }
+        for (lang, text) in impacts {
+            translations.entry(lang).or_default().impact = Some(text);
+        }
+
+        let normalized = raw
+            .rules
+            .iter()
+            .flat_map(|rule| &rule.domains)
+            .filter_map(|domain| Some((domain.written()?.to_string(), domain.to_string())))
+            .collect();
+
+        Ok(Category {
+            name,
+            description,
+            severity: raw.severity,
+            breakage: raw.breakage,
+            impact,
+            sources: raw.sources,
+            last_verified: raw.last_verified,
+            asns: raw.asns,
+            rules: raw.rules,
+            translations,
+            normalized,
+        })
+    }
+}
+
+impl From<Category> for RawCategory {
+    fn from(category: Category) -> Self {
+        let field = |get: fn(&Translation) -> &Option<String>| -> BTreeMap<String, String> {
+            category
+                .translations
+                .iter()
+                .filter_map(|(lang, t)| Some((lang.clone(), get(t).clone()?)))
+                .collect()
+        };
+        let (names, descriptions, impacts) = (field(|t| &t.name), field(|t| &t.description), field(|t| &t.impact));
+
+        RawCategory {
+            name: Text::join(category.name, names),
+            description: Text::join(category.description, descriptions),
+            severity: category.severity,
+            breakage: category.breakage,
+            impact: Text::join(category.impact, impacts),
+            sources: category.sources,
+            last_verified: category.last_verified,
+            asns: category.asns,
+            rules: category.rules,
+        }
+    }
+}
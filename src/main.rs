@@ -2,14 +2,22 @@
 
 use anyhow::{Context, Result};
 use apple_ecocide::{
-    build_output, load_embedded_categories, select_categories, Category, CategorySelection,
-    GenerateParams, Mode, Severity,
+    build_output, localize_categories, select_categories, suggest_categories, unmatched_patterns, Category,
+    CategorySource, Date, DirectorySource, DomainIndex, Error, Feature, GenerateParams, LsVersion, MacosVersion, Mode, NotesStyle, OutputFormat, RemoteSource, RuleSort, Severity, FEATURES,
 };
+#[cfg(not(feature = "no-embed"))]
+use apple_ecocide::EmbeddedSource;
 use clap::builder::styling::{AnsiColor, Effects, Styles};
-use clap::{ArgAction, Parser, ValueEnum};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use std::collections::BTreeMap;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::{env, fs};
-use walkdir::WalkDir;
+
+mod commands;
+
+use commands::lock::LockStatus;
+use commands::summary::{RunSummary, Status};
 
 const STYLES: Styles = Styles::styled()
     .header(AnsiColor::Green.on_default().effects(Effects::BOLD))
@@ -32,12 +40,21 @@ const STYLES: Styles = Styles::styled()
     Block only specific categories:
     \x1b[1;36m$ apple-ecocide --include apple-telemetry google-telemetry -o rules.lsrules\x1b[0m
 
+    Block whichever categories cover a domain:
+    \x1b[1;36m$ apple-ecocide --include-domain gateway.icloud.com -s aggressive -o icloud.lsrules\x1b[0m
+
     Block all telemetry categories using wildcards:
     \x1b[1;36m$ apple-ecocide --include '*-telemetry' -o telemetry.lsrules\x1b[0m
 
     Block everything including aggressive categories:
     \x1b[1;36m$ apple-ecocide --all --severity aggressive -o strict.lsrules\x1b[0m
 
+    Block only the aggressive extras, to layer on top of a recommended rule group:
+    \x1b[1;36m$ apple-ecocide --all -s aggressive --min-severity aggressive -o extras.lsrules\x1b[0m
+
+    Treat a category as less severe than its default classification:
+    \x1b[1;36m$ apple-ecocide --severity minimal --severity-override apple-siri=minimal -o rules.lsrules\x1b[0m
+
     Block everything except specific categories:
     \x1b[1;36m$ apple-ecocide --all -s aggressive --exclude apple-appstore apple-software-updates -o rules.lsrules\x1b[0m
 
@@ -47,6 +64,57 @@ const STYLES: Styles = Styles::styled()
     List all available categories:
     \x1b[1;36m$ apple-ecocide --list --verbose\x1b[0m
 
+    Export the category catalog as CSV:
+    \x1b[1;36m$ apple-ecocide --list --list-format csv > categories.csv\x1b[0m
+
+    Check Little Snitch and deny-process paths on this Mac:
+    \x1b[1;36m$ apple-ecocide doctor\x1b[0m
+
+    Report selected domains that no longer resolve:
+    \x1b[1;36m$ apple-ecocide check --resolve --all -s aggressive\x1b[0m
+
+    List apple.com subdomains from certificate transparency logs missing from categories:
+    \x1b[1;36m$ apple-ecocide discover apple.com\x1b[0m
+
+    Compare Apple categories against a third-party list (JSON report):
+    \x1b[1;36m$ apple-ecocide coverage https://example.com/apple-hosts.txt -i 'apple-*' -s aggressive\x1b[0m
+
+    Fail if the generated rules differ from a reviewed lock file:
+    \x1b[1;36m$ apple-ecocide --all --lock apple-ecocide.lock -o rules.lsrules\x1b[0m
+
+    Print a JSON summary for scripts and CI:
+    \x1b[1;36m$ apple-ecocide --all -o rules.lsrules --json-summary\x1b[0m
+
+    Share the blocking policy with a team as an HTML report:
+    \x1b[1;36m$ apple-ecocide --all --format report-html -o policy.html\x1b[0m
+
+    Write one rules file per vendor so each can be toggled in Little Snitch:
+    \x1b[1;36m$ apple-ecocide --all -s aggressive --split-by vendor -o rules/apple-ecocide.lsrules\x1b[0m
+
+    Keep rule groups small by splitting them into numbered parts:
+    \x1b[1;36m$ apple-ecocide --all -s aggressive --max-rules 100 --on-limit split -o rules.lsrules\x1b[0m
+
+    Export every selected domain and process as CSV for analysis:
+    \x1b[1;36m$ apple-ecocide --all -s aggressive --format csv -o rules.csv\x1b[0m
+
+    Write rules to stdout for another program:
+    \x1b[1;36m$ apple-ecocide --all -o - | ssh mac 'cat > rules.lsrules'\x1b[0m
+
+    Run the web UI locally at http://127.0.0.1:8080/:
+    \x1b[1;36m$ apple-ecocide web\x1b[0m
+
+    Regenerate the rules every day with a launchd agent:
+    \x1b[1;36m$ apple-ecocide --all -o ~/rules.lsrules agent install --interval daily\x1b[0m
+
+    Show what changed in the category data since a release:
+    \x1b[1;36m$ apple-ecocide changelog --since 0.3.0\x1b[0m
+
+    Convert a hosts-style blocklist into a category:
+    \x1b[1;36m$ apple-ecocide import hosts blocklist.txt --slug custom-list --dir categories\x1b[0m
+
+    Convert a hand-maintained Little Snitch rule group into categories:
+    \x1b[1;36m$ apple-ecocide import lsrules existing.lsrules --split-by notes --dir categories\x1b[0m
+
 \x1b[1;32mWildcards:\x1b[0m
     The \x1b[1;36m--include\x1b[0m option supports glob patterns:
       \x1b[1;36m*\x1b[0m           matches any sequence of characters
@@ -58,50 +126,688 @@ const STYLES: Styles = Styles::styled()
       \x1b[1;36m'apple-*'\x1b[0m         all Apple categories
       \x1b[1;36m'google-*'\x1b[0m        all Google categories
 
+    Prefix an include pattern with \x1b[1;36m!\x1b[0m to remove categories matched by earlier
+    patterns. Patterns are evaluated in order, so later ones win:
+      \x1b[1;36m--include 'apple-*' '!apple-appstore'\x1b[0m
+
 \x1b[1;32mCategories:\x1b[0m
     Categories are embedded in the binary by default. Use \x1b[1;36m--categories\x1b[0m to
     override with a custom directory of TOML files.
 ")]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Mode: 'block' blocks selected categories, 'allow' blocks everything except selected
-    #[arg(short, long, value_enum, default_value_t = CliMode::Block)]
+    #[arg(short, long, global = true, value_enum, default_value_t = CliMode::Block, env = "APPLE_ECOCIDE_MODE")]
     mode: CliMode,
 
-    /// Categories to include (supports wildcards: '*-telemetry', 'apple-*')
-    #[arg(short, long, num_args = 1.., value_name = "PATTERN")]
+    /// Categories to include (supports wildcards: '*-telemetry', 'apple-*', and '!pattern' negation)
+    #[arg(short, long, global = true, num_args = 1.., value_name = "PATTERN")]
     include: Option<Vec<String>>,
 
+    /// Include the categories containing these domains (subdomains match parent entries)
+    #[arg(long, global = true, num_args = 1.., value_name = "DOMAIN")]
+    include_domain: Option<Vec<String>>,
+
+    /// Block macOS features by name, e.g. 'siri,spotlight-suggestions,airplay' (see --list-features)
+    #[arg(long, global = true, num_args = 1.., value_delimiter = ',', value_name = "FEATURE")]
+    block_feature: Vec<String>,
+
     /// Categories to exclude from blocking (supports wildcards)
-    #[arg(short = 'x', long, num_args = 1.., value_name = "PATTERN")]
+    #[arg(short = 'x', long, global = true, num_args = 1.., value_name = "PATTERN")]
     exclude: Option<Vec<String>>,
 
     /// Include all categories up to the severity threshold
-    #[arg(short, long, action = ArgAction::SetTrue)]
+    #[arg(short, long, global = true, action = ArgAction::SetTrue, env = "APPLE_ECOCIDE_ALL")]
     all: bool,
 
     /// Maximum severity level to include (minimal < recommended < aggressive)
-    #[arg(short, long, value_enum, default_value_t = CliSeverity::Recommended)]
+    #[arg(short, long, global = true, value_enum, default_value_t = CliSeverity::Recommended, env = "APPLE_ECOCIDE_SEVERITY")]
     severity: CliSeverity,
 
-    /// Output file path
-    #[arg(short, long, default_value = "apple-ecocide.lsrules", value_name = "FILE")]
+    /// Minimum severity level to include, e.g. to generate only the aggressive extras
+    #[arg(long, global = true, value_enum, value_name = "SEVERITY", env = "APPLE_ECOCIDE_MIN_SEVERITY")]
+    min_severity: Option<CliSeverity>,
+
+    /// Re-rank a category before filtering, e.g. 'apple-telemetry=minimal' (repeatable)
+    #[arg(long, global = true, value_name = "PATTERN=SEVERITY", value_parser = parse_severity_override)]
+    severity_override: Vec<(String, Severity)>,
+
+    /// Output file path, or - for stdout
+    #[arg(short, long, default_value = "apple-ecocide.lsrules", value_name = "FILE", env = "APPLE_ECOCIDE_OUTPUT")]
     output: PathBuf,
 
-    /// Path to categories directory (overrides embedded categories)
-    #[arg(short, long, value_name = "DIR")]
+    /// Output format: Little Snitch rules, a human-readable report, or a dataset export
+    #[arg(long, value_enum, default_value_t = CliFormat::Lsrules, value_name = "FORMAT", env = "APPLE_ECOCIDE_FORMAT")]
+    format: CliFormat,
+
+    /// Warn when a rules file has more than N rules
+    #[arg(long, value_name = "N")]
+    max_rules: Option<usize>,
+
+    /// Warn when a rules file is larger than N bytes
+    #[arg(long, value_name = "N")]
+    max_bytes: Option<usize>,
+
+    /// What to do when a rules file exceeds --max-rules or --max-bytes
+    #[arg(long, value_enum, default_value_t = CliOnLimit::Warn, value_name = "ACTION")]
+    on_limit: CliOnLimit,
+
+    /// Write one .lsrules file per category, vendor, or severity next to the output path
+    #[arg(long, value_enum, value_name = "GROUP")]
+    split_by: Option<CliSplitOutput>,
+
+    /// Write one .lsrules file per Little Snitch profile next to the output path: NAME uses the
+    /// command line's parameters, NAME=SEVERITY another severity, NAME=FILE a saved profile (repeatable)
+    #[arg(long, value_name = "NAME[=SEVERITY|FILE]", value_parser = commands::ls_profile::parse, conflicts_with = "split_by")]
+    ls_profile: Vec<commands::ls_profile::LsProfile>,
+
+    /// Path to categories directory, or - to read from stdin (overrides embedded categories)
+    #[arg(short, long, global = true, value_name = "DIR", env = "APPLE_ECOCIDE_CATEGORIES")]
     categories: Option<PathBuf>,
 
+    /// Ignore categories installed by `update --fetch` and use the ones built into the binary
+    #[arg(long, global = true, action = ArgAction::SetTrue, env = "APPLE_ECOCIDE_EMBEDDED_ONLY")]
+    embedded_only: bool,
+
     /// List available categories and exit
     #[arg(short, long, action = ArgAction::SetTrue)]
     list: bool,
 
-    /// Show detailed descriptions and impact information
-    #[arg(short, long, action = ArgAction::SetTrue)]
-    verbose: bool,
+    /// List the features --block-feature accepts and the categories or rules behind them, and exit
+    #[arg(long, action = ArgAction::SetTrue)]
+    list_features: bool,
+
+    /// Output format for --list
+    #[arg(long, value_enum, default_value_t = CliListFormat::Text, value_name = "FORMAT")]
+    list_format: CliListFormat,
+
+    /// Order for --list
+    #[arg(long, value_enum, default_value_t = CliListSort::Slug, value_name = "ORDER")]
+    sort_by: CliListSort,
+
+    /// Show detailed descriptions and impact information, or when generating, why each category was selected;
+    /// repeat (-vv, -vvv) to log selection decisions and skipped rules to stderr
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Only print errors to stderr, no warnings or log messages
+    #[arg(short, long, global = true, action = ArgAction::SetTrue, conflicts_with = "verbose", env = "APPLE_ECOCIDE_QUIET")]
+    quiet: bool,
 
-    /// Custom name for the ruleset in the output file
-    #[arg(long, value_name = "NAME")]
+    /// Language for category names, descriptions and impact notes, e.g. 'de' (falls back to English)
+    #[arg(long, value_name = "LANG", env = "APPLE_ECOCIDE_LANG")]
+    lang: Option<String>,
+
+    /// Custom name for the ruleset in the output file (accepts --description-template placeholders)
+    #[arg(long, value_name = "NAME", env = "APPLE_ECOCIDE_NAME")]
     name: Option<String>,
+
+    /// Little Snitch version to target; older releases reject newer rule keys
+    #[arg(long, value_enum, default_value_t = CliLsVersion::V6, value_name = "VERSION", env = "APPLE_ECOCIDE_LS_VERSION")]
+    ls_version: CliLsVersion,
+
+    /// macOS version to generate for, e.g. '14.4' (detected automatically on macOS)
+    #[arg(long, value_name = "VERSION", env = "APPLE_ECOCIDE_MACOS_VERSION")]
+    macos_version: Option<MacosVersion>,
+
+    /// Check that deny-process paths of the selected categories exist on this system and exit
+    #[arg(long, action = ArgAction::SetTrue)]
+    verify_processes: bool,
+
+    /// Sort rules canonically and omit the version, for byte-identical output across runs
+    #[arg(long, action = ArgAction::SetTrue, env = "APPLE_ECOCIDE_REPRODUCIBLE")]
+    reproducible: bool,
+
+    /// Template for the ruleset description, e.g. 'Built {date} ({severity}, {category_count} categories)'
+    #[arg(long, value_name = "TEMPLATE")]
+    description_template: Option<String>,
+
+    /// How rule notes are written in the output
+    #[arg(long, value_enum, default_value_t = CliNotesStyle::Full, value_name = "STYLE", env = "APPLE_ECOCIDE_NOTES_STYLE")]
+    notes_style: CliNotesStyle,
+
+    /// Template for rule notes, e.g. '[{slug}] {notes} ({severity})'; overrides --notes-style
+    #[arg(long, value_name = "TEMPLATE")]
+    notes_template: Option<String>,
+
+    /// Append each category's sources and last-verified date to its rule notes
+    #[arg(long, action = ArgAction::SetTrue)]
+    notes_provenance: bool,
+
+    /// Add remote-addresses rules for the address ranges announced by each category's ASNs (looked up on RIPEstat)
+    #[cfg(feature = "asn")]
+    #[arg(long, action = ArgAction::SetTrue)]
+    expand_asn: bool,
+
+    /// Include rules marked disabled in their category, shipped disabled in Little Snitch
+    #[arg(long, action = ArgAction::SetTrue)]
+    include_disabled: bool,
+
+    /// Scope domain rules to this app (or executable) instead of any process, e.g. /Applications/Safari.app;
+    /// process rules are left out
+    #[arg(long, value_name = "PATH", env = "APPLE_ECOCIDE_FOR_APP")]
+    for_app: Option<PathBuf>,
+
+    /// Order of rules in the output (default: process denies, domain denies, then allows)
+    #[arg(long, value_enum, value_name = "ORDER", env = "APPLE_ECOCIDE_SORT")]
+    sort: Option<CliSort>,
+
+    /// Write minified JSON instead of pretty-printed
+    #[arg(long, action = ArgAction::SetTrue, env = "APPLE_ECOCIDE_COMPACT")]
+    compact: bool,
+
+    /// Keep N previous versions of the output file (.bak, .bak.2, ...)
+    #[arg(long, default_value_t = 0, value_name = "N", env = "APPLE_ECOCIDE_BACKUP")]
+    backup: usize,
+
+    /// Make the rules temporary: note an expiry ('30d', '2w' or a date) on each rule and write a
+    /// <name>.remove.lsrules that removes them when imported; `prune` strips them once expired
+    #[arg(long, value_name = "WHEN", value_parser = commands::expiry::parse)]
+    expires: Option<Date>,
+
+    /// Add the SHA-256 of the rules and the generation time to the description, and write a .sha256 file next to the output
+    #[arg(long, action = ArgAction::SetTrue, env = "APPLE_ECOCIDE_CHECKSUM")]
+    checksum: bool,
+
+    /// Sign the output with this unencrypted minisign secret key, writing a .minisig file next to it
+    #[cfg(feature = "sign")]
+    #[arg(long, value_name = "FILE", env = "APPLE_ECOCIDE_SIGN_KEY")]
+    sign_key: Option<PathBuf>,
+
+    /// Print a JSON summary of the run instead of the human-readable sentence
+    #[arg(long, env = "APPLE_ECOCIDE_JSON_SUMMARY")]
+    json_summary: bool,
+
+    /// Exit code to use when the output file already matches and is left untouched
+    #[arg(long, default_value_t = 0, value_name = "CODE")]
+    unchanged_exit_code: i32,
+
+    /// Generate and compare with the output file without writing it; exits 4 if up to date, 5 if it would change
+    #[arg(long, conflicts_with = "lock")]
+    check: bool,
+
+    /// Print which categories would be selected and why, and the rules each contributes, without writing anything
+    #[arg(long, conflicts_with_all = ["check", "lock"])]
+    dry_run: bool,
+
+    /// Before overwriting an existing rules file, print the domains and rules the new one adds and removes
+    #[arg(long)]
+    show_diff: bool,
+
+    /// Before writing, print the features (AirDrop, iMessage, ...) the selected rules will break
+    #[arg(long)]
+    advise: bool,
+
+    /// Copy the generated output to the clipboard instead of writing a file
+    #[arg(long, conflicts_with_all = ["check", "dry_run", "split_by"])]
+    clipboard: bool,
+
+    /// Open the written rules file on macOS, so Little Snitch shows its import dialog
+    #[arg(long, conflicts_with_all = ["check", "dry_run", "clipboard"])]
+    open: bool,
+
+    /// Lock file recording the generated rules; later runs fail if the rules would change
+    #[arg(long, value_name = "FILE")]
+    lock: Option<PathBuf>,
+
+    /// Accept changes and rewrite the lock file instead of failing
+    #[arg(long, requires = "lock", action = ArgAction::SetTrue)]
+    update_lock: bool,
+
+    /// Treat include patterns that match no category as an error
+    #[arg(long, action = ArgAction::SetTrue)]
+    strict: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check Little Snitch, its CLI, and deny-process paths on this system
+    Doctor,
+
+    /// Answer a few questions to create a profile and your first rules file
+    Init {
+        /// Where to save the generated profile (generation parameters as TOML)
+        #[arg(long, default_value = "apple-ecocide.toml", value_name = "FILE")]
+        profile: PathBuf,
+    },
+
+    /// Check the selected categories for rot (runs every check when none is given)
+    Check {
+        /// Resolve every selected domain and report ones that no longer exist
+        #[arg(long, action = ArgAction::SetTrue)]
+        resolve: bool,
+
+        /// Report deny-process paths that do not exist on this system
+        #[arg(long, action = ArgAction::SetTrue)]
+        processes: bool,
+
+        /// Maximum number of DNS lookups in flight
+        #[arg(long, default_value_t = 16, value_name = "N")]
+        concurrency: usize,
+    },
+
+    /// List subdomains seen in certificate transparency logs that no category covers
+    Discover {
+        /// Base domain to search, e.g. 'apple.com'
+        #[arg(value_name = "DOMAIN")]
+        domain: String,
+    },
+
+    /// Compare the selected categories with a reference blocklist and print a JSON report
+    Coverage {
+        /// File path or http(s) URL of the reference hosts/adblock/domain list
+        #[arg(value_name = "FILE_OR_URL")]
+        reference: String,
+    },
+
+    /// Show domains and rules added or removed per category between releases
+    Changelog {
+        /// Only show releases after this version, e.g. '0.3.0'
+        #[arg(long, value_name = "VERSION")]
+        since: Option<String>,
+
+        /// Print the changes as JSON
+        #[arg(long, action = ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Remove rules whose --expires date has passed from an existing .lsrules file
+    Prune {
+        /// Rules file to prune, rewritten in place unless --output is given
+        file: PathBuf,
+
+        /// Write the pruned rules here instead
+        #[arg(long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Check for or install newer category data
+    #[command(group(clap::ArgGroup::new("action").args(["check", "fetch"]).required(true).multiple(true)))]
+    Update {
+        /// Compare the categories in use with the latest published release; exits 5 if they are stale
+        #[arg(long, action = ArgAction::SetTrue)]
+        check: bool,
+
+        /// Download the latest category bundle for later runs to use; with --check, only when stale
+        #[arg(long, action = ArgAction::SetTrue)]
+        fetch: bool,
+
+        /// Category bundle to download, a tar archive of categories/ and changelog.toml
+        #[arg(long, value_name = "URL", env = "APPLE_ECOCIDE_BUNDLE_URL", default_value = commands::update::BUNDLE_URL)]
+        bundle_url: String,
+
+        /// Print the result as JSON
+        #[arg(long, action = ArgAction::SetTrue)]
+        json: bool,
+
+        /// Release metadata to compare with, e.g. a mirror of the project's data/changelog.toml
+        #[arg(long, value_name = "URL", env = "APPLE_ECOCIDE_UPDATE_URL", default_value = commands::update::RELEASES_URL)]
+        url: String,
+    },
+
+    /// Suggest categories to enable from the hosts in a Little Snitch traffic log
+    Suggest {
+        /// Traffic log exported with `littlesnitch log-traffic`, a CSV with a hostname column, or a list of hosts
+        #[arg(long, value_name = "FILE_OR_URL")]
+        from_log: String,
+
+        /// Print the suggestions as JSON
+        #[arg(long, action = ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Summarize how many connection attempts a deployed rules file blocked, per category
+    Report {
+        /// Generated .lsrules file that was imported into Little Snitch
+        #[arg(long, value_name = "FILE_OR_URL")]
+        rules: String,
+
+        /// Traffic log exported with `littlesnitch log-traffic`, or a CSV with a hostname column
+        #[arg(long, value_name = "FILE_OR_URL")]
+        log: String,
+
+        /// Print the report as JSON
+        #[arg(long, action = ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Show which of the current connections the selected rules would block (macOS)
+    Snapshot {
+        /// Maximum number of DNS lookups in flight while resolving category domains
+        #[arg(long, default_value_t = 16, value_name = "N")]
+        concurrency: usize,
+
+        /// Print the report as JSON
+        #[arg(long, action = ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Lint every category file and exit non-zero if problems are found
+    Validate {
+        /// Also enforce sorted, globally unique domains, note length, and a non-empty impact
+        #[arg(long, action = ArgAction::SetTrue)]
+        strict: bool,
+
+        /// Print the findings as a JSON array
+        #[arg(long, action = ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// List selected categories whose rules were not verified recently
+    Stale {
+        /// Report categories last verified more than this many days ago
+        #[arg(long, default_value_t = 180, value_name = "N")]
+        days: i64,
+    },
+
+    /// Convert an existing blocklist into a category TOML file
+    #[command(subcommand)]
+    Import(ImportCommand),
+
+    /// Serve the web UI and a JSON API on localhost
+    Web {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1", value_name = "ADDRESS")]
+        host: String,
+
+        /// wasm-pack build to serve instead of the built-in API-backed module
+        #[arg(long, value_name = "DIR")]
+        pkg: Option<PathBuf>,
+    },
+
+    /// Host the selected rules for subscriptions, with a JSON API
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to listen on, e.g. 0.0.0.0 to accept other machines
+        #[arg(long, default_value = "127.0.0.1", value_name = "ADDRESS")]
+        host: String,
+
+        /// Directory of profiles (`<name>.toml` or `<name>.json` generation parameters) to serve at `/profiles/<name>.lsrules`
+        #[arg(long, value_name = "DIR")]
+        profiles: Option<PathBuf>,
+
+        /// Require this token as `Authorization: Bearer <TOKEN>` or a `?token=` query parameter
+        #[arg(long, value_name = "TOKEN", env = "APPLE_ECOCIDE_AUTH_TOKEN", hide_env_values = true)]
+        auth_token: Option<String>,
+
+        /// Require HTTP basic authentication with these credentials
+        #[arg(long, value_name = "USER:PASSWORD", env = "APPLE_ECOCIDE_BASIC_AUTH", hide_env_values = true)]
+        basic_auth: Option<String>,
+
+        /// PEM certificate chain to serve HTTPS with
+        #[cfg(feature = "tls")]
+        #[arg(long, value_name = "FILE", requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+
+        /// PEM private key (PKCS#8 or RSA) of the certificate
+        #[cfg(feature = "tls")]
+        #[arg(long, value_name = "FILE", requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+    },
+
+    /// Manage a launchd agent that regenerates the rules on a schedule
+    #[command(subcommand)]
+    Agent(AgentCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum AgentCommand {
+    /// Install a LaunchAgent running the rest of this command line on a schedule
+    Install {
+        /// How often to regenerate the rules
+        #[arg(long, value_enum, default_value_t = CliInterval::Daily)]
+        interval: CliInterval,
+
+        /// Shell command to run after each successful regeneration, e.g. to re-import rules with the littlesnitch CLI
+        #[arg(long, value_name = "COMMAND")]
+        then: Option<String>,
+    },
+
+    /// Unload and remove the LaunchAgent
+    Uninstall,
+
+    /// Show whether the LaunchAgent is installed and loaded
+    Status,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum CliInterval {
+    Hourly,
+    #[default]
+    Daily,
+    Weekly,
+}
+
+impl From<CliInterval> for commands::agent::Interval {
+    fn from(i: CliInterval) -> Self {
+        match i {
+            CliInterval::Hourly => commands::agent::Interval::Hourly,
+            CliInterval::Daily => commands::agent::Interval::Daily,
+            CliInterval::Weekly => commands::agent::Interval::Weekly,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportCommand {
+    /// Import a hosts file, adblock list, or plain domain list
+    Hosts {
+        /// File path or http(s) URL of the list
+        #[arg(value_name = "FILE_OR_URL")]
+        source: String,
+
+        /// Slug of the new category, used as its file name
+        #[arg(long)]
+        slug: String,
+
+        /// Display name of the new category (defaults to the slug)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Severity of the new category
+        #[arg(long = "category-severity", value_enum, default_value_t = CliSeverity::Recommended, value_name = "SEVERITY")]
+        category_severity: CliSeverity,
+
+        /// Directory to write the category file to
+        #[arg(long, default_value = ".", value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Replace category files that already exist
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Reconstruct category files from an existing .lsrules rule group
+    Lsrules {
+        /// File path or http(s) URL of the .lsrules file
+        #[arg(value_name = "FILE_OR_URL")]
+        source: String,
+
+        /// How rules are grouped into categories
+        #[arg(long, value_enum, default_value_t = CliSplitBy::Notes)]
+        split_by: CliSplitBy,
+
+        /// Slug for rules without notes, or for every rule with '--split-by none'
+        #[arg(long)]
+        slug: Option<String>,
+
+        /// Directory to write the category files to
+        #[arg(long, default_value = ".", value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Replace category files that already exist
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum CliFormat {
+    /// Little Snitch rule group (JSON)
+    #[default]
+    Lsrules,
+    /// Hosts file blocking each denied domain via 0.0.0.0 (no subdomains)
+    Hosts,
+    /// AdGuard / uBlock Origin filter list
+    Adguard,
+    /// dnsmasq configuration
+    Dnsmasq,
+    /// Plain list of denied domains, one per line
+    Domains,
+    /// Markdown report of the selected categories, domains and processes
+    ReportMd,
+    /// Standalone HTML report of the selected categories, domains and processes
+    ReportHtml,
+    /// CSV with one row per category process or domain
+    Csv,
+    /// SQLite database with one row per category process or domain
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliSort {
+    /// Deny rules first, then allow rules
+    Action,
+    /// Grouped by category
+    Category,
+    /// Alphabetical by domain (or process)
+    Domain,
+    /// High-priority rules first
+    Priority,
+}
+
+impl From<CliSort> for RuleSort {
+    fn from(s: CliSort) -> Self {
+        match s {
+            CliSort::Action => RuleSort::Action,
+            CliSort::Category => RuleSort::Category,
+            CliSort::Domain => RuleSort::Domain,
+            CliSort::Priority => RuleSort::Priority,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum CliNotesStyle {
+    /// Category slug and rule notes, e.g. '[apple-ads] Ad tracking'
+    #[default]
+    Full,
+    /// Rule notes only
+    Minimal,
+    /// No notes
+    None,
+}
+
+impl From<CliNotesStyle> for NotesStyle {
+    fn from(s: CliNotesStyle) -> Self {
+        match s {
+            CliNotesStyle::Full => NotesStyle::Full,
+            CliNotesStyle::Minimal => NotesStyle::Minimal,
+            CliNotesStyle::None => NotesStyle::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum CliOnLimit {
+    /// Print a warning and write the file anyway
+    #[default]
+    Warn,
+    /// Write numbered part files ('rules.1.lsrules', 'rules.2.lsrules', ...) that fit the limits
+    Split,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CliSplitOutput {
+    /// One file per category, e.g. 'apple-telemetry.lsrules'
+    Category,
+    /// One file per vendor slug prefix, e.g. 'google.lsrules'
+    Vendor,
+    /// One file per severity level, e.g. 'aggressive.lsrules'
+    Severity,
+}
+
+impl From<CliSplitOutput> for commands::split::SplitBy {
+    fn from(s: CliSplitOutput) -> Self {
+        match s {
+            CliSplitOutput::Category => commands::split::SplitBy::Category,
+            CliSplitOutput::Vendor => commands::split::SplitBy::Vendor,
+            CliSplitOutput::Severity => commands::split::SplitBy::Severity,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum CliListFormat {
+    /// Aligned text for terminals
+    #[default]
+    Text,
+    /// JSON array of category metadata
+    Json,
+    /// YAML list of category metadata
+    Yaml,
+    /// Markdown table
+    Md,
+    /// CSV with a header row
+    Csv,
+}
+
+impl From<CliListFormat> for commands::list::ListFormat {
+    fn from(f: CliListFormat) -> Self {
+        match f {
+            CliListFormat::Text => commands::list::ListFormat::Text,
+            CliListFormat::Json => commands::list::ListFormat::Json,
+            CliListFormat::Yaml => commands::list::ListFormat::Yaml,
+            CliListFormat::Md => commands::list::ListFormat::Markdown,
+            CliListFormat::Csv => commands::list::ListFormat::Csv,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum CliListSort {
+    /// Catalog order, grouped by vendor
+    #[default]
+    Slug,
+    /// Impact score, the categories most worth blocking first
+    Score,
+}
+
+impl From<CliListSort> for commands::list::ListSort {
+    fn from(s: CliListSort) -> Self {
+        match s {
+            CliListSort::Slug => commands::list::ListSort::Slug,
+            CliListSort::Score => commands::list::ListSort::Score,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum CliSplitBy {
+    /// One category per distinct note, honoring '[slug]' prefixes
+    #[default]
+    Notes,
+    /// A single category containing every rule
+    None,
+}
+
+impl From<CliSplitBy> for commands::import::SplitBy {
+    fn from(s: CliSplitBy) -> Self {
+        match s {
+            CliSplitBy::Notes => commands::import::SplitBy::Notes,
+            CliSplitBy::None => commands::import::SplitBy::None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
@@ -143,69 +849,829 @@ impl From<CliSeverity> for Severity {
     }
 }
 
-/// Source of categories (embedded or filesystem)
-enum CategorySource {
-    Embedded,
-    Filesystem(PathBuf),
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+enum CliLsVersion {
+    /// Little Snitch 4 (no rule priorities or disabled rules)
+    #[value(name = "4")]
+    V4,
+    /// Little Snitch 5
+    #[value(name = "5")]
+    V5,
+    /// Little Snitch 6
+    #[default]
+    #[value(name = "6")]
+    V6,
+}
+
+impl From<CliLsVersion> for LsVersion {
+    fn from(v: CliLsVersion) -> Self {
+        match v {
+            CliLsVersion::V4 => LsVersion::V4,
+            CliLsVersion::V5 => LsVersion::V5,
+            CliLsVersion::V6 => LsVersion::V6,
+        }
+    }
+}
+
+fn parse_severity_override(s: &str) -> Result<(String, Severity), String> {
+    let (pattern, severity) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected PATTERN=SEVERITY, got '{}'", s))?;
+    let severity = severity.parse::<Severity>().map_err(|e| e.to_string())?;
+    Ok((pattern.to_string(), severity))
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+/// Detect the running macOS version via `sw_vers`
+#[cfg(target_os = "macos")]
+fn detect_macos_version() -> Option<MacosVersion> {
+    let output = std::process::Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()?;
+    String::from_utf8(output.stdout).ok()?.trim().parse().ok()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn detect_macos_version() -> Option<MacosVersion> {
+    None
+}
+
+fn main() {
+    if let Err(err) = run() {
+        report_error(&err);
+        std::process::exit(commands::exit::for_error(&err));
+    }
+}
+
+/// Print a fatal error, rendering category parse errors with their source snippet
+fn report_error(err: &anyhow::Error) {
+    let diagnostic = err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<Error>())
+        .filter(|cause| miette::Diagnostic::source_code(*cause).is_some());
+    if let Some(diagnostic) = diagnostic {
+        let mut rendered = String::new();
+        if miette::GraphicalReportHandler::new().render_report(&mut rendered, diagnostic).is_ok() {
+            for context in err.chain().take_while(|cause| cause.downcast_ref::<Error>().is_none()) {
+                eprintln!("Error: {context}");
+            }
+            eprint!("\n{rendered}");
+            return;
+        }
+    }
+    eprintln!("Error: {err:?}");
+}
+
+/// Send library log events to stderr: warnings by default, more with each `-v`
+fn init_logging(verbose: u8, quiet: bool) {
+    use tracing::Level;
+
+    let level = match (quiet, verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0 | 1) => Level::WARN,
+        (false, 2) => Level::INFO,
+        (false, 3) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::IsTerminal::is_terminal(&std::io::stderr()))
+        .with_target(false)
+        .without_time()
+        .init();
+}
+
+fn run() -> Result<()> {
+    let args = Args::try_parse().unwrap_or_else(|err| {
+        let _ = err.print();
+        std::process::exit(commands::exit::for_usage(&err));
+    });
+    init_logging(args.verbose, args.quiet);
+
+    if args.open {
+        if !cfg!(target_os = "macos") {
+            anyhow::bail!("--open needs macOS with Little Snitch installed; import the rules file manually instead");
+        }
+        if args.format != CliFormat::Lsrules || args.output.as_os_str() == "-" {
+            anyhow::bail!("--open imports an .lsrules file and cannot be combined with --format or --output -");
+        }
+    }
+
+    #[cfg(feature = "sign")]
+    if args.sign_key.is_some() && (args.output.as_os_str() == "-" || args.clipboard) {
+        anyhow::bail!("--sign-key writes a .minisig file next to the output and cannot be combined with --output - or --clipboard");
+    }
+    #[cfg(feature = "sign")]
+    if let Some(key) = &args.sign_key {
+        // Fail before generating anything rather than after writing the rules
+        commands::sign::SigningKey::load(key)?;
+    }
+
+    if let Some(Command::Changelog { since, json }) = &args.command {
+        return commands::changelog::run(since.as_deref(), *json);
+    }
 
-    let (categories, source) = load_categories(args.categories.as_deref())?;
+    if let Some(Command::Prune { file, output }) = &args.command {
+        let today = Date::today().context("No system clock to compare expiry dates with")?;
+        let (removed, left) = commands::expiry::prune(file, output.as_deref(), today)?;
+        println!(
+            "Removed {} expired rules from {} ({} left)",
+            removed,
+            output.as_deref().unwrap_or(file).display(),
+            left
+        );
+        return Ok(());
+    }
+
+    if let Some(Command::Update {
+        check,
+        fetch,
+        bundle_url,
+        json,
+        url,
+    }) = &args.command
+    {
+        let stale = !*check || commands::update::check(url, *json)?;
+        if *fetch && stale {
+            commands::update::fetch(bundle_url)?;
+        } else if stale {
+            std::process::exit(commands::exit::OUTDATED);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Agent(agent)) = &args.command {
+        return run_agent(agent);
+    }
+
+    let source = category_source(args.categories.as_deref(), args.embedded_only)?;
+    let mut categories = source.load().context("Failed to load categories")?;
+    if let Some(lang) = &args.lang {
+        localize_categories(&mut categories, lang);
+    }
+
+    match &args.command {
+        Some(Command::Doctor) => return commands::doctor::run(&categories, source.as_ref()),
+        Some(Command::Discover { domain }) => return commands::discover::run(&categories, domain),
+        Some(Command::Report { rules, log, json }) => {
+            return commands::effectiveness::run(&categories, rules, log, *json)
+        }
+        Some(Command::Validate { strict, json }) => {
+            if !commands::validate::run(&categories, *strict, *json)? {
+                std::process::exit(commands::exit::INVALID);
+            }
+            return Ok(());
+        }
+        Some(Command::Web { port, host, pkg }) => {
+            return commands::web::run(&categories, host, *port, pkg.as_deref())
+        }
+        Some(Command::Import(ImportCommand::Hosts {
+            source,
+            slug,
+            name,
+            category_severity,
+            dir,
+            force,
+        })) => {
+            let path = commands::import::run_hosts(
+                source,
+                slug,
+                name.as_deref(),
+                (*category_severity).into(),
+                dir,
+                *force,
+            )?;
+            println!("Wrote {}", path.display());
+            return Ok(());
+        }
+        Some(Command::Import(ImportCommand::Lsrules {
+            source,
+            split_by,
+            slug,
+            dir,
+            force,
+        })) => {
+            let paths = commands::import::run_lsrules(source, (*split_by).into(), slug.as_deref(), dir, *force)?;
+            for path in paths {
+                println!("Wrote {}", path.display());
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
 
     if args.list {
-        list_categories(&categories, &source, args.verbose);
+        return commands::list::run(
+            &categories,
+            source.as_ref(),
+            args.list_format.into(),
+            args.sort_by.into(),
+            args.verbose > 0,
+        );
+    }
+
+    if args.list_features {
+        commands::list::features();
         return Ok(());
     }
 
+    let mut warnings = Vec::new();
+    let mut include = args.include.clone().unwrap_or_default();
+    include.extend(resolve_include_domains(
+        args.include_domain.as_deref().unwrap_or_default(),
+        &categories,
+        args.strict,
+        &mut warnings,
+    )?);
+    let mut severity_overrides = args.severity_override.clone();
+    if !args.block_feature.is_empty() {
+        if args.mode == CliMode::Allow {
+            anyhow::bail!("--block-feature blocks features and cannot be combined with --mode allow");
+        }
+        let features = args
+            .block_feature
+            .iter()
+            .map(|id| Feature::find(id).ok_or_else(|| anyhow::anyhow!(unknown_feature_message(id))))
+            .collect::<Result<Vec<_>>>()?;
+        let blocked = Feature::select(&features, &categories, args.severity.into())?;
+        // Naming a feature blocks it whatever the severity of its categories
+        for slug in &blocked.include {
+            severity_overrides.push((slug.clone(), args.severity.into()));
+        }
+        include.extend(blocked.include);
+        include.extend(blocked.categories.iter().map(|(slug, _)| slug.clone()));
+        categories.extend(blocked.categories);
+    }
+
     let params = GenerateParams {
         mode: args.mode.into(),
         severity: args.severity.into(),
-        include: args.include.clone().unwrap_or_default(),
+        min_severity: args.min_severity.map(Into::into),
+        severity_overrides,
+        include,
         exclude: args.exclude.clone().unwrap_or_default(),
         all: args.all,
         name: args.name.clone(),
+        ls_version: args.ls_version.into(),
+        macos_version: args.macos_version.or_else(detect_macos_version),
+        reproducible: args.reproducible,
+        notes_style: args.notes_style.into(),
+        notes_template: args.notes_template.clone(),
+        description_template: args.description_template.clone(),
+        sort: args.sort.map(Into::into),
+        include_disabled: args.include_disabled,
+        app: args.for_app.as_deref().map(commands::processes::app_executable),
+        provenance_notes: args.notes_provenance,
+        asn_prefixes: BTreeMap::new(),
+    };
+    params.validate()?;
+
+    let params = match &args.command {
+        Some(Command::Init { profile }) => {
+            if args.output.as_os_str() == "-" {
+                anyhow::bail!("init asks questions on stdout and cannot be combined with --output -");
+            }
+            commands::init::run(params, &categories, profile)?
+        }
+        _ => params,
     };
 
     let selection = select_categories(&params, &categories);
 
-    if selection.denied.is_empty() && selection.allowed.is_empty() {
+    if !selection.unmatched.is_empty() {
+        if args.strict {
+            for warning in &warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            for pattern in &selection.unmatched {
+                eprintln!("{}", unmatched_message(pattern, &categories));
+            }
+            return Err(Error::UnmatchedPatterns(selection.unmatched).into());
+        }
+        warnings.extend(selection.unmatched.iter().map(|p| unmatched_message(p, &categories)));
+    }
+    let override_patterns: Vec<String> = params
+        .severity_overrides
+        .iter()
+        .map(|(pattern, _)| pattern.clone())
+        .collect();
+    for pattern in unmatched_patterns(&params.exclude, &categories)
+        .into_iter()
+        .chain(unmatched_patterns(&override_patterns, &categories))
+    {
+        warnings.push(unmatched_message(&pattern, &categories));
+    }
+    if !args.quiet {
+        for warning in &warnings {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+
+    if args.verbose > 0 {
+        eprintln!("Selection:");
+        for (slug, _) in &categories {
+            let action = selection.action(slug).map_or("skip", |action| action.as_str());
+            eprintln!("  {:5} {:30} {}", action, slug, selection.explain(slug));
+        }
+    }
+
+    if let Some(Command::Suggest { from_log, json }) = &args.command {
+        return commands::suggest::run(&categories, &selection, from_log, *json);
+    }
+
+    if let Some(Command::Snapshot { concurrency, json }) = args.command {
+        return commands::snapshot::run(&categories, &selection, concurrency, json);
+    }
+
+    if selection.is_empty() {
         eprintln!("No categories selected. Use --include or --all to select categories.");
-        std::process::exit(1);
+        std::process::exit(commands::exit::NOTHING_SELECTED);
+    }
+
+    let selected: Vec<_> = categories
+        .iter()
+        .filter(|(slug, _)| selection.denied.contains(slug) || selection.allowed.contains(slug))
+        .collect();
+
+    if args.verify_processes {
+        if !commands::processes::run(selected) {
+            std::process::exit(commands::exit::INVALID);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Check {
+        resolve,
+        processes,
+        concurrency,
+    }) = args.command
+    {
+        if !commands::check::run(&selected, resolve, processes, concurrency) {
+            std::process::exit(commands::exit::INVALID);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Stale { days }) = args.command {
+        if !commands::stale::run(&selected, days)? {
+            std::process::exit(commands::exit::OUTDATED);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Coverage { reference }) = &args.command {
+        return commands::coverage::run(&selected, reference);
+    }
+
+    #[cfg(feature = "asn")]
+    let params = if args.expand_asn {
+        GenerateParams {
+            asn_prefixes: commands::asn::fetch(&selected)?,
+            ..params
+        }
+    } else {
+        params
+    };
+
+    let mut output = build_output(&params, &categories, &selection);
+
+    if let Some(Command::Serve {
+        port,
+        host,
+        profiles,
+        auth_token,
+        basic_auth,
+        #[cfg(feature = "tls")]
+        tls_cert,
+        #[cfg(feature = "tls")]
+        tls_key,
+    }) = &args.command
+    {
+        let options = commands::serve::Options {
+            host,
+            port: *port,
+            profiles: profiles.as_deref(),
+            auth_token: auth_token.as_deref(),
+            basic_auth: basic_auth.as_deref(),
+            #[cfg(feature = "tls")]
+            tls: tls_cert.as_deref().zip(tls_key.as_deref()),
+        };
+        return commands::serve::run(&categories, &params, &options);
+    }
+
+    if let Some(date) = args.expires {
+        if args.format != CliFormat::Lsrules || args.split_by.is_some() {
+            anyhow::bail!("--expires notes the expiry in .lsrules rules and cannot be combined with --format or --split-by");
+        }
+        commands::expiry::stamp(&mut output, date);
+    }
+
+    if args.checksum {
+        commands::sign::stamp(&mut output, args.reproducible)?;
+    }
+
+    if args.advise {
+        commands::plan::advise(&params, &categories, &selection);
+    }
+
+    if args.dry_run {
+        commands::plan::print(&params, &categories, &selection, &output);
+        return Ok(());
+    }
+
+    if let Some(lock_path) = &args.lock {
+        match commands::lock::check(lock_path, &output, args.update_lock)? {
+            LockStatus::Created => eprintln!("Created lock file {}", lock_path.display()),
+            LockStatus::Updated => eprintln!("Updated lock file {}", lock_path.display()),
+            LockStatus::Unchanged => {}
+        }
+    }
+
+    if let Some(split_by) = args.split_by {
+        return write_split(&args, &params, &categories, &selection, split_by.into(), warnings);
+    }
+
+    if !args.ls_profile.is_empty() {
+        return write_ls_profiles(&args, &params, &categories, warnings);
+    }
+
+    if args.format == CliFormat::Lsrules && args.output.as_os_str() != "-" && !args.clipboard {
+        let output_path = resolve_output_path(&args.output)?;
+        if let Some(date) = args.expires.filter(|_| !args.check) {
+            let removal = commands::expiry::write_removal(&output_path, &output, date)?;
+            if !args.quiet {
+                eprintln!("Wrote {}; import it once the rules expire on {}", removal.display(), date);
+            }
+        }
+        let summaries = write_lsrules(&args, &output_path, output, &selection, &warnings)?;
+        return finish(&args, summaries, false);
+    }
+
+    let rendered: Vec<u8> = match args.format {
+        CliFormat::Lsrules => rules_json(&output, args.compact)?.into_bytes(),
+        CliFormat::Hosts => output.to_format(OutputFormat::Hosts)?.into_bytes(),
+        CliFormat::Adguard => output.to_format(OutputFormat::Adguard)?.into_bytes(),
+        CliFormat::Dnsmasq => output.to_format(OutputFormat::Dnsmasq)?.into_bytes(),
+        CliFormat::Domains => output.to_format(OutputFormat::Domains)?.into_bytes(),
+        CliFormat::ReportMd => {
+            commands::report::markdown(&output, &selected, &selection, &params).into_bytes()
+        }
+        CliFormat::ReportHtml => {
+            commands::report::html(&output, &selected, &selection, &params).into_bytes()
+        }
+        CliFormat::Csv => commands::export::csv(&commands::export::rows(&params, &selected, &selection)).into_bytes(),
+        #[cfg(feature = "sqlite")]
+        CliFormat::Sqlite => commands::export::sqlite(&commands::export::rows(&params, &selected, &selection))?,
+    };
+
+    if args.clipboard {
+        #[cfg(feature = "sqlite")]
+        if args.format == CliFormat::Sqlite {
+            anyhow::bail!("--clipboard copies text and cannot be combined with --format sqlite");
+        }
+        commands::clipboard::copy(&rendered)?;
+        let summary = RunSummary::new(Status::Clipboard, "clipboard".to_string(), &output, &selection, warnings);
+        return finish(&args, vec![summary], false);
+    }
+
+    if args.output.as_os_str() == "-" {
+        if args.check {
+            anyhow::bail!("--check compares with an output file and cannot be combined with --output -");
+        }
+        if args.format == CliFormat::Lsrules {
+            if let Some(reason) = limits(&args).exceeded(&output, rendered.len()) {
+                let warning = format!("generated rules have {}; Little Snitch may become sluggish", reason);
+                if !args.quiet {
+                    eprintln!("Warning: {}", warning);
+                }
+                warnings.push(warning);
+            }
+        }
+
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&rendered)?;
+        if args.format == CliFormat::Lsrules {
+            stdout.write_all(b"\n")?;
+        }
+
+        let summary = RunSummary::new(Status::Stdout, "stdout".to_string(), &output, &selection, warnings);
+        if args.json_summary {
+            eprintln!("{}", serde_json::to_string_pretty(&summary)?);
+        } else if !args.quiet {
+            eprintln!("{}", summary.message());
+        }
+        return Ok(());
     }
 
-    let output = build_output(&params, &categories, &selection);
     let output_path = resolve_output_path(&args.output)?;
-    let json = serde_json::to_string_pretty(&output)?;
-    fs::write(&output_path, &json)?;
+    let status = write_output(&args, &output_path, &rendered)?;
+    let summary = RunSummary::new(status, output_path.display().to_string(), &output, &selection, warnings);
+    finish(&args, vec![summary], false)
+}
 
-    print_summary(&output_path, &output, &selection);
+/// Write an output file unless it already holds `content`; with `--check` only compare
+fn write_output(args: &Args, path: &Path, content: &[u8]) -> Result<Status> {
+    if args.show_diff && args.format == CliFormat::Lsrules {
+        commands::diff::print(path, content)?;
+    }
+    if args.check {
+        return Ok(if commands::write::is_current(path, content) {
+            Status::Unchanged
+        } else {
+            Status::Outdated
+        });
+    }
+    let status = if commands::write::write_if_changed(path, content, args.backup)? {
+        Status::Written
+    } else {
+        Status::Unchanged
+    };
+    write_sidecars(args, path, content)?;
+    Ok(status)
+}
 
+/// Write the `.sha256` and `.minisig` files requested by `--checksum` and `--sign-key`
+fn write_sidecars(args: &Args, path: &Path, content: &[u8]) -> Result<()> {
+    if args.checksum {
+        commands::sign::write_checksum(path, content)?;
+    }
+    #[cfg(feature = "sign")]
+    if let Some(key) = &args.sign_key {
+        commands::sign::SigningKey::load(key)?.write_signature(path, content)?;
+    }
     Ok(())
 }
 
-fn print_summary(output_path: &Path, output: &apple_ecocide::LsRulesOutput, selection: &CategorySelection) {
-    let total_categories = selection.denied.len() + selection.allowed.len();
-    if selection.allowed.is_empty() {
-        println!(
-            "Generated {} with {} rules ({} deny) from {} categories",
-            output_path.display(),
-            output.rules.len(),
-            output.rules.len(),
-            total_categories
-        );
+/// Print the summaries of the written files and apply `--check` and `--unchanged-exit-code`
+///
+/// A single file is summarized as one JSON object unless `as_array` is set,
+/// several files always as an array.
+fn finish(args: &Args, summaries: Vec<RunSummary>, as_array: bool) -> Result<()> {
+    if args.json_summary {
+        match summaries.as_slice() {
+            [only] if !as_array => println!("{}", serde_json::to_string_pretty(only)?),
+            all => println!("{}", serde_json::to_string_pretty(all)?),
+        }
+    } else if !args.quiet {
+        for summary in &summaries {
+            println!("{}", summary.message());
+        }
+    }
+
+    if args.open {
+        for summary in &summaries {
+            open_rules(Path::new(&summary.output))?;
+        }
+    }
+
+    let unchanged = summaries.iter().all(|s| matches!(s.status, Status::Unchanged));
+    if args.check {
+        std::process::exit(if unchanged { commands::exit::UNCHANGED } else { commands::exit::OUTDATED });
+    }
+    if args.unchanged_exit_code != 0 && unchanged {
+        std::process::exit(args.unchanged_exit_code);
+    }
+    Ok(())
+}
+
+/// Hand a rules file to Little Snitch through `open`, which shows the import dialog
+fn open_rules(path: &Path) -> Result<()> {
+    let status = std::process::Command::new("open")
+        .arg(path)
+        .status()
+        .context("Failed to run 'open'")?;
+    if !status.success() {
+        anyhow::bail!("'open {}' exited with {}", path.display(), status);
+    }
+    Ok(())
+}
+
+fn limits(args: &Args) -> commands::chunk::Limits {
+    commands::chunk::Limits {
+        max_rules: args.max_rules,
+        max_bytes: args.max_bytes,
+        compact: args.compact,
+    }
+}
+
+/// Serialize rules as written to files and stdout
+fn rules_json(output: &apple_ecocide::LsRulesOutput, compact: bool) -> apple_ecocide::Result<String> {
+    if compact {
+        output.to_json()
     } else {
-        let allow_count = output.rules.iter().filter(|r| r.action == "allow").count();
-        let deny_count = output.rules.len() - allow_count;
-        println!(
-            "Generated {} with {} rules ({} allow, {} deny) from {} categories",
-            output_path.display(),
-            output.rules.len(),
-            allow_count,
-            deny_count,
-            total_categories
+        output.to_json_pretty()
+    }
+}
+
+/// Write a rules file, warning about or splitting it into numbered parts when it exceeds the limits
+fn write_lsrules(
+    args: &Args,
+    path: &Path,
+    output: apple_ecocide::LsRulesOutput,
+    selection: &apple_ecocide::CategorySelection,
+    warnings: &[String],
+) -> Result<Vec<RunSummary>> {
+    let mut warnings = warnings.to_vec();
+    let json = rules_json(&output, args.compact)?;
+
+    let limits = limits(args);
+    if let Some(reason) = limits.exceeded(&output, json.len()) {
+        if args.on_limit == CliOnLimit::Split {
+            let mut summaries = Vec::new();
+            let parts = commands::chunk::split(output, limits, |part| {
+                if args.checksum {
+                    commands::sign::stamp(part, args.reproducible)?;
+                }
+                Ok(())
+            })?;
+            for (i, part) in parts.into_iter().enumerate() {
+                let part_path = commands::chunk::part_path(path, i + 1);
+                let json = rules_json(&part, args.compact)?;
+                let status = write_output(args, &part_path, json.as_bytes())?;
+                summaries.push(RunSummary::new(
+                    status,
+                    part_path.display().to_string(),
+                    &part,
+                    selection,
+                    warnings.clone(),
+                ));
+            }
+            return Ok(summaries);
+        }
+
+        let warning = format!(
+            "{} has {}; Little Snitch may become sluggish, consider --on-limit split",
+            path.display(),
+            reason
         );
+        if !args.quiet {
+            eprintln!("Warning: {}", warning);
+        }
+        warnings.push(warning);
+    }
+
+    let status = write_output(args, path, json.as_bytes())?;
+    Ok(vec![RunSummary::new(status, path.display().to_string(), &output, selection, warnings)])
+}
+
+/// Write one rules file per group next to the output path (`--split-by`)
+fn write_split(
+    args: &Args,
+    params: &GenerateParams,
+    categories: &[(String, Category)],
+    selection: &apple_ecocide::CategorySelection,
+    split_by: commands::split::SplitBy,
+    warnings: Vec<String>,
+) -> Result<()> {
+    if args.format != CliFormat::Lsrules || args.output.as_os_str() == "-" {
+        anyhow::bail!("--split-by writes .lsrules files and cannot be combined with --format or --output -");
+    }
+
+    let output_path = resolve_output_path(&args.output)?;
+    let dir = output_path.parent().unwrap_or(Path::new("."));
+    if !args.check {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut summaries = Vec::new();
+    for (group, group_selection) in commands::split::groups(params, categories, selection, split_by) {
+        let mut output = build_output(params, categories, &group_selection);
+        output.name = format!("{} ({})", output.name, group);
+        if args.checksum {
+            commands::sign::stamp(&mut output, args.reproducible)?;
+        }
+
+        let path = dir.join(format!("{}.lsrules", group));
+        summaries.extend(write_lsrules(args, &path, output, &group_selection, &warnings)?);
+    }
+
+    finish(args, summaries, true)
+}
+
+/// Write one rules file per Little Snitch profile next to the output path (`--ls-profile`)
+fn write_ls_profiles(
+    args: &Args,
+    params: &GenerateParams,
+    categories: &[(String, Category)],
+    warnings: Vec<String>,
+) -> Result<()> {
+    if args.format != CliFormat::Lsrules || args.output.as_os_str() == "-" || args.clipboard {
+        anyhow::bail!("--ls-profile writes .lsrules files and cannot be combined with --format, --output - or --clipboard");
+    }
+
+    let output_path = resolve_output_path(&args.output)?;
+    let mut paths = std::collections::BTreeSet::new();
+    if let Some(profile) = args.ls_profile.iter().find(|profile| !paths.insert(profile.path(&output_path))) {
+        anyhow::bail!("--ls-profile '{}' is given more than once", profile.name);
+    }
+    if !args.check {
+        if let Some(dir) = output_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+    }
+
+    // Parameters outlive the outputs borrowing from them
+    let profile_params = args
+        .ls_profile
+        .iter()
+        .map(|profile| profile.params(params))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut summaries = Vec::new();
+    for (profile, params) in args.ls_profile.iter().zip(&profile_params) {
+        let selection = select_categories(params, categories);
+        let mut output = build_output(params, categories, &selection);
+        profile.annotate(&mut output);
+        let path = profile.path(&output_path);
+        if let Some(date) = args.expires {
+            commands::expiry::stamp(&mut output, date);
+            if !args.check {
+                let removal = commands::expiry::write_removal(&path, &output, date)?;
+                if !args.quiet {
+                    eprintln!("Wrote {}; import it once the rules expire on {}", removal.display(), date);
+                }
+            }
+        }
+        if args.checksum {
+            commands::sign::stamp(&mut output, args.reproducible)?;
+        }
+
+        summaries.extend(write_lsrules(args, &path, output, &selection, &warnings)?);
+    }
+
+    finish(args, summaries, true)
+}
+
+fn run_agent(command: &AgentCommand) -> Result<()> {
+    match command {
+        AgentCommand::Install { interval, then } => {
+            let path = commands::agent::install(&commands::agent::generation_args(), (*interval).into(), then.as_deref())?;
+            println!("Installed and loaded {}", path.display());
+        }
+        AgentCommand::Uninstall => {
+            if commands::agent::uninstall()? {
+                println!("Uninstalled the apple-ecocide agent");
+            } else {
+                println!("The apple-ecocide agent is not installed");
+            }
+        }
+        AgentCommand::Status => commands::agent::status()?,
+    }
+    Ok(())
+}
+
+/// Slugs of the categories listing each `--include-domain`
+///
+/// Fails when no domain is listed anywhere, as an empty include list would select
+/// every category, and with `strict` when any one is not.
+fn resolve_include_domains(
+    domains: &[String],
+    categories: &[(String, Category)],
+    strict: bool,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<String>> {
+    if domains.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let index = DomainIndex::new(categories);
+    let mut slugs = Vec::new();
+    for domain in domains {
+        let found = index.lookup(domain);
+        if found.is_empty() {
+            if strict {
+                anyhow::bail!("--include-domain '{}' is not listed in any category", domain);
+            }
+            warnings.push(format!("domain '{}' is not listed in any category", domain));
+        }
+        slugs.extend(found.into_iter().map(String::from));
+    }
+    if slugs.is_empty() {
+        anyhow::bail!("--include-domain matched no category: {}", domains.join(", "));
+    }
+    Ok(slugs)
+}
+
+fn unknown_feature_message(id: &str) -> String {
+    let ids: Vec<&str> = FEATURES.iter().map(|feature| feature.id).collect();
+    format!("unknown feature '{}', expected one of: {}", id, ids.join(", "))
+}
+
+fn unmatched_message(pattern: &str, categories: &[(String, Category)]) -> String {
+    if pattern.contains(['*', '?', '[']) {
+        return format!("pattern '{}' did not match any category", pattern);
+    }
+
+    let suggestions = suggest_categories(pattern, categories);
+    match suggestions.as_slice() {
+        [] => format!("unknown category '{}'", pattern),
+        [only] => format!("unknown category '{}', did you mean '{}'?", pattern, only),
+        many => format!(
+            "unknown category '{}', did you mean one of: {}?",
+            pattern,
+            many.join(", ")
+        ),
     }
 }
 
@@ -230,74 +1696,94 @@ fn find_categories_dir(path: &Path) -> Option<PathBuf> {
     .find(|p| p.is_dir())
 }
 
-fn load_categories(custom_path: Option<&Path>) -> Result<(Vec<(String, Category)>, CategorySource)> {
-    if let Some(path) = custom_path {
-        if let Some(dir) = find_categories_dir(path) {
-            let categories = load_categories_from_dir(&dir)?;
-            return Ok((categories, CategorySource::Filesystem(dir)));
+/// Where categories come from: `--categories`, else a bundle installed by
+/// `update --fetch` that is newer than the embedded data, else the embedded data
+fn category_source(custom_path: Option<&Path>, embedded_only: bool) -> Result<Box<dyn CategorySource>> {
+    if custom_path.is_none() && !embedded_only {
+        if let Some(dir) = commands::update::fetched_categories() {
+            return Ok(Box::new(DirectorySource::new(dir)));
         }
-        anyhow::bail!(
-            "Categories directory not found: {}. Try specifying a valid --categories <path>",
-            path.display()
-        );
     }
-
-    let categories = load_embedded_categories().map_err(|e| anyhow::anyhow!("Failed to load categories: {}", e))?;
-    
-    Ok((categories, CategorySource::Embedded))
+    Ok(match custom_path {
+        Some(path) if path.as_os_str() == "-" => Box::new(commands::stdin::StdinSource),
+        Some(path) if path.to_str().is_some_and(|p| p.starts_with("http://") || p.starts_with("https://")) => {
+            Box::new(RemoteSource {
+                urls: vec![path.display().to_string()],
+            })
+        }
+        Some(path) => match find_categories_dir(path) {
+            Some(dir) => Box::new(DirectorySource::new(dir)),
+            None => anyhow::bail!(
+                "Categories directory not found: {}. Try specifying a valid --categories <path>",
+                path.display()
+            ),
+        },
+        #[cfg(not(feature = "no-embed"))]
+        None => Box::new(EmbeddedSource),
+        #[cfg(feature = "no-embed")]
+        None => match find_categories_dir(Path::new("categories")) {
+            Some(dir) => Box::new(DirectorySource::new(dir)),
+            None => anyhow::bail!("This build has no embedded categories. Specify them with --categories <path>"),
+        },
+    })
 }
 
-fn load_categories_from_dir(path: &Path) -> Result<Vec<(String, Category)>> {
-    let mut categories = Vec::new();
 
-    for entry in WalkDir::new(path)
-        .max_depth(1)
-        .into_iter()
-        .filter_map(Result::ok)
-    {
-        let file_path = entry.path();
-        if file_path.extension().is_some_and(|ext| ext == "toml") {
-            let content = fs::read_to_string(file_path)
-                .context(format!("Failed to read: {}", file_path.display()))?;
-            let category: Category = toml::from_str(&content)
-                .context(format!("Failed to parse: {}", file_path.display()))?;
-            let slug = file_path
-                .file_stem()
-                .map(|s| s.to_string_lossy().into_owned())
-                .unwrap_or_default();
-            categories.push((slug, category));
-        }
-    }
-
-    categories.sort_by(|a, b| a.0.cmp(&b.0));
-    Ok(categories)
-}
-
-fn list_categories(categories: &[(String, Category)], source: &CategorySource, verbose: bool) {
-    match source {
-        CategorySource::Embedded => println!("Available categories (embedded):\n"),
-        CategorySource::Filesystem(path) => {
-            println!("Available categories (from {}):\n", path.display())
-        }
-    }
-
-    for (slug, cat) in categories {
-        if verbose {
-            println!("  {} ({})", slug, cat.severity);
-            println!("    Name: {}", cat.name);
-            println!("    Description: {}", cat.description);
-            println!(
-                "    Impact: {}",
-                cat.impact.trim().replace('\n', "\n            ")
-            );
-            println!();
-        } else {
-            println!("  {slug:30} [{:11}] {}", cat.severity, cat.name);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn categories() -> Vec<(String, Category)> {
+        let category = Category::from_toml_str(
+            r#"
+name = "Apple Advertising"
+description = "Blocks Apple's advertising SDK"
+severity = "minimal"
+impact = "- Ads blocked"
+
+[[rules]]
+notes = "Apple advertising SDK"
+domains = ["iadsdk.apple.com"]
+"#,
+        )
+        .unwrap();
+        vec![("apple-ads".to_string(), category)]
     }
 
-    if !verbose {
-        println!("\nUse --verbose for detailed descriptions and impact information.");
+    fn domains(domains: &[&str]) -> Vec<String> {
+        domains.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn usage_errors_exit_with_failure() {
+        let code = |args: &[&str]| Args::try_parse_from(args).map_or_else(|err| commands::exit::for_usage(&err), |_| -1);
+        assert_eq!(code(&["apple-ecocide", "--bogus"]), commands::exit::FAILURE);
+        assert_eq!(code(&["apple-ecocide", "--severity", "extreme"]), commands::exit::FAILURE);
+        assert_eq!(code(&["apple-ecocide", "--help"]), 0);
+        assert_eq!(code(&["apple-ecocide", "--version"]), 0);
     }
-}
 
+    #[test]
+    fn include_domain_selects_listing_categories() {
+        let mut warnings = Vec::new();
+        let slugs = resolve_include_domains(&domains(&["iadsdk.apple.com"]), &categories(), false, &mut warnings);
+        assert_eq!(slugs.unwrap(), ["apple-ads"]);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn include_domain_matching_nothing_fails() {
+        let mut warnings = Vec::new();
+        let slugs = resolve_include_domains(&domains(&["nonexistent.example"]), &categories(), false, &mut warnings);
+        assert!(slugs.is_err());
+    }
+
+    #[test]
+    fn include_domain_unmatched_warns_or_fails_when_strict() {
+        let both = domains(&["iadsdk.apple.com", "nonexistent.example"]);
+        let mut warnings = Vec::new();
+        assert_eq!(resolve_include_domains(&both, &categories(), false, &mut warnings).unwrap(), ["apple-ads"]);
+        assert_eq!(warnings.len(), 1);
+        assert!(resolve_include_domains(&both, &categories(), true, &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Node.js bindings, built as a native addon with the `napi` feature.
+//!
+//! Parameters are plain objects with the kebab-case `GenerateParams` keys, e.g.
+//! `{ mode: "block", include: ["apple-*"] }`. Generated files are returned as
+//! Buffers; `generateRulesAsync` builds them on the libuv thread pool.
+
+// `#[napi]` registers the exports only outside test builds, leaving them unused there
+#![cfg_attr(test, allow(dead_code))]
+
+use crate::bindings::{set_categories, with_categories};
+use crate::{
+    generate_from_categories, get_category_info, suggest_categories, CategorySource, CompiledPatterns,
+    DirectorySource, Error, GenerateParams, OutputFormat,
+};
+use napi::bindgen_prelude::{AsyncTask, Buffer};
+use napi::{Env, Status, Task};
+use napi_derive::napi;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Convert to a JS error, with `InvalidArg` for anything the caller passed wrong
+fn js_error(e: Error) -> napi::Error {
+    let status = match e {
+        Error::EmptySelection
+        | Error::InvalidParams(_)
+        | Error::UnmatchedPatterns(_)
+        | Error::InvalidMode(_)
+        | Error::InvalidSeverity(_)
+        | Error::InvalidLsVersion(_)
+        | Error::InvalidNotesStyle(_)
+        | Error::InvalidSort(_)
+        | Error::InvalidMacosVersion(_)
+        | Error::InvalidFormat(_) => Status::InvalidArg,
+        _ => Status::GenericFailure,
+    };
+    napi::Error::new(status, e.to_string())
+}
+
+fn params_from_js(params: serde_json::Value) -> Result<GenerateParams, Error> {
+    let params: GenerateParams = serde_json::from_value(params).map_err(|e| Error::InvalidParams(e.to_string()))?;
+    params.validate()?;
+    Ok(params)
+}
+
+fn format_from_js(format: Option<String>) -> Result<OutputFormat, Error> {
+    format.map_or(Ok(OutputFormat::default()), |format| format.parse())
+}
+
+fn generated(params: &GenerateParams, format: OutputFormat) -> Result<Vec<u8>, Error> {
+    with_categories(|categories| generate_from_categories(params, categories)?.to_format(format)).map(String::into_bytes)
+}
+
+/// Use the categories in a directory of `.toml` files instead of the embedded ones
+#[napi]
+pub fn load_categories(path: String) -> napi::Result<()> {
+    let categories = DirectorySource::new(path).load().map_err(js_error)?;
+    set_categories(categories);
+    Ok(())
+}
+
+/// List every available category with its metadata
+#[napi(ts_return_type = "Array<Record<string, unknown>>")]
+pub fn list_categories() -> napi::Result<serde_json::Value> {
+    with_categories(|categories| Ok(serde_json::to_value(get_category_info(categories))?)).map_err(js_error)
+}
+
+/// Generate the rules as `format` ("lsrules" by default, or a blocklist format)
+#[napi]
+pub fn generate_rules(
+    #[napi(ts_arg_type = "Record<string, unknown>")] params: serde_json::Value,
+    format: Option<String>,
+) -> napi::Result<Buffer> {
+    let params = params_from_js(params).map_err(js_error)?;
+    let format = format_from_js(format).map_err(js_error)?;
+    Ok(generated(&params, format).map_err(js_error)?.into())
+}
+
+/// Generation run off the main thread by `generateRulesAsync`
+pub struct GenerateTask {
+    params: GenerateParams,
+    format: OutputFormat,
+}
+
+impl Task for GenerateTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        generated(&self.params, self.format).map_err(js_error)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> napi::Result<Self::JsValue> {
+        Ok(output.into())
+    }
+}
+
+/// Like `generateRules`, but generates on the libuv thread pool and resolves with the Buffer
+///
+/// Parameters are checked up front, so malformed ones throw instead of rejecting.
+#[napi]
+pub fn generate_rules_async(
+    #[napi(ts_arg_type = "Record<string, unknown>")] params: serde_json::Value,
+    format: Option<String>,
+) -> napi::Result<AsyncTask<GenerateTask>> {
+    let params = params_from_js(params).map_err(js_error)?;
+    let format = format_from_js(format).map_err(js_error)?;
+    Ok(AsyncTask::new(GenerateTask { params, format }))
+}
+
+/// Check which categories the patterns match, with suggestions for typos
+#[napi(ts_return_type = "{ valid: boolean; matched: string[]; unmatched: string[]; suggestions: Record<string, string[]> }")]
+pub fn validate_patterns(patterns: Vec<String>) -> napi::Result<serde_json::Value> {
+    #[derive(Serialize)]
+    struct PatternValidation<'a> {
+        valid: bool,
+        matched: Vec<&'a str>,
+        unmatched: Vec<String>,
+        suggestions: BTreeMap<String, Vec<&'a str>>,
+    }
+
+    with_categories(|categories| {
+        let compiled = CompiledPatterns::new(&patterns);
+        let matched: Vec<&str> = categories
+            .iter()
+            .map(|(slug, _)| slug.as_str())
+            .filter(|slug| compiled.matches_any(slug))
+            .collect();
+        let unmatched = compiled.unmatched(categories);
+        let suggestions = unmatched
+            .iter()
+            .map(|pattern| (pattern.clone(), suggest_categories(pattern, categories)))
+            .filter(|(_, slugs)| !slugs.is_empty())
+            .collect();
+
+        Ok(serde_json::to_value(PatternValidation {
+            valid: !matched.is_empty() || patterns.is_empty(),
+            matched,
+            unmatched,
+            suggestions,
+        })?)
+    })
+    .map_err(js_error)
+}
+
+/// The library version, e.g. "0.1.0"
+#[napi]
+pub fn version() -> String {
+    crate::get_version().to_string()
+}
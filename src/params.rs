@@ -0,0 +1,162 @@
+//! Fluent construction and validation of [`GenerateParams`].
+
+use crate::{Error, GenerateParams, LsVersion, MacosVersion, Mode, NotesStyle, Result, RuleSort, Severity};
+use std::collections::BTreeMap;
+
+impl GenerateParams {
+    /// Start building parameters from the defaults: block mode, recommended severity
+    pub fn builder() -> GenerateParamsBuilder {
+        GenerateParamsBuilder::default()
+    }
+
+    /// Check that the parameters do not contradict each other
+    ///
+    /// Parameters read from a profile or the web API should be validated before use;
+    /// [`GenerateParamsBuilder::build`] does this already.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| Err(Error::InvalidParams(message.to_string()));
+
+        if self.mode == Mode::Allow && self.include.is_empty() {
+            return invalid("allow mode needs at least one include pattern");
+        }
+        if self.min_severity.is_some_and(|min| min > self.severity) {
+            return invalid("minimum severity cannot be higher than severity");
+        }
+        if self.notes_template.as_deref().is_some_and(|t| t.trim().is_empty()) {
+            return invalid("notes template is empty");
+        }
+        if self.description_template.as_deref().is_some_and(|t| t.trim().is_empty()) {
+            return invalid("description template is empty");
+        }
+        if self.app.as_deref().is_some_and(|app| app.trim().is_empty()) {
+            return invalid("app path is empty");
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`GenerateParams`], validated by [`build`](GenerateParamsBuilder::build)
+#[derive(Debug, Clone, Default)]
+pub struct GenerateParamsBuilder {
+    params: GenerateParams,
+}
+
+impl GenerateParamsBuilder {
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.params.mode = mode;
+        self
+    }
+
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.params.severity = severity;
+        self
+    }
+
+    pub fn min_severity(mut self, severity: Severity) -> Self {
+        self.params.min_severity = Some(severity);
+        self
+    }
+
+    /// Re-rank categories matching `pattern`; later overrides win
+    pub fn severity_override(mut self, pattern: impl Into<String>, severity: Severity) -> Self {
+        self.params.severity_overrides.push((pattern.into(), severity));
+        self
+    }
+
+    /// Add include patterns
+    pub fn include<I: IntoIterator<Item = S>, S: Into<String>>(mut self, patterns: I) -> Self {
+        self.params.include.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add exclude patterns
+    pub fn exclude<I: IntoIterator<Item = S>, S: Into<String>>(mut self, patterns: I) -> Self {
+        self.params.exclude.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn all(mut self, all: bool) -> Self {
+        self.params.all = all;
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.params.name = Some(name.into());
+        self
+    }
+
+    pub fn ls_version(mut self, version: LsVersion) -> Self {
+        self.params.ls_version = version;
+        self
+    }
+
+    pub fn macos_version(mut self, version: MacosVersion) -> Self {
+        self.params.macos_version = Some(version);
+        self
+    }
+
+    pub fn reproducible(mut self, reproducible: bool) -> Self {
+        self.params.reproducible = reproducible;
+        self
+    }
+
+    pub fn notes_style(mut self, style: NotesStyle) -> Self {
+        self.params.notes_style = style;
+        self
+    }
+
+    pub fn notes_template(mut self, template: impl Into<String>) -> Self {
+        self.params.notes_template = Some(template.into());
+        self
+    }
+
+    pub fn provenance_notes(mut self, provenance_notes: bool) -> Self {
+        self.params.provenance_notes = provenance_notes;
+        self
+    }
+
+    pub fn description_template(mut self, template: impl Into<String>) -> Self {
+        self.params.description_template = Some(template.into());
+        self
+    }
+
+    pub fn sort(mut self, sort: RuleSort) -> Self {
+        self.params.sort = Some(sort);
+        self
+    }
+
+    pub fn include_disabled(mut self, include_disabled: bool) -> Self {
+        self.params.include_disabled = include_disabled;
+        self
+    }
+
+    pub fn app(mut self, executable: impl Into<String>) -> Self {
+        self.params.app = Some(executable.into());
+        self
+    }
+
+    pub fn asn_prefixes(mut self, prefixes: BTreeMap<u32, Vec<String>>) -> Self {
+        self.params.asn_prefixes = prefixes;
+        self
+    }
+
+    /// Validate and return the parameters
+    pub fn build(self) -> Result<GenerateParams> {
+        self.params.validate()?;
+        Ok(self.params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_rejects_contradicting_parameters() {
+        assert!(GenerateParams::builder().mode(Mode::Allow).build().is_err());
+        assert!(GenerateParams::builder().mode(Mode::Allow).include(["apple-appstore"]).build().is_ok());
+        let min_above = GenerateParams::builder().severity(Severity::Minimal).min_severity(Severity::Aggressive);
+        assert!(matches!(min_above.build(), Err(Error::InvalidParams(_))));
+        assert!(GenerateParams { notes_template: Some(" ".into()), ..GenerateParams::default() }.validate().is_err());
+    }
+}
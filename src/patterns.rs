@@ -0,0 +1,157 @@
+//! Category patterns compiled once and matched against many slugs.
+
+use crate::Category;
+use glob::Pattern;
+
+/// A single pattern: a glob when it contains wildcards, otherwise a slug or group name
+#[derive(Debug, Clone)]
+enum Matcher {
+    Glob(Pattern),
+    Exact(String),
+    /// A malformed glob such as `apple-[`, which matches nothing
+    Invalid,
+}
+
+impl Matcher {
+    fn new(pattern: &str) -> Self {
+        if pattern.contains(['*', '?', '[']) {
+            Pattern::new(pattern).map_or(Matcher::Invalid, Matcher::Glob)
+        } else {
+            Matcher::Exact(pattern.to_string())
+        }
+    }
+
+    fn matches(&self, slug: &str) -> bool {
+        match self {
+            Matcher::Glob(pattern) => pattern.matches(slug),
+            Matcher::Exact(pattern) => {
+                pattern == slug || slug.strip_prefix(pattern.as_str()).is_some_and(|rest| rest.starts_with('/'))
+            }
+            Matcher::Invalid => false,
+        }
+    }
+}
+
+/// An ordered list of include, exclude or override patterns, each compiled once
+///
+/// Matching a slug against a `&[String]` of globs recompiles every pattern per slug;
+/// [`select_categories`](crate::select_categories) compiles its lists up front instead.
+#[derive(Debug, Clone)]
+pub struct CompiledPatterns {
+    /// Each pattern as written, whether it starts with `!`, and its matcher without the `!`
+    patterns: Vec<(String, bool, Matcher)>,
+}
+
+impl CompiledPatterns {
+    pub fn new(patterns: &[String]) -> Self {
+        CompiledPatterns {
+            patterns: patterns
+                .iter()
+                .map(|pattern| {
+                    let stripped = pattern.strip_prefix('!');
+                    (pattern.clone(), stripped.is_some(), Matcher::new(stripped.unwrap_or(pattern)))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// The first pattern matching the slug; negated entries never match
+    pub fn first_match(&self, slug: &str) -> Option<&str> {
+        self.patterns
+            .iter()
+            .find(|(_, negated, matcher)| !negated && matcher.matches(slug))
+            .map(|(pattern, _, _)| pattern.as_str())
+    }
+
+    /// Index of the last pattern matching the slug, so later entries can override earlier ones
+    pub fn last_position(&self, slug: &str) -> Option<usize> {
+        self.patterns
+            .iter()
+            .rposition(|(_, negated, matcher)| !negated && matcher.matches(slug))
+    }
+
+    /// Check if any pattern matches the slug
+    pub fn matches_any(&self, slug: &str) -> bool {
+        self.first_match(slug).is_some()
+    }
+
+    /// Check if the slug is selected when the patterns are an ordered include list
+    ///
+    /// See [`matches_include_list`](crate::matches_include_list).
+    pub fn matches_include_list(&self, slug: &str) -> bool {
+        self.include_decision(slug).is_ok()
+    }
+
+    /// The last include pattern deciding whether a slug is included: `Ok` with the
+    /// pattern that selected it (`None` if a list starting with a negation kept it),
+    /// or `Err` with the negation that removed it (`None` if nothing matched)
+    pub(crate) fn include_decision(&self, slug: &str) -> Result<Option<&str>, Option<&str>> {
+        let mut decision = match self.patterns.first() {
+            Some((_, true, _)) => Ok(None),
+            _ => Err(None),
+        };
+        for (pattern, negated, matcher) in &self.patterns {
+            if matcher.matches(slug) {
+                decision = if *negated { Err(Some(pattern.as_str())) } else { Ok(Some(pattern.as_str())) };
+            }
+        }
+        decision
+    }
+
+    /// The patterns, as written, that match no category slug (ignoring any `!` prefix)
+    pub fn unmatched(&self, categories: &[(String, Category)]) -> Vec<String> {
+        self.patterns
+            .iter()
+            .filter(|(_, _, matcher)| !categories.iter().any(|(slug, _)| matcher.matches(slug)))
+            .map(|(pattern, _, _)| pattern.clone())
+            .collect()
+    }
+}
+
+/// Check if a slug matches a single pattern, compiling it for this one use
+pub(crate) fn matches_pattern(slug: &str, pattern: &str) -> bool {
+    Matcher::new(pattern).matches(slug)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> CompiledPatterns {
+        CompiledPatterns::new(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn matches_globs_exact_slugs_and_groups() {
+        let compiled = patterns(&["google-*", "apple-telemetry", "vendor"]);
+        assert!(compiled.matches_any("google-ads"));
+        assert!(compiled.matches_any("apple-telemetry"));
+        assert!(compiled.matches_any("vendor/analytics"));
+        assert!(!compiled.matches_any("apple-telemetry-extra"));
+        assert!(!patterns(&["apple-["]).matches_any("apple-["));
+    }
+
+    #[test]
+    fn later_negations_override_earlier_includes() {
+        let compiled = patterns(&["apple-*", "!apple-icloud"]);
+        assert!(compiled.matches_include_list("apple-ads"));
+        assert!(!compiled.matches_include_list("apple-icloud"));
+        assert!(!compiled.matches_include_list("google-ads"));
+        assert_eq!(compiled.first_match("apple-icloud"), Some("apple-*"));
+
+        let reincluded = patterns(&["apple-*", "!apple-icloud", "apple-icloud"]);
+        assert!(reincluded.matches_include_list("apple-icloud"));
+    }
+
+    #[test]
+    fn leading_negation_keeps_everything_else() {
+        let compiled = patterns(&["!google-*"]);
+        assert!(compiled.matches_include_list("apple-ads"));
+        assert!(!compiled.matches_include_list("google-ads"));
+        assert!(!compiled.matches_any("apple-ads"));
+    }
+}
@@ -0,0 +1,169 @@
+//! Summaries of what selections would generate, without building their output.
+
+use crate::{address_rule, expand_rule, select_categories, Action, Category, CategorySelection, GenerateParams, RuleIter};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// What a selection would generate, per category and in total
+///
+/// Counts the same rules [`build_output`](crate::build_output) generates, but without
+/// ordering or serializing them, so it is cheap enough to recompute on every change
+/// of the selection.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectionPreview {
+    /// Every category in catalog order, including skipped ones
+    pub categories: Vec<CategoryPreview>,
+    pub total_rules: usize,
+    /// Distinct domains across all selected categories
+    pub total_domains: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryPreview {
+    pub slug: String,
+    /// `None` when the category is skipped
+    pub action: Option<Action>,
+    pub rule_count: usize,
+    pub domain_count: usize,
+}
+
+impl SelectionPreview {
+    pub fn new(params: &GenerateParams, categories: &[(String, Category)], selection: &CategorySelection) -> Self {
+        let by_slug: HashMap<&str, &Category> = categories.iter().map(|(slug, c)| (slug.as_str(), c)).collect();
+        let mut rules: HashMap<&str, usize> = HashMap::new();
+        let mut domains: HashMap<&str, HashSet<&str>> = HashMap::new();
+
+        for (slug, rule, action) in RuleIter::new(params, categories, selection) {
+            let generated = expand_rule(params, slug, by_slug[slug], rule, action)
+                .into_iter()
+                .filter(|(_, ls_rule)| params.ls_version.adapt(ls_rule.clone()).is_some())
+                .count();
+            *rules.entry(slug).or_default() += generated;
+            domains.entry(slug).or_default().extend(rule.domains.iter().map(|d| d.as_str()));
+        }
+
+        let categories: Vec<CategoryPreview> = categories
+            .iter()
+            .map(|(slug, category)| {
+                let action = selection.action(slug);
+                let address_rules = action
+                    .and_then(|action| address_rule(params, slug, category, action))
+                    .and_then(|rule| params.ls_version.adapt(rule))
+                    .map_or(0, |_| 1);
+                CategoryPreview {
+                    slug: slug.clone(),
+                    action,
+                    rule_count: rules.get(slug.as_str()).copied().unwrap_or(0) + address_rules,
+                    domain_count: domains.get(slug.as_str()).map_or(0, HashSet::len),
+                }
+            })
+            .collect();
+
+        SelectionPreview {
+            total_rules: categories.iter().map(|c| c.rule_count).sum(),
+            total_domains: domains.values().flatten().collect::<HashSet<_>>().len(),
+            categories,
+        }
+    }
+
+    /// The preview of one category, if it is in the catalog
+    pub fn category(&self, slug: &str) -> Option<&CategoryPreview> {
+        self.categories.iter().find(|c| c.slug == slug)
+    }
+}
+
+/// What switching from one set of parameters to another changes
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SelectionDiff {
+    /// Categories only the new parameters select
+    pub added_categories: Vec<String>,
+    /// Categories only the old parameters select
+    pub removed_categories: Vec<String>,
+    /// Categories both select, but with a different action
+    pub changed_categories: Vec<ActionChange>,
+    /// Domains only the new selection lists
+    pub added_domains: Vec<String>,
+    /// Domains only the old selection lists
+    pub removed_domains: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionChange {
+    pub slug: String,
+    pub from: Action,
+    pub to: Action,
+}
+
+impl SelectionDiff {
+    pub fn new(from: &GenerateParams, to: &GenerateParams, categories: &[(String, Category)]) -> Self {
+        let (old, new) = (select_categories(from, categories), select_categories(to, categories));
+        let mut diff = SelectionDiff::default();
+        for (slug, _) in categories {
+            match (old.action(slug), new.action(slug)) {
+                (None, Some(_)) => diff.added_categories.push(slug.clone()),
+                (Some(_), None) => diff.removed_categories.push(slug.clone()),
+                (Some(from), Some(to)) if from != to => diff.changed_categories.push(ActionChange {
+                    slug: slug.clone(),
+                    from,
+                    to,
+                }),
+                _ => {}
+            }
+        }
+
+        let domains = |params, selection| -> BTreeSet<&str> {
+            RuleIter::new(params, categories, selection)
+                .flat_map(|(_, rule, _)| rule.domains.iter().map(|d| d.as_str()))
+                .collect()
+        };
+        let (old_domains, new_domains) = (domains(from, &old), domains(to, &new));
+        diff.added_domains = new_domains.difference(&old_domains).map(|d| d.to_string()).collect();
+        diff.removed_domains = old_domains.difference(&new_domains).map(|d| d.to_string()).collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_categories.is_empty()
+            && self.removed_categories.is_empty()
+            && self.changed_categories.is_empty()
+            && self.added_domains.is_empty()
+            && self.removed_domains.is_empty()
+    }
+}
+
+/// A feature that stops working because the selection blocks rules listing it in `breaks`
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenFeature {
+    pub feature: String,
+    /// Categories whose rules break the feature
+    pub categories: Vec<String>,
+}
+
+impl BrokenFeature {
+    /// Every feature the selection's deny rules break, in alphabetical order
+    ///
+    /// Only counts rules that would be generated enabled, so disabled rules and rules
+    /// for other macOS releases do not show up.
+    pub fn for_selection(
+        params: &GenerateParams,
+        categories: &[(String, Category)],
+        selection: &CategorySelection,
+    ) -> Vec<BrokenFeature> {
+        let mut features: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (slug, rule, action) in RuleIter::new(params, categories, selection) {
+            if action != Action::Deny || rule.disabled {
+                continue;
+            }
+            for feature in &rule.breaks {
+                features.entry(feature).or_default().insert(slug);
+            }
+        }
+        features
+            .into_iter()
+            .map(|(feature, slugs)| BrokenFeature {
+                feature: feature.to_string(),
+                categories: slugs.into_iter().map(str::to_string).collect(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,180 @@
+//! Guided setup: questions about how a Mac is used, turned into generation parameters.
+//!
+//! Backs `apple-ecocide init` and the web UI's guided setup, so both ask the same
+//! questions and pick the same categories.
+
+use crate::{Category, Error, GenerateParams, Mode, Result, Severity};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A yes/no question about a service and the categories it depends on
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Question {
+    /// Key of the answer in [`Answers::uses`]
+    pub id: &'static str,
+    pub prompt: &'static str,
+    /// Categories that stop the service from working when blocked
+    pub slugs: &'static [&'static str],
+    /// Answer assumed when the question is not answered
+    pub default: bool,
+    /// Block the categories, whatever the strictness, when the service is not used
+    #[serde(skip)]
+    pub block_unused: bool,
+}
+
+/// The questions, in the order they are asked
+pub const QUESTIONS: &[Question] = &[
+    Question {
+        id: "icloud",
+        prompt: "Do you use iCloud (Drive, Photos, Keychain, Notes sync)?",
+        slugs: &["apple-icloud"],
+        default: true,
+        block_unused: true,
+    },
+    Question {
+        id: "app-store",
+        prompt: "Do you install or update apps from the App Store?",
+        slugs: &["apple-appstore"],
+        default: true,
+        block_unused: true,
+    },
+    Question {
+        id: "siri",
+        prompt: "Do you use Siri or Apple Intelligence?",
+        slugs: &["apple-siri", "apple-intelligence"],
+        default: true,
+        block_unused: true,
+    },
+    Question {
+        id: "messaging",
+        prompt: "Do you use iMessage or FaceTime?",
+        slugs: &["apple-messaging"],
+        default: true,
+        block_unused: true,
+    },
+    Question {
+        id: "media",
+        prompt: "Do you use Apple Music, TV+, Podcasts or Books?",
+        slugs: &["apple-music", "apple-tv", "apple-podcasts", "apple-books"],
+        default: true,
+        block_unused: true,
+    },
+    Question {
+        id: "development",
+        prompt: "Do you develop software (Xcode, notarization, simulator downloads)?",
+        slugs: &["ocsp", "apple-content"],
+        default: false,
+        block_unused: false,
+    },
+];
+
+/// Answers to [`QUESTIONS`]
+///
+/// Serializes with kebab-case keys, e.g. `{"severity": "aggressive", "uses": {"icloud": false}}`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Answers {
+    /// How strict blocking should be
+    pub severity: Severity,
+    /// Whether each service is used, keyed by [`Question::id`]; unanswered questions take their default
+    pub uses: BTreeMap<String, bool>,
+}
+
+impl Answers {
+    /// Whether the service asked about in `question` is used
+    pub fn uses(&self, question: &Question) -> bool {
+        self.uses.get(question.id).copied().unwrap_or(question.default)
+    }
+
+    /// Generation parameters for these answers, with default output options
+    pub fn to_params(&self, categories: &[(String, Category)]) -> Result<GenerateParams> {
+        self.apply(GenerateParams::default(), categories)
+    }
+
+    /// Replace the selection in `params` with one following these answers
+    ///
+    /// Output options such as `ls_version` or `notes_style` are kept. Every category
+    /// within the severity is blocked, except those of services in use. Services not
+    /// in use are blocked even above the severity, through severity overrides rather
+    /// than a fixed list, so categories added in later releases are still picked up.
+    pub fn apply(&self, params: GenerateParams, categories: &[(String, Category)]) -> Result<GenerateParams> {
+        if let Some(id) = self.uses.keys().find(|id| !QUESTIONS.iter().any(|q| q.id == *id)) {
+            return Err(Error::InvalidParams(format!("unknown question '{}'", id)));
+        }
+
+        let mut params = GenerateParams {
+            mode: Mode::Block,
+            severity: self.severity,
+            min_severity: None,
+            severity_overrides: Vec::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            all: true,
+            ..params
+        };
+        for question in QUESTIONS {
+            let uses = self.uses(question);
+            for (slug, category) in categories.iter().filter(|(slug, _)| question.slugs.contains(&slug.as_str())) {
+                if uses {
+                    params.exclude.push(slug.clone());
+                } else if question.block_unused && category.severity > self.severity {
+                    params.severity_overrides.push((slug.clone(), self.severity));
+                }
+            }
+        }
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{select_categories, LsVersion};
+
+    fn categories() -> Vec<(String, Category)> {
+        [("apple-icloud", "minimal"), ("apple-siri", "aggressive"), ("ocsp", "recommended")]
+            .into_iter()
+            .map(|(slug, severity)| {
+                let toml = format!(
+                    "name = \"Test\"\ndescription = \"\"\nseverity = \"{}\"\nimpact = \"\"\n\n[[rules]]\nnotes = \"Test\"\ndomains = [\"example.com\"]\n",
+                    severity
+                );
+                (slug.to_string(), Category::from_toml_str(&toml).unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn answers_keep_used_services_and_block_unused_ones() {
+        let categories = categories();
+        let answers = Answers {
+            severity: Severity::Recommended,
+            uses: BTreeMap::from([("icloud".to_string(), true), ("siri".to_string(), false)]),
+        };
+        let base = GenerateParams {
+            ls_version: LsVersion::V5,
+            include: vec!["apple-*".to_string()],
+            ..Default::default()
+        };
+        let params = answers.apply(base, &categories).unwrap();
+        assert_eq!(params.ls_version, LsVersion::V5);
+        assert!(params.include.is_empty());
+        assert_eq!(params.exclude, ["apple-icloud"]);
+        assert_eq!(params.severity_overrides, [("apple-siri".to_string(), Severity::Recommended)]);
+
+        // Siri is blocked above the severity; unanswered development keeps OCSP blocked
+        let mut denied: Vec<_> = select_categories(&params, &categories).denied.into_iter().collect();
+        denied.sort();
+        assert_eq!(denied, ["apple-siri", "ocsp"]);
+    }
+
+    #[test]
+    fn unknown_questions_are_rejected() {
+        let answers = Answers {
+            uses: BTreeMap::from([("fax".to_string(), true)]),
+            ..Default::default()
+        };
+        assert!(matches!(answers.to_params(&categories()), Err(Error::InvalidParams(_))));
+    }
+}
@@ -0,0 +1,144 @@
+//! Iteration over the category rules a selection expands to.
+
+use crate::{Category, CategoryRule, CategorySelection, GenerateParams};
+use serde::Serialize;
+
+/// Whether a selected category's rules block or allow connections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Deny,
+    Allow,
+}
+
+impl Action {
+    /// The `action` value in `.lsrules` files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Deny => "deny",
+            Action::Allow => "allow",
+        }
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The rules of the selected categories, as `(slug, rule, action)`
+///
+/// Yields the rules of denied categories, then those of allowed categories, each in
+/// catalog order and skipping rules [`GenerateParams::includes_rule`] leaves out.
+/// Consumers can stream these into their own formats without building an
+/// [`LsRulesOutput`](crate::LsRulesOutput).
+///
+/// The yielded rules borrow only from `categories`, not from the parameters or selection.
+pub struct RuleIter<'a, 'b> {
+    params: &'b GenerateParams,
+    categories: &'a [(String, Category)],
+    selection: &'b CategorySelection,
+    action: Action,
+    category: usize,
+    rule: usize,
+}
+
+impl<'a, 'b> RuleIter<'a, 'b> {
+    pub fn new(
+        params: &'b GenerateParams,
+        categories: &'a [(String, Category)],
+        selection: &'b CategorySelection,
+    ) -> Self {
+        RuleIter {
+            params,
+            categories,
+            selection,
+            action: Action::Deny,
+            category: 0,
+            rule: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for RuleIter<'a, '_> {
+    type Item = (&'a str, &'a CategoryRule, Action);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some((slug, category)) = self.categories.get(self.category) else {
+                if self.action == Action::Allow {
+                    return None;
+                }
+                self.action = Action::Allow;
+                self.category = 0;
+                continue;
+            };
+
+            let selected = match self.action {
+                Action::Deny => &self.selection.denied,
+                Action::Allow => &self.selection.allowed,
+            };
+            let Some(rule) = category.rules.get(self.rule).filter(|_| selected.contains(slug)) else {
+                self.category += 1;
+                self.rule = 0;
+                continue;
+            };
+
+            self.rule += 1;
+            if self.params.includes_rule(rule) {
+                return Some((slug, rule, self.action));
+            }
+            tracing::trace!(category = %slug, "skipped rule that is disabled or needs another macOS version");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{select_categories, Mode};
+
+    fn category(rules: &str) -> Category {
+        let toml = format!(
+            "name = \"Test\"\ndescription = \"\"\nseverity = \"minimal\"\nimpact = \"\"\n{}",
+            rules
+        );
+        Category::from_toml_str(&toml).unwrap()
+    }
+
+    #[test]
+    fn yields_denied_rules_before_allowed_ones() {
+        let categories = vec![
+            ("allowed".to_string(), category("[[rules]]\nnotes = \"allowed\"\ndomains = [\"a.example\"]\n")),
+            (
+                "denied".to_string(),
+                category(
+                    "[[rules]]\nnotes = \"first\"\ndomains = [\"b.example\"]\n\n\
+                     [[rules]]\nnotes = \"opt-in\"\ndomains = [\"c.example\"]\ndisabled = true\n\n\
+                     [[rules]]\nnotes = \"second\"\ndomains = [\"d.example\"]\n",
+                ),
+            ),
+        ];
+        let params = GenerateParams::builder().mode(Mode::Allow).include(["allowed"]).build().unwrap();
+        let selection = select_categories(&params, &categories);
+
+        let rules: Vec<_> = RuleIter::new(&params, &categories, &selection)
+            .map(|(slug, rule, action)| (slug, rule.notes.as_str(), action))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                ("denied", "first", Action::Deny),
+                ("denied", "second", Action::Deny),
+                ("allowed", "allowed", Action::Allow),
+            ]
+        );
+
+        let with_disabled = GenerateParams {
+            include_disabled: true,
+            ..params
+        };
+        assert_eq!(RuleIter::new(&with_disabled, &categories, &selection).count(), 4);
+    }
+}
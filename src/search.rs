@@ -0,0 +1,161 @@
+//! Category search and pattern completion for typeahead inputs.
+
+use crate::{Category, DomainIndex};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// What part of a category a search query matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMatch {
+    Slug,
+    Domain,
+    Name,
+    Description,
+}
+
+/// A category found by [`SearchIndex::search`]
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit<'a> {
+    pub slug: &'a str,
+    pub name: &'a str,
+    /// The best match of the query within the category
+    pub matched: SearchMatch,
+    /// The listed domain that matched, for domain matches
+    pub domain: Option<&'a str>,
+    /// Relevance, higher first; only meaningful relative to other hits of the same query
+    pub score: u32,
+}
+
+/// Search over category slugs, names, descriptions and domains
+///
+/// Build it once per set of categories; each query then only scans lowercased text.
+#[derive(Debug)]
+pub struct SearchIndex<'a> {
+    categories: Vec<Entry<'a>>,
+    domains: DomainIndex,
+}
+
+#[derive(Debug)]
+struct Entry<'a> {
+    slug: &'a str,
+    name: &'a str,
+    lower_name: String,
+    lower_description: String,
+    domains: Vec<&'a str>,
+}
+
+impl<'a> SearchIndex<'a> {
+    pub fn new(categories: &'a [(String, Category)]) -> Self {
+        let entries = categories
+            .iter()
+            .map(|(slug, category)| Entry {
+                slug,
+                name: &category.name,
+                lower_name: category.name.to_lowercase(),
+                lower_description: category.description.to_lowercase(),
+                domains: category.rules.iter().flat_map(|r| &r.domains).map(|d| d.as_str()).collect(),
+            })
+            .collect();
+        SearchIndex {
+            categories: entries,
+            domains: DomainIndex::new(categories),
+        }
+    }
+
+    /// Categories matching `query`, most relevant first
+    ///
+    /// Exact slugs and domains rank first, then slug prefixes, then matches inside
+    /// slugs, names, domains and descriptions. A full domain such as `gateway.icloud.com`
+    /// also finds the categories listing one of its parent domains, as
+    /// [`DomainIndex::lookup`] does.
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'a>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let covering = self.domains.lookup(&query);
+
+        let mut hits: Vec<SearchHit> = self
+            .categories
+            .iter()
+            .filter_map(|entry| {
+                let hit = |matched, score, domain| SearchHit {
+                    slug: entry.slug,
+                    name: entry.name,
+                    matched,
+                    domain,
+                    score,
+                };
+                if entry.slug == query {
+                    return Some(hit(SearchMatch::Slug, 100, None));
+                }
+                if let Some(domain) = entry.domains.iter().find(|d| **d == query) {
+                    return Some(hit(SearchMatch::Domain, 90, Some(*domain)));
+                }
+                if entry.slug.starts_with(&query) {
+                    return Some(hit(SearchMatch::Slug, 80, None));
+                }
+                if covering.contains(&entry.slug) {
+                    let parent = entry.domains.iter().find(|d| query.ends_with(&format!(".{}", d)));
+                    return Some(hit(SearchMatch::Domain, 70, parent.copied()));
+                }
+                if entry.slug.contains(&query) {
+                    return Some(hit(SearchMatch::Slug, 60, None));
+                }
+                if entry.lower_name.starts_with(&query) {
+                    return Some(hit(SearchMatch::Name, 55, None));
+                }
+                if entry.lower_name.contains(&query) {
+                    return Some(hit(SearchMatch::Name, 50, None));
+                }
+                if let Some(domain) = entry.domains.iter().find(|d| d.contains(&query)) {
+                    return Some(hit(SearchMatch::Domain, 40, Some(*domain)));
+                }
+                if entry.lower_description.contains(&query) {
+                    return Some(hit(SearchMatch::Description, 20, None));
+                }
+                None
+            })
+            .collect();
+
+        hits.sort_by(|a, b| b.score.cmp(&a.score).then(a.slug.cmp(b.slug)));
+        hits
+    }
+
+    /// Include patterns starting with `prefix`, for completing `--include` style input
+    ///
+    /// Offers every matching slug plus the wildcards that group several categories:
+    /// vendors (`google-*`), hierarchical groups (`apple/*`) and, for prefixes starting
+    /// with `*`, shared suffixes (`*-telemetry`). Wildcards come first, then slugs.
+    pub fn suggest_patterns(&self, prefix: &str) -> Vec<String> {
+        let prefix = prefix.trim().to_lowercase();
+        let mut wildcards = BTreeSet::new();
+        let mut seen = BTreeSet::new();
+
+        for entry in &self.categories {
+            let slug = entry.slug;
+            let vendor = slug.split(['/', '-']).next().unwrap_or(slug);
+            let mut groups = Vec::new();
+            if let Some(separator) = slug[vendor.len()..].chars().next() {
+                groups.push(format!("{}{}*", vendor, separator));
+            }
+            if let Some((_, suffix)) = slug.rsplit_once('-') {
+                groups.push(format!("*-{}", suffix));
+            }
+            for group in groups {
+                // A wildcard is only worth offering once a second category shares it
+                if !seen.insert(group.clone()) && group.starts_with(&prefix) {
+                    wildcards.insert(group);
+                }
+            }
+        }
+
+        let slugs = self
+            .categories
+            .iter()
+            .map(|entry| entry.slug.to_string())
+            .filter(|slug| slug.starts_with(&prefix));
+        wildcards.into_iter().chain(slugs).collect()
+    }
+}
@@ -0,0 +1,160 @@
+//! UniFFI bindings for Swift (and other UniFFI languages), built with the `uniffi` feature.
+//!
+//! `cargo run --features uniffi-cli --bin uniffi-bindgen -- generate --library <dylib> --language swift --out-dir <dir>`
+//! writes the Swift module for these functions.
+
+use crate::bindings::{set_categories, with_categories};
+use crate::{
+    generate_from_categories, get_category_info, suggest_categories, CategorySource, CompiledPatterns,
+    DirectorySource, Error, GenerateParams, Mode, Severity,
+};
+use std::collections::HashMap;
+
+/// Errors thrown to Swift, named so they do not shadow Swift's `Error` protocol
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum EcocideError {
+    /// Malformed parameters, or patterns that match no category
+    #[error("{message}")]
+    InvalidParams { message: String },
+    /// The parameters selected no category
+    #[error("{message}")]
+    NothingSelected { message: String },
+    /// Categories could not be loaded or parsed, or output could not be written
+    #[error("{message}")]
+    Failed { message: String },
+}
+
+impl From<Error> for EcocideError {
+    fn from(e: Error) -> Self {
+        let message = e.to_string();
+        match e {
+            Error::EmptySelection => EcocideError::NothingSelected { message },
+            Error::InvalidParams(_)
+            | Error::UnmatchedPatterns(_)
+            | Error::InvalidMode(_)
+            | Error::InvalidSeverity(_)
+            | Error::InvalidLsVersion(_)
+            | Error::InvalidNotesStyle(_)
+            | Error::InvalidSort(_)
+            | Error::InvalidMacosVersion(_)
+            | Error::InvalidFormat(_) => EcocideError::InvalidParams { message },
+            _ => EcocideError::Failed { message },
+        }
+    }
+}
+
+/// Category metadata for pickers and settings screens
+#[derive(uniffi::Record)]
+pub struct CategorySummary {
+    pub slug: String,
+    pub name: String,
+    pub description: String,
+    pub severity: Severity,
+    pub impact: String,
+    pub rule_count: u64,
+}
+
+/// The commonly used generation parameters; the rest keep their defaults
+#[derive(uniffi::Record)]
+pub struct RuleParams {
+    pub mode: Mode,
+    pub severity: Severity,
+    /// Category slugs or patterns such as "apple-*"
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Select every category within the severity
+    pub all: bool,
+    /// Rule group name shown in Little Snitch
+    pub name: Option<String>,
+}
+
+/// Which categories a list of patterns selects
+#[derive(uniffi::Record)]
+pub struct PatternValidation {
+    pub valid: bool,
+    pub matched: Vec<String>,
+    /// Patterns that matched no category
+    pub unmatched: Vec<String>,
+    /// Closest category slugs for each unmatched pattern without wildcards
+    pub suggestions: HashMap<String, Vec<String>>,
+}
+
+/// Use the categories in a directory of `.toml` files instead of the embedded ones
+#[uniffi::export]
+pub fn load_categories(path: String) -> Result<(), EcocideError> {
+    let categories = DirectorySource::new(path).load()?;
+    set_categories(categories);
+    Ok(())
+}
+
+/// List every available category
+#[uniffi::export]
+pub fn list_categories() -> Result<Vec<CategorySummary>, EcocideError> {
+    let summaries = with_categories(|categories| {
+        Ok(get_category_info(categories)
+            .into_iter()
+            .map(|info| CategorySummary {
+                slug: info.slug,
+                name: info.name,
+                description: info.description,
+                severity: info.severity,
+                impact: info.impact,
+                rule_count: info.rule_count as u64,
+            })
+            .collect())
+    })?;
+    Ok(summaries)
+}
+
+/// Generate the `.lsrules` file contents
+#[uniffi::export]
+pub fn generate_rules(params: RuleParams) -> Result<String, EcocideError> {
+    let params = GenerateParams {
+        mode: params.mode,
+        severity: params.severity,
+        include: params.include,
+        exclude: params.exclude,
+        all: params.all,
+        name: params.name,
+        ..GenerateParams::default()
+    };
+    params.validate()?;
+    Ok(with_categories(|categories| generate_from_categories(&params, categories)?.to_json_pretty())?)
+}
+
+/// Check which categories the patterns match, with suggestions for typos
+#[uniffi::export]
+pub fn validate_patterns(patterns: Vec<String>) -> Result<PatternValidation, EcocideError> {
+    let validation = with_categories(|categories| {
+        let compiled = CompiledPatterns::new(&patterns);
+        let matched: Vec<String> = categories
+            .iter()
+            .map(|(slug, _)| slug)
+            .filter(|slug| compiled.matches_any(slug))
+            .cloned()
+            .collect();
+        let unmatched = compiled.unmatched(categories);
+        let suggestions = unmatched
+            .iter()
+            .map(|pattern| {
+                let slugs = suggest_categories(pattern, categories).into_iter().map(String::from).collect();
+                (pattern.clone(), slugs)
+            })
+            .filter(|(_, slugs): &(String, Vec<String>)| !slugs.is_empty())
+            .collect();
+
+        Ok(PatternValidation {
+            valid: !matched.is_empty() || patterns.is_empty(),
+            matched,
+            unmatched,
+            suggestions,
+        })
+    })?;
+    Ok(validation)
+}
+
+/// The library version, e.g. "0.1.0"
+#[uniffi::export]
+pub fn version() -> String {
+    crate::get_version().to_string()
+}
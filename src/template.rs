@@ -0,0 +1,28 @@
+//! Minimal `{placeholder}` substitution for user-supplied text templates.
+
+/// Replace each `{key}` in `template` with its value; unknown placeholders are left as-is
+pub(crate) fn render<V: AsRef<str>>(template: &str, values: &[(&str, V)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after
+            .find('}')
+            .and_then(|end| values.iter().find(|(key, _)| *key == &after[..end]).map(|v| (end, v.1.as_ref())));
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
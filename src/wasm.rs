@@ -1,78 +1,535 @@
 //! WebAssembly bindings for the Little Snitch rules generator.
 
 use crate::{
-    build_output, get_category_info, load_embedded_categories, select_categories, GenerateParams,
-    Mode, Severity,
+    generate_from_categories, get_category_info, localize_categories, parse_category, select_categories, slug_for_path, suggest_categories, Action, Answers,
+    Category, CategorySource, CompiledPatterns, Error, InMemorySource, GenerateParams, LsRulesOutput,
+    OutputFormat, SearchIndex, SelectionDiff, SelectionPreview, SelectionReason, Severity, QUESTIONS,
 };
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use wasm_bindgen::prelude::*;
 
-/// Generate Little Snitch rules JSON from parameters.
+thread_local! {
+    static LOCALE: RefCell<Option<String>> = const { RefCell::new(None) };
+    static CATEGORIES: RefCell<Option<Vec<(String, Category)>>> = const { RefCell::new(None) };
+    static CUSTOM: RefCell<BTreeMap<String, Category>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Replace the embedded categories with the given category files.
+///
+/// Required before any other call when the library is built with the `no-embed` feature.
 ///
 /// # Arguments
-/// * `mode` - "block" or "allow"
-/// * `severity` - "minimal", "recommended", or "aggressive"
-/// * `include` - Comma-separated list of category patterns to include
-/// * `exclude` - Comma-separated list of category patterns to exclude
-/// * `name` - Optional custom name for the ruleset
+/// * `files` - JSON object mapping file names (e.g. "apple-telemetry.toml") to TOML content
+#[wasm_bindgen]
+pub fn set_categories(files: &str) -> Result<(), JsError> {
+    let files: BTreeMap<String, String> = serde_json::from_str(files).map_err(Error::from)?;
+    let categories = InMemorySource::new(files).load()?;
+    CATEGORIES.with(|loaded| *loaded.borrow_mut() = Some(categories));
+    Ok(())
+}
+
+/// Add a category of your own alongside the embedded (or `set_categories`) ones.
 ///
-/// # Returns
-/// JSON string of the generated rules, or an error message.
+/// A custom category with the slug of an existing one replaces it, like a file of the
+/// same name in the CLI's `--categories` directory.
+///
+/// # Arguments
+/// * `slug` - Category slug, e.g. "my-trackers"; a trailing ".toml" is ignored
+/// * `toml` - Category file content
 #[wasm_bindgen]
-pub fn generate_rules(mode: &str, severity: &str, include: &str, exclude: &str, name: &str) -> Result<String, JsError> {
-    let mode = Mode::from_str(mode).unwrap_or_default();
-    let severity = Severity::from_str(severity).unwrap_or_default();
-
-    let include: Vec<String> = if include.is_empty() {
-        Vec::new()
-    } else {
-        include.split(',').map(|s| s.trim().to_string()).collect()
-    };
+pub fn add_custom_category(slug: &str, toml: &str) -> Result<(), JsError> {
+    let (slug, category) = parse_custom(slug, toml)?;
+    CUSTOM.with(|custom| custom.borrow_mut().insert(slug, category));
+    Ok(())
+}
 
-    let exclude: Vec<String> = if exclude.is_empty() {
-        Vec::new()
-    } else {
-        exclude.split(',').map(|s| s.trim().to_string()).collect()
-    };
+/// Remove a category added with `add_custom_category`; returns whether it existed.
+#[wasm_bindgen]
+pub fn remove_custom_category(slug: &str) -> bool {
+    CUSTOM.with(|custom| custom.borrow_mut().remove(&slug_for_path(slug)).is_some())
+}
 
-    let name = if name.is_empty() {
-        None
-    } else {
-        Some(name.to_string())
-    };
+/// Remove every category added with `add_custom_category`.
+#[wasm_bindgen]
+pub fn clear_custom_categories() {
+    CUSTOM.with(|custom| custom.borrow_mut().clear());
+}
+
+fn parse_custom(slug: &str, toml: &str) -> Result<(String, Category), Error> {
+    let slug = slug_for_path(slug.trim());
+    if slug.is_empty() {
+        return Err(Error::InvalidParams("custom categories need a slug".into()));
+    }
+    let category = parse_category(&format!("{}.toml", slug), toml)?;
+    Ok((slug, category))
+}
+
+/// Add `custom` to `categories`, replacing categories with the same slug
+fn merge_categories(categories: &mut Vec<(String, Category)>, custom: impl IntoIterator<Item = (String, Category)>) {
+    for (slug, category) in custom {
+        match categories.iter_mut().find(|(s, _)| *s == slug) {
+            Some((_, existing)) => *existing = category,
+            None => categories.push((slug, category)),
+        }
+    }
+    categories.sort_by(|a, b| a.0.cmp(&b.0));
+}
 
-    let params = GenerateParams {
-        mode,
-        severity,
-        include,
-        exclude,
-        all: true, // In WASM mode, always include all categories matching criteria
-        name,
+/// Set the language of category names, descriptions and impact notes.
+///
+/// # Arguments
+/// * `lang` - Language tag such as "de" or "fr-CA"; an empty string restores English
+#[wasm_bindgen]
+pub fn set_locale(lang: &str) {
+    let lang = lang.trim();
+    LOCALE.with(|locale| *locale.borrow_mut() = (!lang.is_empty()).then(|| lang.to_string()));
+}
+
+/// Categories from `set_categories`, or the embedded ones, with the custom ones added,
+/// in the language chosen with `set_locale`
+fn categories() -> Result<Vec<(String, Category)>, Error> {
+    let mut categories = match CATEGORIES.with(|loaded| loaded.borrow().clone()) {
+        Some(categories) => categories,
+        // Custom categories alone are enough for a `no-embed` build
+        None => match embedded_categories() {
+            Err(Error::NoCategories) if CUSTOM.with(|custom| !custom.borrow().is_empty()) => Vec::new(),
+            loaded => loaded?,
+        },
     };
+    CUSTOM.with(|custom| merge_categories(&mut categories, custom.borrow().clone()));
+    LOCALE.with(|locale| {
+        if let Some(lang) = locale.borrow().as_deref() {
+            localize_categories(&mut categories, lang);
+        }
+    });
+    Ok(categories)
+}
 
-    let categories = load_embedded_categories().map_err(|e| JsError::new(&e))?;
-    let selection = select_categories(&params, &categories);
+#[cfg(not(feature = "no-embed"))]
+fn embedded_categories() -> Result<Vec<(String, Category)>, Error> {
+    crate::EmbeddedSource.load()
+}
+
+#[cfg(feature = "no-embed")]
+fn embedded_categories() -> Result<Vec<(String, Category)>, Error> {
+    Err(Error::NoCategories)
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &str = r#"
+export type Mode = "block" | "allow";
+export type Severity = "minimal" | "recommended" | "aggressive";
+export type OutputFormat = "lsrules" | "hosts" | "adguard" | "dnsmasq" | "domains";
+
+/** Generation parameters; omitted keys take their defaults */
+export interface GenerateParams {
+    mode?: Mode;
+    severity?: Severity;
+    "min-severity"?: Severity;
+    "severity-overrides"?: [string, Severity][];
+    include?: string[];
+    exclude?: string[];
+    all?: boolean;
+    name?: string;
+    "ls-version"?: "4" | "5" | "6";
+    "macos-version"?: string;
+    reproducible?: boolean;
+    "notes-style"?: "full" | "minimal" | "none";
+    "notes-template"?: string;
+    "description-template"?: string;
+    sort?: "action" | "category" | "domain" | "priority";
+    "include-disabled"?: boolean;
+    /** Executable the domain rules apply to instead of any process */
+    app?: string;
+    "provenance-notes"?: boolean;
+}
+
+export interface LsRule {
+    action: "deny" | "allow";
+    priority?: "high";
+    process: string;
+    via?: string;
+    "remote-domains"?: string[];
+    "remote-addresses"?: string[];
+    remote?: string;
+    protocol?: string;
+    disabled?: boolean;
+    notes?: string;
+}
+
+export interface LsRules {
+    name: string;
+    description: string;
+    rules: LsRule[];
+}
+
+export interface GeneratedRules {
+    /** The rules as an object */
+    output: LsRules;
+    /** The `.lsrules` file contents */
+    json: string;
+}
+
+export interface SelectionExplanation {
+    slug: string;
+    action: "deny" | "allow" | null;
+    /** Readable reason */
+    message: string;
+    /** Structured reason, tagged by its `reason` key */
+    reason: { reason: string; pattern?: string | null; severity?: Severity };
+}
+
+export interface CategoryPreview {
+    slug: string;
+    action: "deny" | "allow" | null;
+    rule_count: number;
+    domain_count: number;
+}
+
+export interface SelectionPreview {
+    /** Every category, including skipped ones */
+    categories: CategoryPreview[];
+    total_rules: number;
+    /** Distinct domains across all selected categories */
+    total_domains: number;
+}
+
+export interface SelectionDiff {
+    added_categories: string[];
+    removed_categories: string[];
+    /** Categories selected by both, with a different action */
+    changed_categories: { slug: string; from: "deny" | "allow"; to: "deny" | "allow" }[];
+    added_domains: string[];
+    removed_domains: string[];
+}
+
+/** A guided setup question; see `questions` */
+export interface Question {
+    /** Key of the answer in `Answers.uses` */
+    id: string;
+    prompt: string;
+    /** Categories that stop the service from working when blocked */
+    slugs: string[];
+    /** Answer assumed when the question is not answered */
+    default: boolean;
+}
+
+/** Answers to the guided setup questions */
+export interface Answers {
+    severity?: Severity;
+    /** Whether each service is used, keyed by question id */
+    uses?: Record<string, boolean>;
+}
+
+export interface SearchHit {
+    slug: string;
+    name: string;
+    matched: "slug" | "domain" | "name" | "description";
+    /** The listed domain that matched, for domain matches */
+    domain: string | null;
+    score: number;
+}
+
+export interface CategoryInfo {
+    slug: string;
+    name: string;
+    description: string;
+    severity: Severity;
+    breakage?: "none" | "low" | "medium" | "high";
+    /** 0-100, higher means more worth blocking; see `Category::impact_score` */
+    score: number;
+    impact: string;
+    rule_count: number;
+    sources?: string[];
+    "last-verified"?: string;
+}
+
+export interface CategoryRuleDetails {
+    notes: string;
+    domains: string[];
+    process: string | null;
+}
 
-    if selection.denied.is_empty() && selection.allowed.is_empty() {
-        return Err(JsError::new(
-            "No categories selected. Check your include/exclude patterns.",
-        ));
+export interface CategoryDetails {
+    slug: string;
+    name: string;
+    description: string;
+    severity: Severity;
+    impact: string;
+    domains: string[];
+    processes: string[];
+    rules: CategoryRuleDetails[];
+}
+
+export interface PatternValidation {
+    valid: boolean;
+    matched: string[];
+    /** Patterns, as written, that match no category */
+    unmatched: string[];
+    /** Closest category slugs for each unmatched pattern without wildcards */
+    suggestions: Record<string, string[]>;
+}
+"#;
+
+/// Generate Little Snitch rules from parameters.
+///
+/// # Arguments
+/// * `params` - Object with the `GenerateParams` keys, e.g.
+///   `{ mode: "block", severity: "aggressive", include: ["apple-*"], exclude: [], name: "My rules" }`;
+///   missing keys take their defaults and multi-word keys are kebab-case (`"ls-version"`)
+///
+/// # Returns
+/// Object with `output` (the rules as an object) and `json` (the `.lsrules` file contents),
+/// or an error for malformed or contradictory parameters.
+#[wasm_bindgen(unchecked_return_type = "GeneratedRules")]
+pub fn generate_rules(#[wasm_bindgen(unchecked_param_type = "GenerateParams")] params: JsValue) -> Result<JsValue, JsError> {
+    let params = params_from_js(params)?;
+    let categories = categories()?;
+    generated(&params, &categories)
+}
+
+/// Generate Little Snitch rules with extra categories for this call only.
+///
+/// # Arguments
+/// * `params` - Same as `generate_rules`
+/// * `custom` - Object mapping slugs to category TOML, e.g. `{ "my-trackers": "name = ..." }`;
+///   they are merged like `add_custom_category` but not kept for later calls
+///
+/// # Returns
+/// Same as `generate_rules`.
+#[wasm_bindgen(unchecked_return_type = "GeneratedRules")]
+pub fn generate_with_custom_categories(
+    #[wasm_bindgen(unchecked_param_type = "GenerateParams")] params: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "Record<string, string>")] custom: JsValue,
+) -> Result<JsValue, JsError> {
+    let params = params_from_js(params)?;
+    let custom: BTreeMap<String, String> = serde_wasm_bindgen::from_value(custom)?;
+    let custom = custom
+        .iter()
+        .map(|(slug, toml)| parse_custom(slug, toml))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut categories = categories()?;
+    merge_categories(&mut categories, custom);
+    generated(&params, &categories)
+}
+
+/// The `GeneratedRules` object for `params`
+fn generated(params: &GenerateParams, categories: &[(String, Category)]) -> Result<JsValue, JsError> {
+    #[derive(Serialize)]
+    struct Generated<'a> {
+        output: &'a LsRulesOutput<'a>,
+        json: String,
     }
 
-    let output = build_output(&params, &categories, &selection);
-    serde_json::to_string_pretty(&output)
-        .map_err(|e| JsError::new(&format!("JSON serialization error: {}", e)))
+    let output = generate_from_categories(params, categories)?;
+    to_js(&Generated {
+        json: output.to_json_pretty()?,
+        output: &output,
+    })
+}
+
+/// Generate the rules in another file format.
+///
+/// # Arguments
+/// * `format` - "lsrules", or a DNS blocklist format: "hosts", "adguard", "dnsmasq" or "domains"
+/// * `params` - Same as `generate_rules`
+///
+/// # Returns
+/// The file contents. Blocklists list the domains of the deny rules and leave out
+/// process-wide and allow rules.
+#[wasm_bindgen]
+pub fn generate(
+    #[wasm_bindgen(unchecked_param_type = "OutputFormat")] format: &str,
+    #[wasm_bindgen(unchecked_param_type = "GenerateParams")] params: JsValue,
+) -> Result<String, JsError> {
+    let format: OutputFormat = format.parse()?;
+    let params = params_from_js(params)?;
+    let categories = categories()?;
+    Ok(generate_from_categories(&params, &categories)?.to_format(format)?)
+}
+
+/// A generated file, ready to be wrapped in a `Blob` and downloaded
+#[wasm_bindgen(getter_with_clone)]
+pub struct Download {
+    /// File contents as a `Uint8Array`
+    pub bytes: Vec<u8>,
+    /// Suggested file name from the rule group name, e.g. "apple-ecocide.lsrules"
+    pub filename: String,
+    /// MIME type for the `Blob`
+    pub mime_type: String,
+}
+
+/// Generate a file to download, in any of the `generate` formats.
+///
+/// # Arguments
+/// * `format` - Same as `generate`
+/// * `params` - Same as `generate_rules`
+///
+/// # Returns
+/// Object with `bytes` (a `Uint8Array`), `filename` and `mime_type`, e.g. for
+/// `new Blob([file.bytes], { type: file.mime_type })`.
+#[wasm_bindgen]
+pub fn download(
+    #[wasm_bindgen(unchecked_param_type = "OutputFormat")] format: &str,
+    #[wasm_bindgen(unchecked_param_type = "GenerateParams")] params: JsValue,
+) -> Result<Download, JsError> {
+    let format: OutputFormat = format.parse()?;
+    let params = params_from_js(params)?;
+    let categories = categories()?;
+    let output = generate_from_categories(&params, &categories)?;
+    Ok(Download {
+        bytes: output.to_format(format)?.into_bytes(),
+        filename: format.file_name(&output.name),
+        mime_type: format.mime_type().to_string(),
+    })
+}
+
+/// Explain why each category is denied, allowed or skipped for the given parameters.
+///
+/// Takes the same parameters object as `generate_rules`.
+///
+/// # Returns
+/// Array of objects with `slug`, `action` ("deny", "allow", or null when skipped),
+/// `reason` (the structured reason, tagged by its `reason` key), and `message` (readable text).
+#[wasm_bindgen(unchecked_return_type = "SelectionExplanation[]")]
+pub fn explain_selection(#[wasm_bindgen(unchecked_param_type = "GenerateParams")] params: JsValue) -> Result<JsValue, JsError> {
+    #[derive(Serialize)]
+    struct Explanation<'a> {
+        slug: &'a str,
+        action: Option<Action>,
+        message: String,
+        reason: SelectionReason,
+    }
+
+    let params = params_from_js(params)?;
+    let categories = categories()?;
+    let selection = select_categories(&params, &categories);
+
+    let explanation: Vec<Explanation> = categories
+        .iter()
+        .map(|(slug, _)| {
+            let reason = selection.explain(slug);
+            Explanation {
+                slug,
+                action: selection.action(slug),
+                message: reason.to_string(),
+                reason,
+            }
+        })
+        .collect();
+    to_js(&explanation)
+}
+
+/// Count the rules and domains the given parameters would generate, without generating them.
+///
+/// Takes the same parameters object as `generate_rules`; cheap enough to call on every change.
+///
+/// # Returns
+/// Object with `categories` (per category `slug`, `action`, `rule_count` and `domain_count`),
+/// `total_rules`, and `total_domains` (distinct domains across all selected categories).
+#[wasm_bindgen(unchecked_return_type = "SelectionPreview")]
+pub fn preview_selection(#[wasm_bindgen(unchecked_param_type = "GenerateParams")] params: JsValue) -> Result<JsValue, JsError> {
+    let params = params_from_js(params)?;
+    let categories = categories()?;
+    let selection = select_categories(&params, &categories);
+    to_js(&SelectionPreview::new(&params, &categories, &selection))
+}
+
+/// Compare what two parameter sets select, e.g. recommended against aggressive severity.
+///
+/// # Arguments
+/// * `params_a` - The current parameters, same as for `generate_rules`
+/// * `params_b` - The parameters to switch to
+///
+/// # Returns
+/// Object with `added_categories`, `removed_categories`, `changed_categories` (selected by both
+/// but denied in one and allowed in the other), `added_domains` and `removed_domains`.
+#[wasm_bindgen(unchecked_return_type = "SelectionDiff")]
+pub fn diff_rules(
+    #[wasm_bindgen(unchecked_param_type = "GenerateParams")] params_a: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "GenerateParams")] params_b: JsValue,
+) -> Result<JsValue, JsError> {
+    let (params_a, params_b) = (params_from_js(params_a)?, params_from_js(params_b)?);
+    let categories = categories()?;
+    to_js(&SelectionDiff::new(&params_a, &params_b, &categories))
+}
+
+/// The guided setup questions, in the order to ask them.
+///
+/// # Returns
+/// Array of questions with `id`, `prompt`, `slugs` (the categories depending on the
+/// service) and `default`. The CLI's `init` asks the same questions.
+#[wasm_bindgen(unchecked_return_type = "Question[]")]
+pub fn questions() -> Result<JsValue, JsError> {
+    to_js(&QUESTIONS)
+}
+
+/// Turn guided setup answers into generation parameters.
+///
+/// # Arguments
+/// * `answers` - Object with `severity` and `uses`, mapping question ids to whether the
+///   service is used; unanswered questions take their default
+///
+/// # Returns
+/// Parameters for `generate_rules` and the other functions: every category within the
+/// severity is blocked, except those of services in use, and services not in use are
+/// blocked even above the severity.
+#[wasm_bindgen(unchecked_return_type = "GenerateParams")]
+pub fn params_from_answers(#[wasm_bindgen(unchecked_param_type = "Answers")] answers: JsValue) -> Result<JsValue, JsError> {
+    let answers: Answers = serde_wasm_bindgen::from_value(answers)?;
+    let categories = categories()?;
+    to_js(&answers.to_params(&categories)?)
+}
+
+/// Read and validate a parameters object passed from JavaScript
+fn params_from_js(params: JsValue) -> Result<GenerateParams, JsError> {
+    let params: GenerateParams = serde_wasm_bindgen::from_value(params)?;
+    params.validate()?;
+    Ok(params)
+}
+
+/// Convert a value to a plain JavaScript object, with maps as objects rather than `Map`s
+fn to_js(value: &impl Serialize) -> Result<JsValue, JsError> {
+    Ok(value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
 }
 
 /// List all available categories with their metadata.
 ///
 /// # Returns
-/// JSON array of category objects with slug, name, description, severity, impact, and rule_count.
+/// Array of category objects with slug, name, description, severity, impact, and rule_count.
+#[wasm_bindgen(unchecked_return_type = "CategoryInfo[]")]
+pub fn list_categories() -> Result<JsValue, JsError> {
+    let categories = categories()?;
+    to_js(&get_category_info(&categories))
+}
+
+/// Search categories by slug, name, description or domain, for typeahead.
+///
+/// # Arguments
+/// * `query` - Text to find, e.g. "telemetry" or a domain such as "gateway.icloud.com"
+///
+/// # Returns
+/// Array of hits with `slug`, `name`, `matched` (which field matched), `domain`
+/// (the matching listed domain, if any) and `score`, most relevant first.
+#[wasm_bindgen(unchecked_return_type = "SearchHit[]")]
+pub fn search_categories(query: &str) -> Result<JsValue, JsError> {
+    let categories = categories()?;
+    to_js(&SearchIndex::new(&categories).search(query))
+}
+
+/// Complete a category pattern, for typeahead in include and exclude inputs.
+///
+/// # Arguments
+/// * `prefix` - What has been typed so far, e.g. "goo" or "*-"
+///
+/// # Returns
+/// Patterns starting with `prefix`: shared wildcards such as "google-*" first, then slugs.
 #[wasm_bindgen]
-pub fn list_categories() -> Result<String, JsError> {
-    let categories = load_embedded_categories().map_err(|e| JsError::new(&e))?;
-    let info = get_category_info(&categories);
-    serde_json::to_string(&info)
-        .map_err(|e| JsError::new(&format!("JSON serialization error: {}", e)))
+pub fn suggest_patterns(prefix: &str) -> Result<Vec<String>, JsError> {
+    let categories = categories()?;
+    Ok(SearchIndex::new(&categories).suggest_patterns(prefix))
 }
 
 /// Get the version of the library.
@@ -84,39 +541,44 @@ pub fn get_version() -> String {
 /// Validate that category patterns match at least one category.
 ///
 /// # Arguments
-/// * `patterns` - Comma-separated list of patterns to validate
+/// * `patterns` - Patterns to validate
 ///
 /// # Returns
-/// JSON object with `valid` (bool) and `matched` (array of matched category slugs)
-#[wasm_bindgen]
-pub fn validate_patterns(patterns: &str) -> Result<String, JsError> {
-    use crate::matches_pattern;
-
-    let patterns: Vec<String> = if patterns.is_empty() {
-        Vec::new()
-    } else {
-        patterns.split(',').map(|s| s.trim().to_string()).collect()
-    };
+/// Object with `valid` (bool), `matched` (array of matched category slugs),
+/// `unmatched` (array of patterns that matched no category), and `suggestions`
+/// (object mapping each unmatched pattern to the closest category slugs, for typos)
+#[wasm_bindgen(unchecked_return_type = "PatternValidation")]
+pub fn validate_patterns(patterns: Vec<String>) -> Result<JsValue, JsError> {
+    #[derive(Serialize)]
+    struct PatternValidation<'a> {
+        valid: bool,
+        matched: Vec<&'a str>,
+        unmatched: Vec<String>,
+        suggestions: BTreeMap<String, Vec<&'a str>>,
+    }
 
-    let categories = load_embedded_categories().map_err(|e| JsError::new(&e))?;
+    let categories = categories()?;
+    let compiled = CompiledPatterns::new(&patterns);
 
-    let mut matched: Vec<String> = Vec::new();
-    for (slug, _) in &categories {
-        for pattern in &patterns {
-            if matches_pattern(slug, pattern) {
-                matched.push(slug.clone());
-                break;
-            }
-        }
-    }
+    let matched: Vec<&str> = categories
+        .iter()
+        .map(|(slug, _)| slug.as_str())
+        .filter(|slug| compiled.matches_any(slug))
+        .collect();
 
-    let result = serde_json::json!({
-        "valid": !matched.is_empty() || patterns.is_empty(),
-        "matched": matched
-    });
+    let unmatched = compiled.unmatched(&categories);
+    let suggestions = unmatched
+        .iter()
+        .map(|pattern| (pattern.clone(), suggest_categories(pattern, &categories)))
+        .filter(|(_, slugs)| !slugs.is_empty())
+        .collect();
 
-    serde_json::to_string(&result)
-        .map_err(|e| JsError::new(&format!("JSON serialization error: {}", e)))
+    to_js(&PatternValidation {
+        valid: !matched.is_empty() || patterns.is_empty(),
+        matched,
+        unmatched,
+        suggestions,
+    })
 }
 
 /// Get detailed information about a specific category including all rules.
@@ -125,46 +587,54 @@ pub fn validate_patterns(patterns: &str) -> Result<String, JsError> {
 /// * `slug` - The category slug (e.g., "apple-telemetry")
 ///
 /// # Returns
-/// JSON object with full category details including domains and processes
-#[wasm_bindgen]
-pub fn get_category_details(slug: &str) -> Result<String, JsError> {
-    let categories = load_embedded_categories().map_err(|e| JsError::new(&e))?;
+/// Object with full category details including domains and processes
+#[wasm_bindgen(unchecked_return_type = "CategoryDetails")]
+pub fn get_category_details(slug: &str) -> Result<JsValue, JsError> {
+    #[derive(Serialize)]
+    struct RuleDetails<'a> {
+        notes: &'a str,
+        domains: Vec<&'a str>,
+        process: Option<&'a str>,
+    }
+
+    #[derive(Serialize)]
+    struct CategoryDetails<'a> {
+        slug: &'a str,
+        name: &'a str,
+        description: &'a str,
+        severity: Severity,
+        impact: &'a str,
+        domains: Vec<&'a str>,
+        processes: Vec<&'a str>,
+        rules: Vec<RuleDetails<'a>>,
+    }
+
+    let categories = categories()?;
 
     let category = categories
         .iter()
         .find(|(s, _)| s == slug)
         .map(|(_, c)| c)
-        .ok_or_else(|| JsError::new(&format!("Category not found: {}", slug)))?;
-
-    let mut domains: Vec<&str> = Vec::new();
-    let mut processes: Vec<&str> = Vec::new();
-
-    for rule in &category.rules {
-        for domain in &rule.domains {
-            domains.push(domain);
-        }
-        if let Some(process) = &rule.deny_process {
-            processes.push(process);
-        }
-    }
+        .ok_or_else(|| Error::UnknownCategory(slug.to_string()))?;
 
-    let result = serde_json::json!({
-        "slug": slug,
-        "name": category.name,
-        "description": category.description,
-        "severity": category.severity,
-        "impact": category.impact,
-        "domains": domains,
-        "processes": processes,
-        "rules": category.rules.iter().map(|r| {
-            serde_json::json!({
-                "notes": r.notes,
-                "domains": r.domains,
-                "process": r.deny_process
-            })
-        }).collect::<Vec<_>>()
-    });
+    let rules: Vec<RuleDetails> = category
+        .rules
+        .iter()
+        .map(|r| RuleDetails {
+            notes: &r.notes,
+            domains: r.domains.iter().map(|d| d.as_str()).collect(),
+            process: r.deny_process.as_deref(),
+        })
+        .collect();
 
-    serde_json::to_string_pretty(&result)
-        .map_err(|e| JsError::new(&format!("JSON serialization error: {}", e)))
+    to_js(&CategoryDetails {
+        slug,
+        name: &category.name,
+        description: &category.description,
+        severity: category.severity,
+        impact: &category.impact,
+        domains: rules.iter().flat_map(|r| r.domains.iter().copied()).collect(),
+        processes: rules.iter().filter_map(|r| r.process).collect(),
+        rules,
+    })
 }